@@ -1,9 +1,10 @@
 //! # Affine Space
-//! In this module an affine space is defined.
+//! In this module an affine space in one, two or three dimensions is defined.
 //!
-//! All objects use rational coefficients.
-//! A Pos3 is different from a Vec3 in that the translation part of an affine transformation aren't
-//! applied to a Vec3.
+//! All objects use rational coefficients and are generic over the dimension N, the aliases Vec3,
+//! Pos3, Mat3, Affine3 and Bounds3 are the three dimensional types.
+//! A Position is different from a Vector in that the translation part of an affine transformation
+//! isn't applied to a Vector.
 //!
 //! ## on the implementation of Rem (%)
 //!
@@ -14,7 +15,7 @@
 //! 0 <= x < a
 //! 0 <= y < b
 //! 0 <= z < c
-//! This is what the implementation for Pos3 % Vec3 does.
+//! This is what the implementation for Pos3 % Bounds3 does.
 //!
 //! For Vec3 however they should be represented as the shortest equivalent translation. As such the
 //! coefficients (x, y, z) are brought into the range
@@ -23,6 +24,8 @@
 //! -b/2 < y <= b/2
 //! -c/2 < z <= c/2
 //!
+//! The same holds coordinatewise in one and two dimensions.
+//!
 //! Furthermore, note that implemntation of Ord on structs do not provide mathematical information
 //! about the struct, rather provide a unique way to sort a Vec<T> of the given struct.
 
@@ -32,81 +35,124 @@ use std::{
     str::FromStr,
 };
 
-use nalgebra::{Matrix3, Point3, Vector3};
+use nalgebra::{Matrix3, Point, SMatrix, SVector, Vector3};
 use pest::iterators::Pair;
 
-use crate::{copy_mul_impl, Frac, Rule};
+use crate::{
+    copy_mul_impl,
+    frac::{gcd, lcm},
+    Frac, Rule,
+};
 
 /// A vector type using rational indexes
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Vec3([Frac; 3]);
+pub struct Vector<const N: usize>([Frac; N]);
 
-impl Vec3 {
-    /// constructor
-    pub fn new(x: Frac, y: Frac, z: Frac) -> Self {
-        Self([x, y, z])
-    }
+/// a vector in three dimensions
+pub type Vec3 = Vector<3>;
 
+impl<const N: usize> Vector<N> {
     /// returns a vector with all values set to `val`
     pub fn splat(val: Frac) -> Self {
-        Self([val, val, val])
+        Self([val; N])
     }
 
     /// returns the zero vector
     pub fn zero() -> Self {
-        Self([0.into(); 3])
+        Self([0.into(); N])
+    }
+
+    /// returns the components
+    pub fn components(&self) -> [Frac; N] {
+        self.0
     }
 
+    /// reads a vector with N coefficients
     pub(crate) fn from_parser_vector(pair: Pair<Rule>) -> Self {
-        debug_assert_eq!(pair.as_rule(), Rule::vector);
-        let mut vec: [Frac; 3] = Default::default();
+        debug_assert!(matches!(pair.as_rule(), Rule::vector | Rule::low_vector));
+        let mut vec = [Frac::from(0); N];
         for (i, t) in pair.into_inner().enumerate() {
-            debug_assert_eq!(t.as_rule(), Rule::coefficient);
-            let mut active_minus = false;
-            let mut iter = t.into_inner();
-            while let Some(p) = iter.next() {
-                if p.as_rule() == Rule::sign {
-                    if p.as_str() == "-" {
-                        active_minus = true;
-                    }
-                } else if p.as_rule() == Rule::p_rational_num {
-                    let mut num = Frac::from_str(p.as_str()).expect("enforced by grammar");
-                    if active_minus {
-                        num = -num;
-                    }
-                    vec[i] = num;
-                } else {
-                    unreachable!()
-                }
+            vec[i] = coefficient_from_parser(t);
+        }
+        Self(vec)
+    }
+}
+
+impl Vec3 {
+    /// constructor
+    pub fn new(x: Frac, y: Frac, z: Frac) -> Self {
+        Self([x, y, z])
+    }
+
+    /// returns the x component
+    pub fn x(&self) -> Frac {
+        self.0[0]
+    }
+
+    /// returns the y component
+    pub fn y(&self) -> Frac {
+        self.0[1]
+    }
+
+    /// returns the z component
+    pub fn z(&self) -> Frac {
+        self.0[2]
+    }
+}
+
+/// reads a parsed rational coefficient
+fn coefficient_from_parser(pair: Pair<Rule>) -> Frac {
+    debug_assert_eq!(pair.as_rule(), Rule::coefficient);
+    let mut active_minus = false;
+    let mut num = Frac::from(0);
+    for p in pair.into_inner() {
+        if p.as_rule() == Rule::sign {
+            if p.as_str() == "-" {
+                active_minus = true;
+            }
+        } else if p.as_rule() == Rule::p_rational_num {
+            num = Frac::from_str(p.as_str().trim()).expect("enforced by grammar");
+            if active_minus {
+                num = -num;
             }
+        } else {
+            unreachable!()
         }
-        vec.into()
     }
+    num
+}
+
+/// writes the components as [a, b, c]
+fn components_string(components: &[Frac]) -> String {
+    let components: Vec<String> = components.iter().map(Frac::to_string).collect();
+    format!("[{}]", components.join(", "))
 }
 
-impl Display for Vec3 {
+impl<const N: usize> Display for Vector<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[{}, {}, {}]", self.0[0], self.0[1], self.0[2])
+        write!(f, "{}", components_string(&self.0))
     }
 }
 
-impl<T: Into<Frac>> From<[T; 3]> for Vec3 {
-    fn from(value: [T; 3]) -> Self {
-        let mut arr: [Frac; 3] = Default::default();
-        for (i, val) in value.into_iter().enumerate() {
-            arr[i] = val.into();
-        }
-        Self(arr)
+impl<T: Into<Frac>, const N: usize> From<[T; N]> for Vector<N> {
+    fn from(value: [T; N]) -> Self {
+        Self(value.map(Into::into))
     }
 }
 
-impl Into<Vector3<f32>> for Vec3 {
-    fn into(self) -> Vector3<f32> {
-        [self.0[0].into(), self.0[1].into(), self.0[2].into()].into()
+impl<const N: usize> From<Vector<N>> for SVector<f32, N> {
+    fn from(val: Vector<N>) -> Self {
+        SVector::from_fn(|i, _| val.0[i].into())
     }
 }
 
-impl Vec3 {
+impl<const N: usize> From<Vector<N>> for SVector<f64, N> {
+    fn from(val: Vector<N>) -> Self {
+        SVector::from_fn(|i, _| val.0[i].into())
+    }
+}
+
+impl<const N: usize> Vector<N> {
     /// The dot product
     pub fn dot(&self, other: &Self) -> Frac {
         self.0
@@ -117,7 +163,7 @@ impl Vec3 {
 
     /// returns the square of the norm of the vector
     pub fn norm_sq(&self) -> Frac {
-        self.dot(&self)
+        self.dot(self)
     }
 
     /// returns the norm of the vector as an f32
@@ -127,44 +173,44 @@ impl Vec3 {
     }
 }
 
-impl Add for Vec3 {
-    type Output = Vec3;
+impl<const N: usize> Add for Vector<N> {
+    type Output = Self;
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Self([
-            self.0[0] + rhs.0[0],
-            self.0[1] + rhs.0[1],
-            self.0[2] + rhs.0[2],
-        ])
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self.0
+            .iter_mut()
+            .zip(rhs.0.iter())
+            .for_each(|(a, b)| *a += *b);
+        self
     }
 }
 
-impl AddAssign for Vec3 {
+impl<const N: usize> AddAssign for Vector<N> {
     fn add_assign(&mut self, rhs: Self) {
         *self = *self + rhs
     }
 }
 
-impl Sub for Vec3 {
+impl<const N: usize> Sub for Vector<N> {
     type Output = Self;
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self([
-            self.0[0] - rhs.0[0],
-            self.0[1] - rhs.0[1],
-            self.0[2] - rhs.0[2],
-        ])
+    fn sub(mut self, rhs: Self) -> Self::Output {
+        self.0
+            .iter_mut()
+            .zip(rhs.0.iter())
+            .for_each(|(a, b)| *a -= *b);
+        self
     }
 }
 
-impl SubAssign for Vec3 {
+impl<const N: usize> SubAssign for Vector<N> {
     fn sub_assign(&mut self, rhs: Self) {
         *self = *self - rhs
     }
 }
 
-impl Mul<Frac> for Vec3 {
-    type Output = Vec3;
+impl<const N: usize> Mul<Frac> for Vector<N> {
+    type Output = Self;
 
     fn mul(mut self, rhs: Frac) -> Self::Output {
         self.0.iter_mut().for_each(|val| *val *= rhs);
@@ -172,44 +218,45 @@ impl Mul<Frac> for Vec3 {
     }
 }
 
-impl Mul<Vec3> for Frac {
-    type Output = Vec3;
+impl<const N: usize> Mul<Vector<N>> for Frac {
+    type Output = Vector<N>;
 
-    fn mul(self, mut rhs: Vec3) -> Self::Output {
+    fn mul(self, mut rhs: Vector<N>) -> Self::Output {
         rhs.0.iter_mut().for_each(|val| *val *= self);
         rhs
     }
 }
 
-impl Div<Frac> for Vec3 {
-    type Output = Vec3;
+impl<const N: usize> Div<Frac> for Vector<N> {
+    type Output = Self;
 
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn div(self, rhs: Frac) -> Self::Output {
         self * rhs.reciprocal()
     }
 }
 
-impl Div<&Frac> for Vec3 {
-    type Output = Vec3;
+impl<const N: usize> Div<&Frac> for Vector<N> {
+    type Output = Self;
 
     fn div(self, rhs: &Frac) -> Self::Output {
         self / *rhs
     }
 }
 
-impl DivAssign<Frac> for Vec3 {
+impl<const N: usize> DivAssign<Frac> for Vector<N> {
     fn div_assign(&mut self, rhs: Frac) {
         *self = *self / rhs
     }
 }
 
-impl DivAssign<&Frac> for Vec3 {
+impl<const N: usize> DivAssign<&Frac> for Vector<N> {
     fn div_assign(&mut self, rhs: &Frac) {
         *self = *self / rhs
     }
 }
-impl Neg for Vec3 {
-    type Output = Vec3;
+impl<const N: usize> Neg for Vector<N> {
+    type Output = Self;
 
     fn neg(mut self) -> Self::Output {
         self.0.iter_mut().for_each(|val| *val = -*val);
@@ -217,18 +264,18 @@ impl Neg for Vec3 {
     }
 }
 
-impl Neg for &Vec3 {
-    type Output = Vec3;
+impl<const N: usize> Neg for &Vector<N> {
+    type Output = Vector<N>;
 
     fn neg(self) -> Self::Output {
         -*self
     }
 }
 
-impl Rem<Bounds3> for Vec3 {
-    type Output = Vec3;
+impl<const N: usize> Rem<Bounds<N>> for Vector<N> {
+    type Output = Self;
 
-    fn rem(mut self, rhs: Bounds3) -> Self::Output {
+    fn rem(mut self, rhs: Bounds<N>) -> Self::Output {
         self.0.iter_mut().zip(rhs.0.iter()).for_each(|(a, b)| {
             let b = Frac::new(*b, 1);
             *a %= b;
@@ -240,29 +287,49 @@ impl Rem<Bounds3> for Vec3 {
     }
 }
 
-impl Rem<&Bounds3> for Vec3 {
-    type Output = Vec3;
+impl<const N: usize> Rem<&Bounds<N>> for Vector<N> {
+    type Output = Self;
 
-    fn rem(self, rhs: &Bounds3) -> Self::Output {
+    fn rem(self, rhs: &Bounds<N>) -> Self::Output {
         self % *rhs
     }
 }
 
-impl RemAssign<Bounds3> for Vec3 {
-    fn rem_assign(&mut self, rhs: Bounds3) {
+impl<const N: usize> RemAssign<Bounds<N>> for Vector<N> {
+    fn rem_assign(&mut self, rhs: Bounds<N>) {
         *self = *self % rhs;
     }
 }
 
-impl RemAssign<&Bounds3> for Vec3 {
-    fn rem_assign(&mut self, rhs: &Bounds3) {
+impl<const N: usize> RemAssign<&Bounds<N>> for Vector<N> {
+    fn rem_assign(&mut self, rhs: &Bounds<N>) {
         *self = *self % *rhs
     }
 }
 
 /// A position type using rational values
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Pos3([Frac; 3]);
+pub struct Position<const N: usize>([Frac; N]);
+
+/// a position in three dimensions
+pub type Pos3 = Position<3>;
+
+impl<const N: usize> Position<N> {
+    /// returns a position where all components are set to `val`
+    pub fn splat(val: Frac) -> Self {
+        Self([val; N])
+    }
+
+    /// returns the origin (0, 0, 0)
+    pub fn origin() -> Self {
+        Self([0_u16.into(); N])
+    }
+
+    /// returns the coordinates
+    pub fn coordinates(&self) -> [Frac; N] {
+        self.0
+    }
+}
 
 impl Pos3 {
     /// constructor
@@ -270,34 +337,31 @@ impl Pos3 {
         Self([x, y, z])
     }
 
-    /// returns a position where all components are set to `val`
-    pub fn splat(val: Frac) -> Self {
-        Self([val, val, val])
+    /// returns the x coordinate
+    pub fn x(&self) -> Frac {
+        self.0[0]
     }
 
-    /// returns the origin (0, 0, 0)
-    pub fn origin() -> Self {
-        Self([0_u16.into(); 3])
+    /// returns the y coordinate
+    pub fn y(&self) -> Frac {
+        self.0[1]
     }
 
-    pub(crate) fn from_parser_vector(pair: Pair<Rule>) -> Self {
-        Vec3::from_parser_vector(pair).into()
+    /// returns the z coordinate
+    pub fn z(&self) -> Frac {
+        self.0[2]
     }
 }
 
-impl Display for Pos3 {
+impl<const N: usize> Display for Position<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[{}, {}, {}]", self.0[0], self.0[1], self.0[2])
+        write!(f, "{}", components_string(&self.0))
     }
 }
 
-impl<T: Into<Frac>> From<[T; 3]> for Pos3 {
-    fn from(value: [T; 3]) -> Self {
-        let mut arr: [Frac; 3] = Default::default();
-        for (i, val) in value.into_iter().enumerate() {
-            arr[i] = val.into();
-        }
-        Self(arr)
+impl<T: Into<Frac>, const N: usize> From<[T; N]> for Position<N> {
+    fn from(value: [T; N]) -> Self {
+        Self(value.map(Into::into))
     }
 }
 
@@ -307,50 +371,44 @@ impl From<(Frac, Frac, Frac)> for Pos3 {
     }
 }
 
-impl From<Vec3> for Pos3 {
-    fn from(value: Vec3) -> Self {
+impl<const N: usize> From<Vector<N>> for Position<N> {
+    fn from(value: Vector<N>) -> Self {
         Self(value.0)
     }
 }
 
-impl From<Pos3> for Vec3 {
-    fn from(value: Pos3) -> Self {
+impl<const N: usize> From<Position<N>> for Vector<N> {
+    fn from(value: Position<N>) -> Self {
         Self(value.0)
     }
 }
 
-impl Into<Point3<f32>> for Pos3 {
-    fn into(self) -> Point3<f32> {
-        [self.0[0].into(), self.0[1].into(), self.0[2].into()].into()
+impl<const N: usize> From<Position<N>> for Point<f32, N> {
+    fn from(val: Position<N>) -> Self {
+        SVector::<f32, N>::from(Vector::from(val)).into()
     }
 }
 
-impl Sub for Pos3 {
-    type Output = Vec3;
+impl<const N: usize> Sub for Position<N> {
+    type Output = Vector<N>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        let mut arr = self.0;
-        arr.iter_mut().zip(rhs.0.iter()).for_each(|(l, r)| *l -= *r);
-        Vec3(arr)
+        Vector(self.0) - Vector(rhs.0)
     }
 }
 
-impl Add<Pos3> for Vec3 {
-    type Output = Pos3;
+impl<const N: usize> Add<Position<N>> for Vector<N> {
+    type Output = Position<N>;
 
-    fn add(self, mut rhs: Pos3) -> Self::Output {
-        rhs.0
-            .iter_mut()
-            .zip(self.0.iter())
-            .for_each(|(a, b)| *a += *b);
-        rhs
+    fn add(self, rhs: Position<N>) -> Self::Output {
+        rhs + self
     }
 }
 
-impl Add<Vec3> for Pos3 {
-    type Output = Pos3;
+impl<const N: usize> Add<Vector<N>> for Position<N> {
+    type Output = Self;
 
-    fn add(mut self, rhs: Vec3) -> Self::Output {
+    fn add(mut self, rhs: Vector<N>) -> Self::Output {
         self.0
             .iter_mut()
             .zip(rhs.0.iter())
@@ -359,16 +417,16 @@ impl Add<Vec3> for Pos3 {
     }
 }
 
-impl AddAssign<Vec3> for Pos3 {
-    fn add_assign(&mut self, rhs: Vec3) {
+impl<const N: usize> AddAssign<Vector<N>> for Position<N> {
+    fn add_assign(&mut self, rhs: Vector<N>) {
         *self = *self + rhs
     }
 }
 
-impl Rem<Bounds3> for Pos3 {
-    type Output = Pos3;
+impl<const N: usize> Rem<Bounds<N>> for Position<N> {
+    type Output = Self;
 
-    fn rem(mut self, rhs: Bounds3) -> Self::Output {
+    fn rem(mut self, rhs: Bounds<N>) -> Self::Output {
         self.0
             .iter_mut()
             .zip(rhs.0.iter())
@@ -377,103 +435,223 @@ impl Rem<Bounds3> for Pos3 {
     }
 }
 
-impl Rem<&Bounds3> for Pos3 {
-    type Output = Pos3;
+impl<const N: usize> Rem<&Bounds<N>> for Position<N> {
+    type Output = Self;
 
-    fn rem(self, rhs: &Bounds3) -> Self::Output {
+    fn rem(self, rhs: &Bounds<N>) -> Self::Output {
         self % *rhs
     }
 }
 
-impl RemAssign<Bounds3> for Pos3 {
-    fn rem_assign(&mut self, rhs: Bounds3) {
+impl<const N: usize> RemAssign<Bounds<N>> for Position<N> {
+    fn rem_assign(&mut self, rhs: Bounds<N>) {
         *self = *self % rhs
     }
 }
 
-impl RemAssign<&Bounds3> for Pos3 {
-    fn rem_assign(&mut self, rhs: &Bounds3) {
+impl<const N: usize> RemAssign<&Bounds<N>> for Position<N> {
+    fn rem_assign(&mut self, rhs: &Bounds<N>) {
         *self = *self % *rhs
     }
 }
 
-// [x x x
-//  x x x
-//  x x x]
-// represented like this
-/// A 3x3 Matrix using rational components
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Mat3([Frac; 9]);
+/// A reciprocal lattice vector (h, k, l) using rational indexes
+/// the scalar product with a Vec3 is given by h x + k y + l z
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RecVec3([Frac; 3]);
 
-impl Mat3 {
-    /// creates a matrix from three column vectors
-    pub fn from_columns(v1: Vec3, v2: Vec3, v3: Vec3) -> Self {
-        let mut array: [Frac; 9] = Default::default();
-        for i in 0..3 {
-            array[3 * i] = v1.0[i];
-            array[3 * i + 1] = v2.0[i];
-            array[3 * i + 2] = v3.0[i];
+impl RecVec3 {
+    /// constructor
+    pub fn new(h: Frac, k: Frac, l: Frac) -> Self {
+        Self([h, k, l])
+    }
+
+    /// returns the zero vector
+    pub fn zero() -> Self {
+        Self([0.into(); 3])
+    }
+
+    /// returns the h index
+    pub fn h(&self) -> Frac {
+        self.0[0]
+    }
+
+    /// returns the k index
+    pub fn k(&self) -> Frac {
+        self.0[1]
+    }
+
+    /// returns the l index
+    pub fn l(&self) -> Frac {
+        self.0[2]
+    }
+
+    /// the scalar product with a vector in direct space
+    pub fn dot(&self, vec: &Vec3) -> Frac {
+        self.0
+            .iter()
+            .zip(vec.0.iter())
+            .fold(0.into(), |acc, (a, b)| acc + *a * *b)
+    }
+}
+
+impl Display for RecVec3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {}, {})", self.0[0], self.0[1], self.0[2])
+    }
+}
+
+impl<T: Into<Frac>> From<[T; 3]> for RecVec3 {
+    fn from(value: [T; 3]) -> Self {
+        let mut arr: [Frac; 3] = Default::default();
+        for (i, val) in value.into_iter().enumerate() {
+            arr[i] = val.into();
         }
-        Self(array)
+        Self(arr)
     }
+}
+
+impl From<RecVec3> for Vector3<f64> {
+    fn from(val: RecVec3) -> Self {
+        [val.0[0].into(), val.0[1].into(), val.0[2].into()].into()
+    }
+}
+
+impl Add for RecVec3 {
+    type Output = RecVec3;
 
+    fn add(self, rhs: Self) -> Self::Output {
+        Self([
+            self.0[0] + rhs.0[0],
+            self.0[1] + rhs.0[1],
+            self.0[2] + rhs.0[2],
+        ])
+    }
+}
+
+impl Sub for RecVec3 {
+    type Output = RecVec3;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self([
+            self.0[0] - rhs.0[0],
+            self.0[1] - rhs.0[1],
+            self.0[2] - rhs.0[2],
+        ])
+    }
+}
+
+impl Neg for RecVec3 {
+    type Output = RecVec3;
+
+    fn neg(mut self) -> Self::Output {
+        self.0.iter_mut().for_each(|val| *val = -*val);
+        self
+    }
+}
+
+/// A NxN Matrix using rational components, stored row by row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Matrix<const N: usize>([[Frac; N]; N]);
+
+/// a 3x3 matrix
+pub type Mat3 = Matrix<3>;
+
+impl<const N: usize> Matrix<N> {
     /// returns the identity matrix
-    #[rustfmt::skip]
     pub fn identity() -> Self {
-        let i: Frac = 1.into();
-        let o: Frac = 0.into();
-        Self([i, o, o,
-              o, i, o,
-              o, o, i])
+        let mut rows = [[Frac::from(0); N]; N];
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[i] = 1.into();
+        }
+        Self(rows)
     }
-}
 
-impl Display for Mat3 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let as_affine: Affine3 = (*self).into();
-        Display::fmt(&as_affine, f)
+    /// returns the entry in row i and column j
+    pub fn entry(&self, i: usize, j: usize) -> Frac {
+        self.0[i][j]
+    }
+
+    /// returns the column j
+    pub fn column(&self, j: usize) -> Vector<N> {
+        Vector(self.0.map(|row| row[j]))
     }
 }
 
 impl Mat3 {
-    /// calculates the determinant of the matrix
-    #[rustfmt::skip]
-    pub fn determinant(&self) -> Frac {
-        let [a, b, c,
-             d, e, f,
-             g, h, i] = self.0;
-
-        a * (e * i - f * h) - b * (d * i - f * g) + c * (d * h - e * g)
+    /// creates a matrix from three column vectors
+    pub fn from_columns(v1: Vec3, v2: Vec3, v3: Vec3) -> Self {
+        Self(std::array::from_fn(|i| [v1.0[i], v2.0[i], v3.0[i]]))
     }
+}
 
-    /// returns the cofactor matrix
-    #[rustfmt::skip]
-    pub fn cofactor(&self) -> Self {
-        let [a, b, c,
-             d, e, f,
-             g, h, i] = self.0;
-        Self([e*i - f*h, f*g - d*i, d*h - e*g,
-              h*c - i*b, a*i - c*g, b*g - a*h,
-              b*f - c*e, c*d - a*f, a*e - b*d])
+impl<const N: usize> Display for Matrix<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let as_affine: Affine<N> = (*self).into();
+        Display::fmt(&as_affine, f)
     }
+}
 
-    /// returns the adjoint of the matrix
-    pub fn adjoint(&self) -> Self {
-        self.cofactor().transpose()
+impl<const N: usize> Matrix<N> {
+    /// calculates the determinant of the matrix
+    pub fn determinant(&self) -> Frac {
+        let mut rows = self.0;
+        let mut det = Frac::from(1);
+        for col in 0..N {
+            let Some(i) = (col..N).find(|&i| rows[i][col] != 0.into()) else {
+                return 0.into();
+            };
+            if i != col {
+                rows.swap(col, i);
+                det = -det;
+            }
+            let pivot = rows[col];
+            det *= pivot[col];
+            for row in rows.iter_mut().skip(col + 1) {
+                let factor = row[col] / pivot[col];
+                row.iter_mut()
+                    .zip(pivot.iter())
+                    .for_each(|(val, piv)| *val -= factor * *piv);
+            }
+        }
+        det
     }
 
     /// returns the inverse of a matrix if it exists
     pub fn inverse(&self) -> Option<Self> {
-        let det = self.determinant();
-        if det == 0.into() {
-            return None;
+        let mut rows = self.0;
+        let mut inverse = Self::identity().0;
+        for col in 0..N {
+            let i = (col..N).find(|&i| rows[i][col] != 0.into())?;
+            rows.swap(col, i);
+            inverse.swap(col, i);
+            let pivot = rows[col][col];
+            rows[col].iter_mut().for_each(|val| *val /= pivot);
+            inverse[col].iter_mut().for_each(|val| *val /= pivot);
+            for i in (0..N).filter(|&i| i != col) {
+                let factor = rows[i][col];
+                let (reduced, reduced_inverse) = (rows[col], inverse[col]);
+                rows[i]
+                    .iter_mut()
+                    .zip(reduced.iter())
+                    .for_each(|(val, red)| *val -= factor * *red);
+                inverse[i]
+                    .iter_mut()
+                    .zip(reduced_inverse.iter())
+                    .for_each(|(val, red)| *val -= factor * *red);
+            }
         }
-        Some((Frac::new(1, 1) / det) * self.adjoint())
+        Some(Self(inverse))
     }
 
     /// retruns true if the matrix is invertible
     pub fn is_invertible(&self) -> bool {
-        !(self.determinant() == 0.into())
+        self.determinant() != 0.into()
+    }
+
+    /// returns the trace of the matrix
+    pub fn trace(&self) -> Frac {
+        (0..N).fold(0.into(), |acc, i| acc + self.0[i][i])
     }
 
     /// returns true if the matrix is orthogonal
@@ -482,48 +660,93 @@ impl Mat3 {
     }
 
     /// returns the transpose of the matrix
-    #[rustfmt::skip]
     pub fn transpose(&self) -> Self {
-        let [a, b, c,
-             d, e, f,
-             g, h, i] = self.0;
-        Self([a, d, g,
-              b, e, h,
-              c, f, i])
+        Self(std::array::from_fn(|i| self.0.map(|row| row[i])))
+    }
+}
+
+impl Mat3 {
+    /// returns the cofactor matrix
+    #[rustfmt::skip]
+    pub fn cofactor(&self) -> Self {
+        let [[a, b, c],
+             [d, e, f],
+             [g, h, i]] = self.0;
+        Self([[e*i - f*h, f*g - d*i, d*h - e*g],
+              [h*c - i*b, a*i - c*g, b*g - a*h],
+              [b*f - c*e, c*d - a*f, a*e - b*d]])
+    }
+
+    /// returns the adjoint of the matrix
+    pub fn adjoint(&self) -> Self {
+        self.cofactor().transpose()
     }
 }
 
 impl<T: Into<Frac>> From<[T; 9]> for Mat3 {
     fn from(value: [T; 9]) -> Self {
-        let mut arr: [Frac; 9] = Default::default();
+        let mut arr = [[Frac::from(0); 3]; 3];
         for (i, val) in value.into_iter().enumerate() {
-            arr[i] = val.into();
+            arr[i / 3][i % 3] = val.into();
         }
         Self(arr)
     }
 }
 
-impl Into<Matrix3<f32>> for Mat3 {
-    fn into(self) -> Matrix3<f32> {
-        [
-            [self.0[0].into(), self.0[3].into(), self.0[6].into()],
-            [self.0[1].into(), self.0[4].into(), self.0[7].into()],
-            [self.0[2].into(), self.0[5].into(), self.0[8].into()],
-        ]
-        .into()
+impl<T: Into<Frac>, const N: usize> From<[[T; N]; N]> for Matrix<N> {
+    fn from(value: [[T; N]; N]) -> Self {
+        Self(value.map(|row| row.map(Into::into)))
+    }
+}
+
+impl<const N: usize> From<Matrix<N>> for SMatrix<f32, N, N> {
+    fn from(val: Matrix<N>) -> Self {
+        SMatrix::from_fn(|i, j| val.0[i][j].into())
+    }
+}
+
+impl<const N: usize> From<Matrix<N>> for SMatrix<f64, N, N> {
+    fn from(val: Matrix<N>) -> Self {
+        SMatrix::from_fn(|i, j| val.0[i][j].into())
     }
 }
 
-impl Mul for Mat3 {
+impl<const N: usize> Add for Matrix<N> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self.0
+            .iter_mut()
+            .flatten()
+            .zip(rhs.0.iter().flatten())
+            .for_each(|(a, b)| *a += *b);
+        self
+    }
+}
+
+impl<const N: usize> Sub for Matrix<N> {
+    type Output = Self;
+
+    fn sub(mut self, rhs: Self) -> Self::Output {
+        self.0
+            .iter_mut()
+            .flatten()
+            .zip(rhs.0.iter().flatten())
+            .for_each(|(a, b)| *a -= *b);
+        self
+    }
+}
+
+impl<const N: usize> Mul for Matrix<N> {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        let mut result = [Frac::new(0, 1); 9];
+        let mut result = [[Frac::new(0, 1); N]; N];
 
-        for i in 0..3 {
-            for j in 0..3 {
-                for k in 0..3 {
-                    result[i * 3 + j] += self.0[i * 3 + k] * rhs.0[k * 3 + j];
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, val) in row.iter_mut().enumerate() {
+                for k in 0..N {
+                    *val += self.0[i][k] * rhs.0[k][j];
                 }
             }
         }
@@ -532,85 +755,274 @@ impl Mul for Mat3 {
     }
 }
 
-copy_mul_impl!(Mat3, Mat3);
+impl<const N: usize> Mul<&Matrix<N>> for Matrix<N> {
+    type Output = Self;
+
+    fn mul(self, rhs: &Matrix<N>) -> Self::Output {
+        self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Matrix<N>> for &Matrix<N> {
+    type Output = Matrix<N>;
+
+    fn mul(self, rhs: Matrix<N>) -> Self::Output {
+        *self * rhs
+    }
+}
 
-impl Mul<Frac> for Mat3 {
-    type Output = Mat3;
+impl<const N: usize> Mul<&Matrix<N>> for &Matrix<N> {
+    type Output = Matrix<N>;
+
+    fn mul(self, rhs: &Matrix<N>) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Frac> for Matrix<N> {
+    type Output = Self;
 
     fn mul(mut self, rhs: Frac) -> Self::Output {
-        self.0.iter_mut().for_each(|val| *val *= rhs);
+        self.0.iter_mut().flatten().for_each(|val| *val *= rhs);
         self
     }
 }
 
-copy_mul_impl!(Mat3, Frac);
+impl<const N: usize> Mul<Matrix<N>> for Frac {
+    type Output = Matrix<N>;
 
-impl Mul<Mat3> for Frac {
-    type Output = Mat3;
-
-    fn mul(self, rhs: Mat3) -> Self::Output {
+    fn mul(self, rhs: Matrix<N>) -> Self::Output {
         rhs * self
     }
 }
 
-copy_mul_impl!(Frac, Mat3);
+impl<const N: usize> Mul<Vector<N>> for Matrix<N> {
+    type Output = Vector<N>;
 
-impl Mul<Vec3> for Mat3 {
-    type Output = Vec3;
+    fn mul(self, rhs: Vector<N>) -> Self::Output {
+        Vector(self.0.map(|row| Vector(row).dot(&rhs)))
+    }
+}
 
-    #[rustfmt::skip]
-    fn mul(self, rhs: Vec3) -> Self::Output {
-        let Vec3([x, y, z]) = rhs;
-        let [a, b, c,
-             d, e, f,
-             g, h, i] = self.0;
-        Vec3([
-            x*a + y*b + z*c,
-            x*d + y*e + z*f,
-            x*g + y*h + z*i,
-        ])
+impl<const N: usize> Mul<&Vector<N>> for Matrix<N> {
+    type Output = Vector<N>;
+
+    fn mul(self, rhs: &Vector<N>) -> Self::Output {
+        self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Vector<N>> for &Matrix<N> {
+    type Output = Vector<N>;
+
+    fn mul(self, rhs: Vector<N>) -> Self::Output {
+        *self * rhs
     }
 }
 
-copy_mul_impl!(Mat3, Vec3);
+impl<const N: usize> Mul<&Vector<N>> for &Matrix<N> {
+    type Output = Vector<N>;
 
-impl Mul<Pos3> for Mat3 {
-    type Output = Pos3;
+    fn mul(self, rhs: &Vector<N>) -> Self::Output {
+        *self * *rhs
+    }
+}
 
-    fn mul(self, rhs: Pos3) -> Self::Output {
-        let as_vec: Vec3 = rhs.into();
+impl<const N: usize> Mul<Position<N>> for Matrix<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: Position<N>) -> Self::Output {
+        let as_vec: Vector<N> = rhs.into();
         (self * as_vec).into()
     }
 }
 
-copy_mul_impl!(Mat3, Pos3);
+impl<const N: usize> Mul<&Position<N>> for Matrix<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: &Position<N>) -> Self::Output {
+        self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Position<N>> for &Matrix<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: Position<N>) -> Self::Output {
+        *self * rhs
+    }
+}
+
+impl<const N: usize> Mul<&Position<N>> for &Matrix<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: &Position<N>) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+/// scales the components to the shortest integer vector in the same direction
+/// the zero vector is returned unchanged
+pub(crate) fn primitive_integer<const N: usize>(arr: [Frac; N]) -> [i32; N] {
+    let denominator = arr
+        .iter()
+        .fold(1, |acc, val| lcm(acc, val.get_denominator()));
+    let numerators = arr.map(|val| (val * Frac::from(denominator)).get_numerator());
+    let divisor = numerators.iter().fold(0, |acc, &val| gcd(acc, val));
+    if divisor == 0 {
+        return numerators;
+    }
+    numerators.map(|val| val / divisor)
+}
+
+/// brings the rows into reduced row echelon form and removes zero rows
+/// the result is a unique basis of the space spanned by the rows
+pub(crate) fn reduced_row_echelon<const N: usize>(mut rows: Vec<[Frac; N]>) -> Vec<[Frac; N]> {
+    let mut pivot_row = 0;
+    for col in 0..N {
+        let Some(i) = (pivot_row..rows.len()).find(|&i| rows[i][col] != 0.into()) else {
+            continue;
+        };
+        rows.swap(pivot_row, i);
+        let pivot = rows[pivot_row][col];
+        rows[pivot_row].iter_mut().for_each(|val| *val /= pivot);
+        for i in 0..rows.len() {
+            if i != pivot_row && rows[i][col] != 0.into() {
+                let factor = rows[i][col];
+                let reduced = rows[pivot_row];
+                rows[i]
+                    .iter_mut()
+                    .zip(reduced.iter())
+                    .for_each(|(val, red)| *val -= factor * *red);
+            }
+        }
+        pivot_row += 1;
+    }
+    rows.truncate(pivot_row);
+    rows
+}
+
+impl<const N: usize> Matrix<N> {
+    /// solves M x = b and returns None if there is no solution.
+    /// otherwise a particular solution and the directions of the solution space are returned.
+    /// each direction is paired with the index of the free coordinate parametrizing it.
+    /// the free coordinates are chosen as early as possible, such that x,x,0 is preferred over
+    /// y,y,0.
+    pub fn solve(&self, rhs: Vector<N>) -> Option<(Vector<N>, Vec<(usize, Vector<N>)>)> {
+        // each row of the augmented system is stored with its right hand side
+        let mut rows: Vec<([Frac; N], Frac)> =
+            self.0.iter().copied().zip(rhs.0.iter().copied()).collect();
+        let mut pivots: Vec<(usize, usize)> = Vec::new();
+        for col in (0..N).rev() {
+            let Some(i) = (pivots.len()..N).find(|&i| rows[i].0[col] != 0.into()) else {
+                continue;
+            };
+            let pivot_row = pivots.len();
+            rows.swap(pivot_row, i);
+            let pivot = rows[pivot_row].0[col];
+            rows[pivot_row].0.iter_mut().for_each(|val| *val /= pivot);
+            rows[pivot_row].1 /= pivot;
+            for i in 0..N {
+                if i != pivot_row && rows[i].0[col] != 0.into() {
+                    let factor = rows[i].0[col];
+                    let (reduced, reduced_rhs) = rows[pivot_row];
+                    rows[i]
+                        .0
+                        .iter_mut()
+                        .zip(reduced.iter())
+                        .for_each(|(val, red)| *val -= factor * *red);
+                    rows[i].1 -= factor * reduced_rhs;
+                }
+            }
+            pivots.push((pivot_row, col));
+        }
+        if rows[pivots.len()..].iter().any(|row| row.1 != 0.into()) {
+            return None;
+        }
+        let mut particular = Vector::zero();
+        for &(row, col) in &pivots {
+            particular.0[col] = rows[row].1;
+        }
+        let mut directions = Vec::new();
+        for free in (0..N).filter(|c| !pivots.iter().any(|&(_, col)| col == *c)) {
+            let mut direction = Vector::zero();
+            direction.0[free] = 1.into();
+            for &(row, col) in &pivots {
+                direction.0[col] = -rows[row].0[free];
+            }
+            directions.push((free, direction));
+        }
+        Some((particular, directions))
+    }
+}
+
+impl Mat3 {
+    /// returns a basis of the reciprocal vectors h with h M = 0 in reduced row echelon form
+    pub fn left_kernel(&self) -> Vec<RecVec3> {
+        // the kernel of M^T is found from the reduced rows of M^T, the free columns parametrize it
+        let reduced = reduced_row_echelon(self.transpose().0.to_vec());
+        let pivots: Vec<usize> = reduced
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .position(|val| *val != 0.into())
+                    .expect("rows are nonzero")
+            })
+            .collect();
+        let mut basis = Vec::new();
+        for free in (0..3).filter(|col| !pivots.contains(col)) {
+            let mut vec: [Frac; 3] = Default::default();
+            vec[free] = 1.into();
+            for (row, &pivot) in reduced.iter().zip(pivots.iter()) {
+                vec[pivot] = -row[free];
+            }
+            basis.push(vec);
+        }
+        reduced_row_echelon(basis)
+            .into_iter()
+            .map(RecVec3)
+            .collect()
+    }
+
+    /// multiplies the reciprocal vector as a row vector from the left, h' = h M
+    /// this is the action of the transpose of the matrix on the reciprocal vector
+    pub fn row_mul(&self, rec: RecVec3) -> RecVec3 {
+        RecVec3((self.transpose() * Vec3::from(rec.0)).0)
+    }
+}
+
+copy_mul_impl!(Mat3, Frac);
+copy_mul_impl!(Frac, Mat3);
 
 /// an affine transformation using rational components
 /// the affine transformation consists of a matrix multiplication and then the addition of a vector
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Affine3 {
-    mat: Mat3,
-    translation: Vec3,
+pub struct Affine<const N: usize> {
+    mat: Matrix<N>,
+    translation: Vector<N>,
 }
 
-impl Affine3 {
+/// an affine transformation in three dimensions
+pub type Affine3 = Affine<3>;
+
+impl<const N: usize> Affine<N> {
     /// constructor for an affine transformation
-    pub fn new(mat: Mat3, translation: Vec3) -> Self {
+    pub fn new(mat: Matrix<N>, translation: Vector<N>) -> Self {
         Self { mat, translation }
     }
 
     /// constructor from a matrix
-    pub fn from_mat(mat: Mat3) -> Self {
+    pub fn from_mat(mat: Matrix<N>) -> Self {
         Self {
             mat,
-            translation: Vec3::zero(),
+            translation: Vector::zero(),
         }
     }
 
     /// constructor for translations
-    pub fn from_translation(translation: Vec3) -> Self {
+    pub fn from_translation(translation: Vector<N>) -> Self {
         Self {
-            mat: Mat3::identity(),
+            mat: Matrix::identity(),
             translation,
         }
     }
@@ -618,42 +1030,28 @@ impl Affine3 {
     /// returns the identity transformation
     pub fn identity() -> Self {
         Self {
-            mat: Mat3::identity(),
-            translation: Vec3::zero(),
+            mat: Matrix::identity(),
+            translation: Vector::zero(),
         }
     }
 
+    /// reads a coordinate triplet, the pair must have N rows using the first N coordinates
     pub(crate) fn from_parser_affine(pair: Pair<Rule>) -> Self {
-        debug_assert_eq!(pair.as_rule(), Rule::affine);
-        let mut mat: [Frac; 9] = Default::default();
-        let mut translation: [Frac; 3] = Default::default();
+        debug_assert!(matches!(pair.as_rule(), Rule::affine | Rule::low_affine));
+        let mut mat = [[Frac::from(0); N]; N];
+        let mut translation = [Frac::from(0); N];
         for (i, p) in pair.into_inner().enumerate() {
             debug_assert_eq!(p.as_rule(), Rule::affine_coefficient);
             let mut active_minus = false;
             for op in p.into_inner() {
                 use Rule::*;
                 match op.as_rule() {
-                    x => {
+                    x | y | z => {
+                        let j = coordinate_index(op.as_rule());
                         if active_minus {
-                            mat[3 * i + 0] = Frac::new(-1, 1)
+                            mat[i][j] = Frac::new(-1, 1)
                         } else {
-                            mat[3 * i + 0] = 1.into()
-                        }
-                        active_minus = false;
-                    }
-                    y => {
-                        if active_minus {
-                            mat[3 * i + 1] = Frac::new(-1, 1)
-                        } else {
-                            mat[3 * i + 1] = 1.into()
-                        }
-                        active_minus = false;
-                    }
-                    z => {
-                        if active_minus {
-                            mat[3 * i + 2] = Frac::new(-1, 1)
-                        } else {
-                            mat[3 * i + 2] = 1.into()
+                            mat[i][j] = 1.into()
                         }
                         active_minus = false;
                     }
@@ -680,58 +1078,89 @@ impl Affine3 {
         }
     }
 
+    /// reads a Nx(N+1) matrix or a (N+1)x(N+1) augmented matrix, the last column is the
+    /// translation
+    pub(crate) fn from_parser_matrix(pair: Pair<Rule>) -> Self {
+        debug_assert_eq!(pair.as_rule(), Rule::matrix);
+        let mut mat = [[Frac::from(0); N]; N];
+        let mut translation = [Frac::from(0); N];
+        for (i, row) in pair
+            .into_inner()
+            .filter(|row| row.as_rule() == Rule::matrix_row)
+            .enumerate()
+        {
+            for (j, coeff) in row.into_inner().enumerate() {
+                let val = coefficient_from_parser(coeff);
+                if j < N {
+                    mat[i][j] = val;
+                } else {
+                    translation[i] = val;
+                }
+            }
+        }
+        Self {
+            mat: mat.into(),
+            translation: translation.into(),
+        }
+    }
+
     /// creates an object from the parsed pair
     pub(crate) fn from_parser(pair: Pair<Rule>) -> Self {
         match pair.as_rule() {
-            Rule::affine => Self::from_parser_affine(pair),
-            Rule::vector => Vec3::from_parser_vector(pair).into(),
+            Rule::affine | Rule::low_affine => Self::from_parser_affine(pair),
+            Rule::matrix => Self::from_parser_matrix(pair),
+            Rule::vector | Rule::low_vector => Vector::from_parser_vector(pair).into(),
             _ => unreachable!("{:?}", pair.as_rule()), // by grammar
         }
     }
 
     /// returns the matrix of the transformation
-    pub fn mat(&self) -> Mat3 {
+    pub fn mat(&self) -> Matrix<N> {
         self.mat
     }
 
     /// returns the translation of the transformation
-    pub fn translation(&self) -> Vec3 {
+    pub fn translation(&self) -> Vector<N> {
         self.translation
     }
 }
 
-impl From<Vec3> for Affine3 {
-    fn from(value: Vec3) -> Self {
-        Affine3 {
-            mat: Mat3::identity(),
-            translation: value,
-        }
+/// returns the index of the coordinate x, y or z
+pub(crate) fn coordinate_index(rule: Rule) -> usize {
+    match rule {
+        Rule::x => 0,
+        Rule::y => 1,
+        Rule::z => 2,
+        _ => unreachable!("{:?}", rule), // by grammar
     }
 }
 
-impl From<Mat3> for Affine3 {
-    fn from(value: Mat3) -> Self {
-        Self {
-            mat: value,
-            translation: Vec3::zero(),
-        }
+impl<const N: usize> From<Vector<N>> for Affine<N> {
+    fn from(value: Vector<N>) -> Self {
+        Self::from_translation(value)
     }
 }
 
-impl Into<nalgebra::Affine3<f32>> for Affine3 {
-    fn into(self) -> nalgebra::Affine3<f32> {
-        let mat: Matrix3<f32> = self.mat.into();
+impl<const N: usize> From<Matrix<N>> for Affine<N> {
+    fn from(value: Matrix<N>) -> Self {
+        Self::from_mat(value)
+    }
+}
+
+impl From<Affine3> for nalgebra::Affine3<f32> {
+    fn from(val: Affine3) -> Self {
+        let mat: Matrix3<f32> = val.mat.into();
         let mut mat = mat
             .insert_fixed_rows::<1>(3, 0.0)
             .insert_fixed_columns::<1>(3, 0.0);
-        let translation: Vector3<f32> = self.translation.into();
+        let translation: Vector3<f32> = val.translation.into();
         let temp = translation.insert_fixed_rows::<1>(3, 1.0);
         mat.set_column(3, &temp);
         nalgebra::Affine3::from_matrix_unchecked(mat)
     }
 }
 
-impl Affine3 {
+impl<const N: usize> Affine<N> {
     /// calculates the determinant of the matrix of the transformation
     pub fn mat_determinant(&self) -> Frac {
         self.mat.determinant()
@@ -754,25 +1183,31 @@ impl Affine3 {
 
 /// A struct used for the remainder implementation
 #[derive(Copy, Clone, Debug)]
-pub struct Bounds3([i32; 3]);
+pub struct Bounds<const N: usize>([i32; N]);
 
-impl Bounds3 {
+/// bounds in three dimensions
+pub type Bounds3 = Bounds<3>;
+
+impl<const N: usize> Bounds<N> {
     /// Creates a bounds struct with all values set to the given value
     pub fn splat(val: i32) -> Self {
         assert!(val > 0, "bounds must be positive");
-        Self([val; 3])
+        Self([val; N])
     }
 
     /// Creates bounds which can include all roatations around the origin of the give vector
-    pub fn include_rotations_of(vec: Vec3) -> Self {
+    pub fn include_rotations_of(vec: Vector<N>) -> Self {
         let max = vec.norm().ceil() as i32 + 1;
-        Self([max; 3])
+        Self([max; N])
     }
 
     /// creates bounds from the given pair
     pub fn from_parser_int_vector(pair: Pair<Rule>) -> Self {
-        debug_assert_eq!(pair.as_rule(), Rule::int_vector);
-        let mut bounds = [0; 3];
+        debug_assert!(matches!(
+            pair.as_rule(),
+            Rule::int_vector | Rule::low_int_vector
+        ));
+        let mut bounds = [1; N];
         for (i, pair) in pair.into_inner().enumerate() {
             bounds[i] = i32::from_str(pair.as_str()).expect("enforced by parser");
         }
@@ -781,9 +1216,39 @@ impl Bounds3 {
 
     /// counts how many unit cells are inculded in the bounds
     pub fn volume(&self) -> i32 {
-        self.0.iter().fold(1, |acc, a| acc * a)
+        self.0.iter().product()
+    }
+
+    /// returns the bound along every axis
+    pub fn lengths(&self) -> [i32; N] {
+        self.0
     }
 
+    /// returns true if the position is in the correct form for these bounds
+    pub fn contains_pos(&self, pos: Position<N>) -> bool {
+        self.0.iter().zip(pos.0).fold(true, |acc, (&b, p)| {
+            acc && (p < b.into()) && (p >= 0.into())
+        })
+    }
+
+    /// returns true if the vector is in the correct form for these bounds
+    pub fn contains_vec(&self, vec: Vector<N>) -> bool {
+        self.0.iter().zip(vec.0).fold(true, |acc, (&b, p)| {
+            acc && (p <= Frac::new(b, 2)) && (p > Frac::new(-b, 2))
+        })
+    }
+
+    /// returns true if all positions in the array are bigger than zero and smaller than the bounds
+    /// at the corresponding index
+    pub fn contains_arr(&self, arr: &[i32; N]) -> bool {
+        self.0
+            .iter()
+            .zip(arr.iter())
+            .fold(true, |acc, (&b, &p)| acc && (p < b) && (p >= 0))
+    }
+}
+
+impl Bounds3 {
     /// returns x bound
     pub fn x(&self) -> i32 {
         self.0[0]
@@ -798,49 +1263,53 @@ impl Bounds3 {
     pub fn z(&self) -> i32 {
         self.0[2]
     }
+}
 
-    /// returns true if the position is in the correct form for these bounds
-    pub fn contains_pos(&self, pos: Pos3) -> bool {
-        self.0
-            .iter()
-            .zip(pos.0.into_iter())
-            .fold(true, |acc, (&b, p)| {
-                acc && (p < b.into()) && (p >= 0.into())
-            })
+impl<T: Into<i32>, const N: usize> From<[T; N]> for Bounds<N> {
+    fn from(value: [T; N]) -> Self {
+        Self(value.map(Into::into))
     }
-
-    /// returns true if the vector is in the correct form for these bounds
-    pub fn contains_vec(&self, vec: Vec3) -> bool {
-        self.0
+}
+impl<const N: usize> Affine<N> {
+    /// returns the augmented matrix with aligned columns, one row per line
+    fn augmented_matrix_string(&self) -> String {
+        let mut rows: Vec<Vec<String>> = (0..N)
+            .map(|i| {
+                let mut row: Vec<String> = self.mat.0[i].iter().map(Frac::to_string).collect();
+                row.push(self.translation.0[i].to_string());
+                row
+            })
+            .collect();
+        let mut last = vec![String::from("0"); N];
+        last.push(String::from("1"));
+        rows.push(last);
+        let widths: Vec<usize> = (0..=N)
+            .map(|j| rows.iter().map(|row| row[j].len()).max().unwrap_or(0))
+            .collect();
+        let lines: Vec<String> = rows
             .iter()
-            .zip(vec.0.into_iter())
-            .fold(true, |acc, (&b, p)| {
-                acc && (p <= Frac::new(b, 2)) && (p > Frac::new(-b, 2))
+            .map(|row| {
+                let cells: Vec<String> = row
+                    .iter()
+                    .zip(widths.iter())
+                    .map(|(cell, &width)| format!("{:>width$}", cell))
+                    .collect();
+                cells.join(" ")
             })
-    }
-
-    /// returns true if all positions in the array are bigger than zero and smaller than the bounds
-    /// at the corresponding index
-    pub fn contains_arr(&self, arr: &[i32; 3]) -> bool {
-        self.0.iter().zip(arr.iter()).fold(true, |acc, (&b, &p)| {
-            acc && (p < b.into()) && (p >= 0.into())
-        })
+            .collect();
+        format!("[{}]", lines.join("\n "))
     }
 }
 
-impl<T: Into<i32>> From<[T; 3]> for Bounds3 {
-    fn from(value: [T; 3]) -> Self {
-        let mut arr: [i32; 3] = Default::default();
-        for (i, val) in value.into_iter().enumerate() {
-            arr[i] = val.into();
-        }
-        Self(arr)
-    }
-}
-impl Display for Affine3 {
+/// writes the coordinate triplet such as -x,y,z+1/2, in lower dimensions only x and y are used.
+/// the alternate flag {:#} writes the augmented matrix instead
+impl<const N: usize> Display for Affine<N> {
     fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if formatter.alternate() {
+            return write!(formatter, "{}", self.augmented_matrix_string());
+        }
         let mut out = String::new();
-        for (row, &translation) in (&self.mat.0).chunks(3).zip(self.translation.0.iter()) {
+        for (row, &translation) in self.mat.0.iter().zip(self.translation.0.iter()) {
             if !out.is_empty() {
                 out.push(',')
             }
@@ -851,19 +1320,15 @@ impl Display for Affine3 {
                 }
             }
             if translation != 0.into() {
-                coeff_op.push_str(&format!("{}", translation.as_string_signed()));
-            }
-            if coeff_op.starts_with("+") {
-                out.push_str(&coeff_op[1..]);
-            } else {
-                out.push_str(&coeff_op);
+                coeff_op.push_str(&translation.as_string_signed());
             }
+            out.push_str(coeff_op.strip_prefix('+').unwrap_or(&coeff_op));
         }
         write!(formatter, "{}", out)
     }
 }
 
-impl Mul for Affine3 {
+impl<const N: usize> Mul for Affine<N> {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
@@ -874,69 +1339,135 @@ impl Mul for Affine3 {
     }
 }
 
-copy_mul_impl!(Affine3, Affine3);
+impl<const N: usize> Mul<&Affine<N>> for Affine<N> {
+    type Output = Self;
 
-impl Mul<Vec3> for Affine3 {
-    type Output = Vec3;
+    fn mul(self, rhs: &Affine<N>) -> Self::Output {
+        self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Affine<N>> for &Affine<N> {
+    type Output = Affine<N>;
 
-    fn mul(self, rhs: Vec3) -> Self::Output {
+    fn mul(self, rhs: Affine<N>) -> Self::Output {
+        *self * rhs
+    }
+}
+
+impl<const N: usize> Mul<&Affine<N>> for &Affine<N> {
+    type Output = Affine<N>;
+
+    fn mul(self, rhs: &Affine<N>) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Vector<N>> for Affine<N> {
+    type Output = Vector<N>;
+
+    fn mul(self, rhs: Vector<N>) -> Self::Output {
         self.mat * rhs
     }
 }
 
-copy_mul_impl!(Affine3, Vec3);
+impl<const N: usize> Mul<&Vector<N>> for Affine<N> {
+    type Output = Vector<N>;
+
+    fn mul(self, rhs: &Vector<N>) -> Self::Output {
+        self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Vector<N>> for &Affine<N> {
+    type Output = Vector<N>;
+
+    fn mul(self, rhs: Vector<N>) -> Self::Output {
+        *self * rhs
+    }
+}
+
+impl<const N: usize> Mul<&Vector<N>> for &Affine<N> {
+    type Output = Vector<N>;
 
-impl Mul<Pos3> for Affine3 {
-    type Output = Pos3;
+    fn mul(self, rhs: &Vector<N>) -> Self::Output {
+        *self * *rhs
+    }
+}
 
-    fn mul(self, rhs: Pos3) -> Self::Output {
+impl<const N: usize> Mul<Position<N>> for Affine<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: Position<N>) -> Self::Output {
         self.mat * rhs + self.translation
     }
 }
 
-copy_mul_impl!(Affine3, Pos3);
+impl<const N: usize> Mul<&Position<N>> for Affine<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: &Position<N>) -> Self::Output {
+        self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Position<N>> for &Affine<N> {
+    type Output = Position<N>;
 
-impl Rem<Bounds3> for Affine3 {
-    type Output = Affine3;
+    fn mul(self, rhs: Position<N>) -> Self::Output {
+        *self * rhs
+    }
+}
 
-    fn rem(mut self, rhs: Bounds3) -> Self::Output {
+impl<const N: usize> Mul<&Position<N>> for &Affine<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: &Position<N>) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+impl<const N: usize> Rem<Bounds<N>> for Affine<N> {
+    type Output = Self;
+
+    fn rem(mut self, rhs: Bounds<N>) -> Self::Output {
         self.translation %= rhs;
         self
     }
 }
 
-impl Rem<&Bounds3> for Affine3 {
-    type Output = Affine3;
+impl<const N: usize> Rem<&Bounds<N>> for Affine<N> {
+    type Output = Self;
 
-    fn rem(self, rhs: &Bounds3) -> Self::Output {
+    fn rem(self, rhs: &Bounds<N>) -> Self::Output {
         self % *rhs
     }
 }
 
-impl Rem<Bounds3> for &Affine3 {
-    type Output = Affine3;
+impl<const N: usize> Rem<Bounds<N>> for &Affine<N> {
+    type Output = Affine<N>;
 
-    fn rem(self, rhs: Bounds3) -> Self::Output {
+    fn rem(self, rhs: Bounds<N>) -> Self::Output {
         *self % rhs
     }
 }
 
-impl Rem<&Bounds3> for &Affine3 {
-    type Output = Affine3;
+impl<const N: usize> Rem<&Bounds<N>> for &Affine<N> {
+    type Output = Affine<N>;
 
-    fn rem(self, rhs: &Bounds3) -> Self::Output {
+    fn rem(self, rhs: &Bounds<N>) -> Self::Output {
         *self % *rhs
     }
 }
 
-impl RemAssign<Bounds3> for Affine3 {
-    fn rem_assign(&mut self, rhs: Bounds3) {
+impl<const N: usize> RemAssign<Bounds<N>> for Affine<N> {
+    fn rem_assign(&mut self, rhs: Bounds<N>) {
         *self = *self % rhs
     }
 }
 
-impl RemAssign<&Bounds3> for Affine3 {
-    fn rem_assign(&mut self, rhs: &Bounds3) {
+impl<const N: usize> RemAssign<&Bounds<N>> for Affine<N> {
+    fn rem_assign(&mut self, rhs: &Bounds<N>) {
         *self = *self % *rhs
     }
 }
@@ -946,6 +1477,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_vec3_dot() {
         let vec1 = Vec3::new(1.into(), 2.into(), 3.into());
         let vec2 = Vec3::new(2.into(), 3.into(), 4.into());
@@ -958,18 +1490,19 @@ mod tests {
         let vec2 = Vec3::new(4.into(), 5.into(), 6.into());
         let vec3 = Vec3::new(7.into(), 8.into(), 9.into());
         let mat = Mat3::from_columns(vec1, vec2, vec3);
-        assert_eq!(mat.0[0], 1.into());
-        assert_eq!(mat.0[3], 2.into());
-        assert_eq!(mat.0[6], 3.into());
+        assert_eq!(mat.entry(0, 0), 1.into());
+        assert_eq!(mat.entry(1, 0), 2.into());
+        assert_eq!(mat.entry(2, 0), 3.into());
     }
 
     #[rustfmt::skip]
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_mat3_determinant() {
-        let mat = Mat3([
-            1.into(), 2.into(), 3.into(),
-            4.into(), 5.into(), 6.into(),
-            7.into(), 8.into(), 9.into(),
+        let mat = Mat3::from([
+            1, 2, 3,
+            4, 5, 6,
+            7, 8, 9,
         ]);
         assert_eq!(
             mat.determinant(),
@@ -982,50 +1515,50 @@ mod tests {
     #[test]
     #[rustfmt::skip]
     fn test_mat3_mul() {
-        let mat = Mat3([
-            9.into(), 8.into(), 7.into(),
-            6.into(), 5.into(), 4.into(),
-            3.into(), 2.into(), 1.into(),
+        let mat = Mat3::from([
+            9, 8, 7,
+            6, 5, 4,
+            3, 2, 1,
         ]);
-        let other = Mat3([
-            1.into(), 0.into(), 0.into(),
-            0.into(), 1.into(), 0.into(),
-            0.into(), 0.into(), 1.into(),
+        let other = Mat3::from([
+            1, 0, 0,
+            0, 1, 0,
+            0, 0, 1,
         ]);
         assert_eq!(other * mat, mat);
         assert_eq!(mat * other, mat);
 
-        let other = Mat3([
-            0.into(), 1.into(), 0.into(),
-            1.into(), 0.into(), 0.into(),
-            0.into(), 0.into(), 1.into(),
+        let other = Mat3::from([
+            0, 1, 0,
+            1, 0, 0,
+            0, 0, 1,
         ]);
-        assert_eq!(other * mat, Mat3([
-            6.into(), 5.into(), 4.into(),
-            9.into(), 8.into(), 7.into(),
-            3.into(), 2.into(), 1.into(),
+        assert_eq!(other * mat, Mat3::from([
+            6, 5, 4,
+            9, 8, 7,
+            3, 2, 1,
         ]));
-        assert_eq!(mat * other, Mat3([
-            8.into(), 9.into(), 7.into(),
-            5.into(), 6.into(), 4.into(),
-            2.into(), 3.into(), 1.into(),
+        assert_eq!(mat * other, Mat3::from([
+            8, 9, 7,
+            5, 6, 4,
+            2, 3, 1,
 
         ]));
 
-        let other = Mat3([
-            1.into(), 0.into(), 0.into(),
-            0.into(), 0.into(), 1.into(),
-            0.into(), 1.into(), 0.into(),
+        let other = Mat3::from([
+            1, 0, 0,
+            0, 0, 1,
+            0, 1, 0,
         ]);
-        assert_eq!(other * mat, Mat3([
-            9.into(), 8.into(), 7.into(),
-            3.into(), 2.into(), 1.into(),
-            6.into(), 5.into(), 4.into(),
+        assert_eq!(other * mat, Mat3::from([
+            9, 8, 7,
+            3, 2, 1,
+            6, 5, 4,
         ]));
-        assert_eq!(mat * other, Mat3([
-            9.into(), 7.into(), 8.into(), 
-            6.into(), 4.into(), 5.into(), 
-            3.into(), 1.into(), 2.into(), 
+        assert_eq!(mat * other, Mat3::from([
+            9, 7, 8,
+            6, 4, 5,
+            3, 1, 2,
 
         ]))
     }
@@ -1033,13 +1566,13 @@ mod tests {
     #[rustfmt::skip]
     #[test]
     fn test_inverse_mat() {
-        let mat = Mat3([
-            1.into(), 4.into(), 4.into(),
-            5.into(), 2.into(), 5.into(),
-            3.into(), 2.into(), 5.into(),
+        let mat = Mat3::from([
+            1, 4, 4,
+            5, 2, 5,
+            3, 2, 5,
         ]);
         assert_eq!(Mat3::identity(), mat * mat.inverse().unwrap());
-        let mat = Mat3([
+        let mat = Mat3::from([
             1.into(), 4.into(), 4.into(),
             5.into(), 2.into(), 3.into(),
             Frac::new(1, 2), 1.into(), 5.into(),
@@ -1068,6 +1601,27 @@ mod tests {
         assert_eq!(vec, inverse * (affine * vec));
     }
 
+    #[test]
+    fn test_matrix_notation() {
+        use pest::Parser;
+        let parse = |string: &str| {
+            let pair = crate::MyParser::parse(Rule::matrix, string)
+                .unwrap()
+                .next()
+                .unwrap();
+            Affine3::from_parser(pair)
+        };
+        let affine = Affine3::new([0, -1, 0, 1, -1, 0, 0, 0, 1].into(), [0, 0, 1].into());
+        let affine = Affine3::new(affine.mat(), affine.translation() / Frac::from(3));
+        let string = format!("{:#}", affine);
+        assert_eq!(
+            string,
+            "[0 -1 0   0\n 1 -1 0   0\n 0  0 1 1/3\n 0  0 0   1]"
+        );
+        assert_eq!(parse(&string), affine);
+        assert_eq!(parse("[0 -1 0 0, 1 -1 0 0, 0 0 1 1/3]"), affine);
+    }
+
     #[test]
     fn test_rem() {
         let super_cell: Bounds3 = [3, 2, 1].into();
@@ -1085,6 +1639,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn plane_matrices() {
+        let mat: Matrix<2> = [[0, -1], [1, -1]].into();
+        assert_eq!(mat.determinant(), 1.into());
+        assert_eq!(mat * mat * mat, Matrix::identity());
+        assert_eq!(mat * mat.inverse().unwrap(), Matrix::identity());
+        let affine = Affine::new(mat, Vector::from([Frac::new(1, 2), 0.into()]));
+        assert_eq!(affine.to_string(), "-y+1/2,x-y");
+        let bounds: Bounds<2> = [2, 1].into();
+        let pos = Position::from([3, 1]) % bounds;
+        assert_eq!(pos, Position::from([1, 0]));
+    }
+
     #[test]
     fn contains_test() {
         let bounds: Bounds3 = [1, 4, 5].into();
@@ -0,0 +1,281 @@
+//! # Centering
+//! This module detects the centering of a space group from its pure translations and converts
+//! inputs between the conventional and the primitive cell.
+//!
+//! The primitive cells are the ones of the International Tables for Crystallography Vol. A,
+//! rhombohedral lattices are expected in the obverse setting on hexagonal axes.
+//! Multiplicities are given per unit cell, so they are multiplied by the ratio of the cell
+//! volumes when changing the cell. A supercell can only be converted if it is spanned by
+//! multiples of the new basis vectors, which is never the case between the conventional and the
+//! primitive cell of a centered lattice. Such supercells are rejected with the smallest diagonal
+//! supercell of the new cell containing them, which can be used instead.
+
+use std::fmt::Display;
+
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::{
+    symmetry::{Isometry, IsometryGroup},
+    transformation::Transformation,
+    Bounds3, Frac, Mat3, Pos3, Vec3,
+};
+
+#[derive(Error, Debug)]
+enum CenteringError {
+    #[error("the centering translations {0:?} are not one of P, A, B, C, I, F and R")]
+    Unknown(Vec<Vec3>),
+    #[error(
+        "the supercell {0:?} is not spanned by multiples of the new basis vectors, the smallest \
+         diagonal supercell of the new cell containing it is {1:?}"
+    )]
+    Supercell(Bounds3, Bounds3),
+}
+
+/// the centering types of the conventional cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Centering {
+    /// primitive
+    P,
+    /// centered on the bc face
+    A,
+    /// centered on the ac face
+    B,
+    /// centered on the ab face
+    C,
+    /// body centered
+    I,
+    /// centered on all faces
+    F,
+    /// rhombohedral in the obverse setting
+    R,
+}
+
+impl Display for Centering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Centering {
+    const ALL: [Centering; 7] = [
+        Centering::P,
+        Centering::A,
+        Centering::B,
+        Centering::C,
+        Centering::I,
+        Centering::F,
+        Centering::R,
+    ];
+
+    /// returns the centering translations modulo unit translations, without the zero vector
+    pub fn translations(&self) -> Vec<Vec3> {
+        let vec = |num: [i32; 3], den: i32| {
+            Vec3::new(
+                Frac::new(num[0], den),
+                Frac::new(num[1], den),
+                Frac::new(num[2], den),
+            )
+        };
+        match self {
+            Self::P => vec![],
+            Self::A => vec![vec([0, 1, 1], 2)],
+            Self::B => vec![vec([1, 0, 1], 2)],
+            Self::C => vec![vec([1, 1, 0], 2)],
+            Self::I => vec![vec([1, 1, 1], 2)],
+            Self::F => vec![vec([0, 1, 1], 2), vec([1, 0, 1], 2), vec([1, 1, 0], 2)],
+            Self::R => vec![vec([2, 1, 1], 3), vec([1, 2, 2], 3)],
+        }
+    }
+
+    /// returns the number of lattice points per conventional cell
+    pub fn lattice_points(&self) -> usize {
+        self.translations().len() + 1
+    }
+
+    /// returns the transformation from the conventional to the primitive cell
+    pub fn to_primitive(&self) -> Transformation {
+        // the new basis vectors are the columns in units of 1/den
+        let (columns, den): ([[i32; 3]; 3], i32) = match self {
+            Self::P => ([[1, 0, 0], [0, 1, 0], [0, 0, 1]], 1),
+            Self::A => ([[2, 0, 0], [0, 1, 1], [0, -1, 1]], 2),
+            Self::B => ([[1, 0, 1], [0, 2, 0], [-1, 0, 1]], 2),
+            Self::C => ([[1, -1, 0], [1, 1, 0], [0, 0, 2]], 2),
+            Self::I => ([[-1, 1, 1], [1, -1, 1], [1, 1, -1]], 2),
+            Self::F => ([[0, 1, 1], [1, 0, 1], [1, 1, 0]], 2),
+            Self::R => ([[2, 1, 1], [-1, 1, 1], [-1, -2, 1]], 3),
+        };
+        let [v1, v2, v3] = columns.map(|col| Vec3::from(col) / Frac::from(den));
+        Transformation::from_basis_change(Mat3::from_columns(v1, v2, v3))
+            .expect("the primitive bases are invertible")
+    }
+
+    /// converts an input given in the primitive cell of this centering to the conventional
+    /// cell, the inverse of IsometryGroup::to_primitive.
+    /// returns Err if the conventional basis vectors are not translations of the group
+    pub fn to_conventional(
+        &self,
+        group: &IsometryGroup,
+        positions: &[Pos3],
+    ) -> Result<ConvertedInput> {
+        ConvertedInput::new(self.to_primitive().inverse(), group, positions)
+    }
+}
+
+/// an input converted to another cell together with the transformation used
+#[derive(Debug, Clone)]
+pub struct ConvertedInput {
+    transformation: Transformation,
+    group: IsometryGroup,
+    positions: Vec<Pos3>,
+}
+
+impl ConvertedInput {
+    fn new(
+        transformation: Transformation,
+        group: &IsometryGroup,
+        positions: &[Pos3],
+    ) -> Result<Self> {
+        Ok(Self {
+            group: transformation.transform_group(group)?,
+            positions: positions
+                .iter()
+                .map(|&pos| transformation.transform_position(pos) % Bounds3::splat(1))
+                .collect(),
+            transformation,
+        })
+    }
+
+    /// returns the transformation from the old to the new cell
+    pub fn transformation(&self) -> Transformation {
+        self.transformation
+    }
+
+    /// returns the group in the new cell
+    pub fn group(&self) -> &IsometryGroup {
+        &self.group
+    }
+
+    /// returns the positions in the new cell
+    pub fn positions(&self) -> &[Pos3] {
+        &self.positions
+    }
+
+    /// returns the supercell in the new cell.
+    /// returns Err naming the smallest diagonal supercell of the new cell containing it if it is
+    /// not spanned by multiples of the new basis vectors
+    pub fn convert_bounds(&self, bounds: Bounds3) -> Result<Bounds3> {
+        self.transformation.transform_bounds(bounds).ok_or_else(|| {
+            CenteringError::Supercell(bounds, self.transformation.compatible_bounds(bounds)).into()
+        })
+    }
+
+    /// returns the pair vector in the new cell
+    pub fn convert_vector(&self, vec: Vec3) -> Vec3 {
+        self.transformation.transform_vector(vec)
+    }
+
+    /// returns the factor by which multiplicities per unit cell change, the ratio of the volume
+    /// of the new cell to the old one
+    pub fn multiplicity_factor(&self) -> Frac {
+        self.transformation.mat().determinant()
+    }
+}
+
+impl IsometryGroup {
+    /// detects the centering from the pure translations of the group.
+    /// returns Err if they are not one of the conventional centerings
+    pub fn centering(&self) -> Result<Centering> {
+        let translations: Vec<Vec3> = self
+            .get_operations()
+            .iter()
+            .filter(|op| op.mat() == Mat3::identity() && op.translation() != Vec3::zero())
+            .map(Isometry::translation)
+            .collect();
+        Centering::ALL
+            .into_iter()
+            .find(|centering| {
+                let expected = centering.translations();
+                expected.len() == translations.len()
+                    && expected
+                        .iter()
+                        .all(|&vec| self.contains(Isometry::from_translation(vec)))
+            })
+            .ok_or_else(|| CenteringError::Unknown(translations).into())
+    }
+
+    /// converts the group and the positions to the primitive cell, the supercell is converted by
+    /// ConvertedInput::convert_bounds.
+    /// returns Err if the centering is not known
+    pub fn to_primitive(&self, positions: &[Pos3]) -> Result<ConvertedInput> {
+        ConvertedInput::new(self.centering()?.to_primitive(), self, positions)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn group(name: &str) -> IsometryGroup {
+        IsometryGroup::from_file(format!("../files/space_groups/{}", name)).unwrap()
+    }
+
+    #[test]
+    fn detection() {
+        assert_eq!(group("P-1").centering().unwrap(), Centering::P);
+        assert_eq!(group("C2|m").centering().unwrap(), Centering::C);
+        assert_eq!(group("Fm-3m").centering().unwrap(), Centering::F);
+        assert_eq!(group("R-3m").centering().unwrap(), Centering::R);
+        let hexagonal = IsometryGroup::from_generators(vec![Isometry::from_translation(
+            Vec3::from([2, 1, 0]) / Frac::from(3),
+        )]);
+        assert!(hexagonal.centering().is_err());
+        // the primitive cells contain one lattice point
+        for centering in Centering::ALL {
+            let transformation = centering.to_primitive();
+            assert_eq!(
+                transformation.mat().determinant(),
+                Frac::new(1, centering.lattice_points() as i32)
+            );
+        }
+    }
+
+    #[test]
+    fn conversion() {
+        let sg = group("C2|m");
+        let position = Pos3::from(Vec3::from([1, 1, 0]) / Frac::from(4));
+        let converted = sg.to_primitive(&[position]).unwrap();
+        assert_eq!(converted.group().len() * 2, sg.len());
+        assert!(converted.group().centering().unwrap() == Centering::P);
+        assert_eq!(converted.multiplicity_factor(), Frac::new(1, 2));
+        // the supercell 4a x 4b x 2c is not spanned by multiples of (a-b)/2 and (a+b)/2, the
+        // primitive supercell 8 x 8 x 2 is the smallest one containing it
+        let err = converted.convert_bounds([4, 4, 2].into()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&format!("{:?}", Bounds3::from([8, 8, 2]))));
+        assert_eq!(
+            converted.convert_vector([1, 1, 0].into()),
+            Vec3::from([0, 2, 0])
+        );
+        let back = Centering::C
+            .to_conventional(converted.group(), converted.positions())
+            .unwrap();
+        assert_eq!(back.group().len(), sg.len());
+        assert!(sg
+            .get_operations()
+            .iter()
+            .all(|&op| back.group().contains(op)));
+        assert_eq!(back.positions(), [position]);
+        assert_eq!(back.multiplicity_factor(), 2.into());
+        let err = back.convert_bounds([2, 2, 2].into()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&format!("{:?}", Bounds3::from([2, 2, 2]))));
+        // without centering the supercell is kept
+        let converted = group("P-1").to_primitive(&[position]).unwrap();
+        let bounds = converted.convert_bounds([4, 4, 2].into()).unwrap();
+        assert_eq!([bounds.x(), bounds.y(), bounds.z()], [4, 4, 2]);
+        assert_eq!(converted.multiplicity_factor(), 1.into());
+    }
+}
@@ -0,0 +1,621 @@
+//! # Input Files
+//! This module reads input files into a configuration and runs the calculations described by it.
+//!
+//! The sections of a file can be given in any order. The space group is given by its operations
+//! or by its symbol or number such as Fm-3m or 225. Space Group, Positions and Bounds may be
+//! repeated, the operations are combined, the positions are collected and every supercell is
+//! a separate job. All other sections are options which may be given at most once.
+//! Without Bounds a supercell of 3 cells along every coordinate is used, or the smallest one
+//! whose pair vectors contain all pairs within the cutoff if Cutoff and Cell are given.
+//!
+//! With the float arithmetic the positions are used as given, without snapping, and the pairs
+//! are calculated with floating point coordinates which are equal up to the tolerance. This is
+//! meant for refined general positions and is only available for space groups.
+//!
+//! A configuration is read for a fixed number of coordinates, Input reads the Dimension section
+//! first and holds the configuration of the line, plane or space group problem.
+
+use std::{fs::read_to_string, path::Path, str::FromStr};
+
+use anyhow::Result;
+use nalgebra::Vector3;
+use pest::{iterators::Pairs, Parser};
+use thiserror::Error;
+
+use crate::{
+    affine_space::{Bounds, Position, Vector},
+    dimension::{check_components, Dimension},
+    lattice::Metric,
+    numeric::{
+        calculate_float_pairs, produce_float_output_string, FloatPairExpansion, FloatPos3,
+        DEFAULT_EQUALITY_TOLERANCE,
+    },
+    objects::{calculate_pairs, produce_output_string, PairExpansion},
+    snapping::{SnappedPosition, SnappingWarning, DEFAULT_TOLERANCE},
+    symmetry::IsometryGroup,
+    validation::validate,
+    MyParser, Rule,
+};
+
+#[derive(Error, Debug)]
+enum ConfigError {
+    #[error("the section {0} is missing")]
+    Missing(&'static str),
+    #[error("the section {0} can only be given once")]
+    Repeated(&'static str),
+    #[error("the cutoff requires the cell")]
+    CutoffWithoutCell,
+    #[error("the input is {0} dimensional but {1} dimensional was expected")]
+    Dimension(Dimension, usize),
+    #[error("the float arithmetic is only available in three dimensions")]
+    FloatDimension,
+}
+
+/// the length of the supercell along each coordinate if no bounds are given
+const DEFAULT_BOUND: i32 = 3;
+
+/// the formats in which the results can be written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// a table with aligned columns
+    #[default]
+    Table,
+    /// comma separated values with quoted positions and vectors
+    Csv,
+}
+
+impl OutputFormat {
+    /// writes the pairs
+    pub fn write<const N: usize>(&self, expansions: &[PairExpansion<N>]) -> String {
+        match self {
+            Self::Table => produce_output_string(expansions),
+            Self::Csv => {
+                let mut string = "Origin,Vector,Multiplicity".to_string();
+                for expansion in expansions {
+                    string += &format!(
+                        "\n\"{}\",\"{}\",{}",
+                        expansion.origin_position(),
+                        expansion.vector(),
+                        expansion.multiplicity()
+                    );
+                }
+                string
+            }
+        }
+    }
+
+    /// writes the pairs with floating point positions
+    pub fn write_float(&self, expansions: &[FloatPairExpansion]) -> String {
+        match self {
+            Self::Table => produce_float_output_string(expansions),
+            Self::Csv => {
+                let mut string = "Origin,Vector,Multiplicity".to_string();
+                for (origin, vector, multiplicity) in
+                    expansions.iter().map(FloatPairExpansion::to_string)
+                {
+                    string += &format!("\n\"{}\",\"{}\",{}", origin, vector, multiplicity);
+                }
+                string
+            }
+        }
+    }
+}
+
+/// the arithmetic used for the positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Arithmetic {
+    /// exact fractions, decimals are snapped to special positions
+    #[default]
+    Exact,
+    /// floating point numbers which are equal up to the tolerance
+    Float,
+}
+
+/// the content of an input file describing a problem in N dimensions
+#[derive(Debug, Clone)]
+pub struct Config<const N: usize = 3> {
+    group: IsometryGroup<N>,
+    positions: Vec<Position<N>>,
+    bounds: Vec<Bounds<N>>,
+    construct_ab_pairs: bool,
+    tolerance: f64,
+    output_format: OutputFormat,
+    arithmetic: Arithmetic,
+    cutoff: Option<f64>,
+    cell: Option<Metric>,
+    warnings: Vec<SnappingWarning<N>>,
+}
+
+impl<const N: usize> Config<N> {
+    /// reads the input file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        read_to_string(path)?.parse()
+    }
+
+    /// creates the configuration from the parsed file.
+    /// decimal coordinates are snapped to special positions within the tolerance.
+    /// returns Err with the diagnostics of the validation if the input is invalid, if a
+    /// required section is missing or an option is repeated, or if the file isn't N dimensional
+    pub fn from_tree(mut pairs: Pairs<Rule>) -> Result<Self> {
+        validate::<N>(pairs.clone())?;
+        let sections: Vec<_> = pairs
+            .next()
+            .expect("must contain file")
+            .into_inner()
+            .collect();
+        let once = |rule: Rule, name: &'static str| {
+            let mut found = sections.iter().filter(|section| section.as_rule() == rule);
+            let first = found.next().map(|section| {
+                section
+                    .clone()
+                    .into_inner()
+                    .next()
+                    .expect("enforced by grammar")
+            });
+            match found.next() {
+                Some(_) => Err(ConfigError::Repeated(name)),
+                None => Ok(first),
+            }
+        };
+        let dimension = once(Rule::dimension_section, "Dimension")?
+            .map(Dimension::from_parser)
+            .unwrap_or_default();
+        if dimension.count() != N {
+            return Err(ConfigError::Dimension(dimension, N).into());
+        }
+        let arithmetic = match once(Rule::arithmetic_section, "Arithmetic")? {
+            Some(pair) if pair.as_str() == "float" => Arithmetic::Float,
+            _ => Arithmetic::Exact,
+        };
+        if arithmetic == Arithmetic::Float && N != 3 {
+            return Err(ConfigError::FloatDimension.into());
+        }
+        // the tolerance is needed to read the positions, with floating point positions it is
+        // the tolerance of the equality instead of snapping
+        let tolerance = match (once(Rule::tolerance_section, "Tolerance")?, arithmetic) {
+            (Some(pair), _) => pair.as_str().parse().expect("enforced by grammar"),
+            (None, Arithmetic::Exact) => DEFAULT_TOLERANCE,
+            (None, Arithmetic::Float) => DEFAULT_EQUALITY_TOLERANCE,
+        };
+        let number = |pair: pest::iterators::Pair<Rule>| -> f64 {
+            pair.as_str().parse().expect("enforced by grammar")
+        };
+        let construct_ab_pairs = once(Rule::mixed_pairs_section, "Mixed Pairs")?
+            .is_some_and(|pair| pair.as_str() == "true");
+        let output_format = match once(Rule::output_section, "Output")? {
+            Some(pair) if pair.as_str() == "csv" => OutputFormat::Csv,
+            _ => OutputFormat::Table,
+        };
+        let cutoff = once(Rule::cutoff_section, "Cutoff")?.map(number);
+        let cell = match sections
+            .iter()
+            .filter(|section| section.as_rule() == Rule::cell_section)
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [] => None,
+            [section] => {
+                let [a, b, c, alpha, beta, gamma]: [f64; 6] = (*section)
+                    .clone()
+                    .into_inner()
+                    .map(number)
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .expect("enforced by grammar");
+                Some(Metric::from_parameters(a, b, c, alpha, beta, gamma)?)
+            }
+            _ => return Err(ConfigError::Repeated("Cell").into()),
+        };
+        if cutoff.is_some() && cell.is_none() {
+            return Err(ConfigError::CutoffWithoutCell.into());
+        }
+
+        let mut generators = Vec::new();
+        let mut position_pairs = Vec::new();
+        let mut bounds = Vec::new();
+        for section in sections {
+            match section.as_rule() {
+                Rule::group_section => {
+                    let list = section.into_inner().next().expect("enforced by grammar");
+                    let group = match list.as_rule() {
+                        Rule::group_symbol => {
+                            IsometryGroup::from_symbol(list.as_str())?.restrict::<N>()?
+                        }
+                        _ => IsometryGroup::from_affine_list(list.into_inner())?,
+                    };
+                    generators.extend_from_slice(group.get_operations());
+                }
+                Rule::positions_section => {
+                    for pair in section.into_inner() {
+                        check_components::<N>(&pair)?;
+                        position_pairs.push(pair);
+                    }
+                }
+                Rule::bounds_section => {
+                    for pair in section.into_inner() {
+                        check_components::<N>(&pair)?;
+                        bounds.push(Bounds::from_parser_int_vector(pair));
+                    }
+                }
+                _ => (),
+            }
+        }
+        if generators.is_empty() {
+            return Err(ConfigError::Missing("Space Group").into());
+        }
+        if position_pairs.is_empty() {
+            return Err(ConfigError::Missing("Positions").into());
+        }
+        let group = IsometryGroup::from_generators(generators);
+        // snapping depends on the site symmetry, so positions are read once the group is known
+        let snapped: Vec<SnappedPosition<N>> = position_pairs
+            .into_iter()
+            .map(|pair| SnappedPosition::from_parser(pair, tolerance, &group))
+            .collect();
+        let (positions, warnings) = match arithmetic {
+            Arithmetic::Exact => (
+                snapped.iter().map(SnappedPosition::position).collect(),
+                snapped
+                    .iter()
+                    .filter_map(|pos| pos.check_site_symmetry(&group))
+                    .collect(),
+            ),
+            Arithmetic::Float => (
+                snapped.iter().map(SnappedPosition::unsnapped).collect(),
+                Vec::new(),
+            ),
+        };
+        let mut config = Self {
+            group,
+            positions,
+            bounds,
+            construct_ab_pairs,
+            tolerance,
+            output_format,
+            arithmetic,
+            cutoff,
+            cell,
+            warnings,
+        };
+        if config.bounds.is_empty() {
+            config.bounds.push(config.default_bounds());
+        }
+        Ok(config)
+    }
+
+    /// returns the bounds used if none are given
+    fn default_bounds(&self) -> Bounds<N> {
+        let bounds: [i32; N] = std::array::from_fn(|i| {
+            match (self.cutoff, self.cell) {
+                // the components of the vectors within the cutoff are at most the cutoff times
+                // the length of the reciprocal basis vector
+                (Some(cutoff), Some(cell)) => {
+                    let reciprocal = cell.mat().try_inverse().expect("the metric is invertible");
+                    (2.0 * cutoff * reciprocal[(i, i)].sqrt()).floor() as i32 + 1
+                }
+                _ => DEFAULT_BOUND,
+            }
+        });
+        bounds.into()
+    }
+
+    /// returns the group generated by all given operations
+    pub fn group(&self) -> &IsometryGroup<N> {
+        &self.group
+    }
+
+    /// returns the positions after snapping, or as given with the float arithmetic
+    pub fn positions(&self) -> &[Position<N>] {
+        &self.positions
+    }
+
+    /// returns the supercells, one for each job
+    pub fn bounds(&self) -> &[Bounds<N>] {
+        &self.bounds
+    }
+
+    /// returns true if pairs of different sites are constructed
+    pub fn construct_ab_pairs(&self) -> bool {
+        self.construct_ab_pairs
+    }
+
+    /// returns the tolerance used for snapping, or for the equality with the float arithmetic
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    /// returns the format of the output
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+
+    /// returns the arithmetic used for the positions
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
+    /// returns the largest pair distance if one is given
+    pub fn cutoff(&self) -> Option<f64> {
+        self.cutoff
+    }
+
+    /// returns the metric of the cell if it is given
+    pub fn cell(&self) -> Option<Metric> {
+        self.cell
+    }
+
+    /// returns the warnings about positions whose site symmetry was changed by snapping
+    pub fn warnings(&self) -> &[SnappingWarning<N>] {
+        &self.warnings
+    }
+
+    /// calculates the pairs for every supercell. the input is canonicalized first and pairs
+    /// longer than the cutoff are removed
+    pub fn calculate_pairs(&self) -> Vec<Vec<PairExpansion<N>>> {
+        self.bounds
+            .iter()
+            .map(|&bounds| {
+                // equivalent descriptions of the structure give the same output
+                let (group, positions) = self.group.canonicalize(&self.positions, bounds);
+                let mut expansions =
+                    calculate_pairs(group, positions, bounds, self.construct_ab_pairs);
+                if let (Some(cutoff), Some(cell)) = (self.cutoff, self.cell) {
+                    expansions.retain(|expansion| cell.length(expansion.vector()) <= cutoff);
+                }
+                expansions
+            })
+            .collect()
+    }
+
+    /// writes the results of all jobs in the output format with exact arithmetic, each
+    /// preceded by its bounds if there are several
+    pub fn produce_output(&self) -> String {
+        let tables: Vec<String> = self
+            .calculate_pairs()
+            .iter()
+            .map(|expansions| self.output_format.write(expansions))
+            .collect();
+        self.join_jobs(tables)
+    }
+
+    /// precedes the tables by their bounds if there are several jobs
+    fn join_jobs(&self, tables: Vec<String>) -> String {
+        if self.bounds.len() == 1 {
+            return tables.join("\n\n");
+        }
+        let results: Vec<String> = tables
+            .iter()
+            .zip(self.bounds.iter())
+            .map(|(table, bounds)| format!("Bounds: {}\n{}", Vector::from(bounds.lengths()), table))
+            .collect();
+        results.join("\n\n")
+    }
+}
+
+impl Config {
+    /// calculates the pairs for every supercell with floating point positions. pairs longer
+    /// than the cutoff are removed
+    pub fn calculate_float_pairs(&self) -> Vec<Vec<FloatPairExpansion>> {
+        let positions: Vec<FloatPos3> = self.positions.iter().map(|&pos| pos.into()).collect();
+        self.bounds
+            .iter()
+            .map(|&bounds| {
+                let mut expansions = calculate_float_pairs(
+                    &self.group,
+                    &positions,
+                    bounds,
+                    self.construct_ab_pairs,
+                    self.tolerance,
+                );
+                if let (Some(cutoff), Some(cell)) = (self.cutoff, self.cell) {
+                    expansions.retain(|expansion| {
+                        let vec = Vector3::from(expansion.vector());
+                        vec.dot(&(cell.mat() * vec)).sqrt() <= cutoff
+                    });
+                }
+                expansions
+            })
+            .collect()
+    }
+
+    /// writes the results of all jobs in the output format with floating point positions, see
+    /// produce_output
+    pub fn produce_float_output(&self) -> String {
+        let tables: Vec<String> = self
+            .calculate_float_pairs()
+            .iter()
+            .map(|expansions| self.output_format.write_float(expansions))
+            .collect();
+        self.join_jobs(tables)
+    }
+}
+
+impl<const N: usize> FromStr for Config<N> {
+    type Err = anyhow::Error;
+
+    fn from_str(string: &str) -> Result<Self> {
+        Self::from_tree(MyParser::parse(Rule::file, string)?)
+    }
+}
+
+/// the configuration of an input file in the dimension given by the file
+#[derive(Debug, Clone)]
+pub enum Input {
+    /// a line group problem
+    One(Config<1>),
+    /// a plane group problem
+    Two(Config<2>),
+    /// a space group problem
+    Three(Config<3>),
+}
+
+impl Input {
+    /// reads the input file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        read_to_string(path)?.parse()
+    }
+
+    /// creates the configuration of the dimension given in the parsed file, see Config::from_tree
+    pub fn from_tree(pairs: Pairs<Rule>) -> Result<Self> {
+        let dimension = pairs
+            .clone()
+            .flat_map(|file| file.into_inner())
+            .find(|section| section.as_rule() == Rule::dimension_section)
+            .and_then(|section| section.into_inner().next())
+            .map(Dimension::from_parser)
+            .unwrap_or_default();
+        Ok(match dimension {
+            Dimension::One => Self::One(Config::from_tree(pairs)?),
+            Dimension::Two => Self::Two(Config::from_tree(pairs)?),
+            Dimension::Three => Self::Three(Config::from_tree(pairs)?),
+        })
+    }
+
+    /// returns the dimension of the problem
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Self::One(_) => Dimension::One,
+            Self::Two(_) => Dimension::Two,
+            Self::Three(_) => Dimension::Three,
+        }
+    }
+
+    /// returns the warnings about positions whose site symmetry was changed by snapping
+    pub fn warnings(&self) -> Vec<String> {
+        match self {
+            Self::One(config) => config.warnings().iter().map(ToString::to_string).collect(),
+            Self::Two(config) => config.warnings().iter().map(ToString::to_string).collect(),
+            Self::Three(config) => config.warnings().iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    /// writes the results of all jobs in the arithmetic given in the file, see
+    /// Config::produce_output
+    pub fn produce_output(&self) -> String {
+        match self {
+            Self::One(config) => config.produce_output(),
+            Self::Two(config) => config.produce_output(),
+            Self::Three(config) => match config.arithmetic() {
+                Arithmetic::Exact => config.produce_output(),
+                Arithmetic::Float => config.produce_float_output(),
+            },
+        }
+    }
+}
+
+impl FromStr for Input {
+    type Err = anyhow::Error;
+
+    fn from_str(string: &str) -> Result<Self> {
+        Self::from_tree(MyParser::parse(Rule::file, string)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Vec3;
+
+    const EXAMPLE: &str = "Space Group:\n-x,-y,-z;\nPositions:\n0,0,0;\n";
+
+    #[test]
+    fn order_independent() {
+        let ordered: Config = Config::from_file("../files/input/example2").unwrap();
+        let string = read_to_string("../files/input/example2").unwrap();
+        let (group, rest) = string.split_at(string.find("Positions:").unwrap());
+        let reordered: Config = format!("{}\n{}", rest, group).parse().unwrap();
+        assert_eq!(ordered.produce_output(), reordered.produce_output());
+        // repeated options and missing sections are rejected
+        assert!(format!("{}Output:\ncsv;\nOutput:\ntable;", EXAMPLE)
+            .parse::<Config>()
+            .is_err());
+        assert!("Positions:\n0,0,0;".parse::<Config>().is_err());
+        assert!(format!("{}Cutoff:\n2;", EXAMPLE).parse::<Config>().is_err());
+    }
+
+    #[test]
+    fn jobs() {
+        let config: Config = format!("{}Bounds:\n2,2,2;\n3,3,3;\nOutput:\ncsv;", EXAMPLE)
+            .parse()
+            .unwrap();
+        assert_eq!(config.bounds().len(), 2);
+        let output = config.produce_output();
+        assert!(output.starts_with("Bounds: [2, 2, 2]\nOrigin,Vector,Multiplicity"));
+        assert!(output.contains("Bounds: [3, 3, 3]"));
+        assert!(output.contains("\"[0, 0, 0]\",\"[1, 1, 1]\",1"));
+        // the default bounds
+        let config: Config = EXAMPLE.parse().unwrap();
+        let bounds = config.bounds()[0];
+        assert_eq!([bounds.x(), bounds.y(), bounds.z()], [3, 3, 3]);
+    }
+
+    #[test]
+    fn group_symbol() {
+        let operations: Config = EXAMPLE.parse().unwrap();
+        let symbol: Config = EXAMPLE
+            .replace("-x,-y,-z;", "P-1; // the same as 2")
+            .parse()
+            .unwrap();
+        assert_eq!(operations.group(), symbol.group());
+        let error = EXAMPLE
+            .replace("-x,-y,-z;", "P2_1/n;")
+            .parse::<Config>()
+            .unwrap_err();
+        assert!(error.to_string().contains("P2_1/c"));
+    }
+
+    #[test]
+    fn cutoff() {
+        let config: Config = format!("{}Cell:\n2,3,4,90,90,90;\nCutoff:\n4;", EXAMPLE)
+            .parse()
+            .unwrap();
+        let bounds = config.bounds()[0];
+        assert_eq!([bounds.x(), bounds.y(), bounds.z()], [5, 3, 3]);
+        let cell = config.cell().unwrap();
+        let pairs = &config.calculate_pairs()[0];
+        assert!(pairs
+            .iter()
+            .all(|pair| cell.length(pair.vector()) <= 4.0 + 1e-9));
+        // [2, 0, 0] of length 4 and [0, 1, 0] of length 3 are kept, [1, 1, 1] of length 5.4 is not
+        assert!(pairs
+            .iter()
+            .any(|pair| pair.vector() == Vec3::from([2, 0, 0])));
+        assert!(pairs
+            .iter()
+            .any(|pair| pair.vector() == Vec3::from([0, 1, 0])));
+        assert!(!pairs
+            .iter()
+            .any(|pair| pair.vector() == Vec3::from([1, 1, 1])));
+    }
+
+    #[test]
+    fn float_arithmetic() {
+        let exact: Config = format!("{}Positions:\n1/7,2/9,3/11;\nBounds:\n2,2,2;", EXAMPLE)
+            .parse()
+            .unwrap();
+        let string = format!(
+            "{}Positions:\n0.1428571,0.2222222,0.2727273;\nBounds:\n2,2,2;\nArithmetic:\nfloat;",
+            EXAMPLE
+        );
+        let float: Config = string.parse().unwrap();
+        assert_eq!(float.arithmetic(), Arithmetic::Float);
+        assert_eq!(float.tolerance(), DEFAULT_EQUALITY_TOLERANCE);
+        let mut exact: Vec<usize> = exact.calculate_pairs()[0]
+            .iter()
+            .map(PairExpansion::multiplicity)
+            .collect();
+        let mut float: Vec<usize> = float.calculate_float_pairs()[0]
+            .iter()
+            .map(FloatPairExpansion::multiplicity)
+            .collect();
+        exact.sort();
+        float.sort();
+        assert_eq!(exact, float);
+        // the input chooses the output of the floating point pairs
+        let output = string.parse::<Input>().unwrap().produce_output();
+        assert!(output.contains("[0.1429, 0.2222, 0.2727]"));
+        // floating point positions are only available in three dimensions
+        let plane = "Dimension: 2;\nSpace Group:\n-y,x;\nPositions:\n0.1,0.2;\nArithmetic:\nfloat;";
+        assert!(plane.parse::<Input>().is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! # Crystal Classes
+//! This module identifies which of the 32 crystallographic point groups a point group is.
+//!
+//! The crystallographic point groups are uniquely determined by how many elements of each type
+//! (1, 2, 3, 4, 6, -1, m, -3, -4, -6) they contain. As the type of an element only depends on
+//! its determinant and trace, the identification works in any basis, e.g. on hexagonal axes.
+
+use std::fmt::Display;
+
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::{
+    symmetry::{IsometryGroup, PointGroup},
+    symmetry_elements::rotation_type,
+    Mat3,
+};
+
+#[derive(Error, Debug)]
+enum CrystalClassError {
+    #[error("the point group is not one of the 32 crystallographic point groups")]
+    NotCrystallographic,
+}
+
+/// the seven crystal systems
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CrystalSystem {
+    /// point groups 1 and -1
+    Triclinic,
+    /// point groups 2, m and 2/m
+    Monoclinic,
+    /// point groups 222, mm2 and mmm
+    Orthorhombic,
+    /// point groups with a single four-fold axis
+    Tetragonal,
+    /// point groups with a single three-fold axis
+    Trigonal,
+    /// point groups with a single six-fold axis
+    Hexagonal,
+    /// point groups with four three-fold axes
+    Cubic,
+}
+
+impl Display for CrystalSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Triclinic => "triclinic",
+            Self::Monoclinic => "monoclinic",
+            Self::Orthorhombic => "orthorhombic",
+            Self::Tetragonal => "tetragonal",
+            Self::Trigonal => "trigonal",
+            Self::Hexagonal => "hexagonal",
+            Self::Cubic => "cubic",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// the element types in the order used by the table of crystal classes
+const ELEMENT_TYPES: [i32; 10] = [1, 2, 3, 4, 6, -1, -2, -3, -4, -6];
+
+/// the Hermann-Mauguin symbol, crystal system, Laue class and the number of elements of each
+/// type for every crystallographic point group
+#[rustfmt::skip]
+const CRYSTAL_CLASSES: [(&str, CrystalSystem, &str, [usize; 10]); 32] = {
+    use CrystalSystem::*;
+    [
+        ("1", Triclinic, "-1", [1, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+        ("-1", Triclinic, "-1", [1, 0, 0, 0, 0, 1, 0, 0, 0, 0]),
+        ("2", Monoclinic, "2/m", [1, 1, 0, 0, 0, 0, 0, 0, 0, 0]),
+        ("m", Monoclinic, "2/m", [1, 0, 0, 0, 0, 0, 1, 0, 0, 0]),
+        ("2/m", Monoclinic, "2/m", [1, 1, 0, 0, 0, 1, 1, 0, 0, 0]),
+        ("222", Orthorhombic, "mmm", [1, 3, 0, 0, 0, 0, 0, 0, 0, 0]),
+        ("mm2", Orthorhombic, "mmm", [1, 1, 0, 0, 0, 0, 2, 0, 0, 0]),
+        ("mmm", Orthorhombic, "mmm", [1, 3, 0, 0, 0, 1, 3, 0, 0, 0]),
+        ("4", Tetragonal, "4/m", [1, 1, 0, 2, 0, 0, 0, 0, 0, 0]),
+        ("-4", Tetragonal, "4/m", [1, 1, 0, 0, 0, 0, 0, 0, 2, 0]),
+        ("4/m", Tetragonal, "4/m", [1, 1, 0, 2, 0, 1, 1, 0, 2, 0]),
+        ("422", Tetragonal, "4/mmm", [1, 5, 0, 2, 0, 0, 0, 0, 0, 0]),
+        ("4mm", Tetragonal, "4/mmm", [1, 1, 0, 2, 0, 0, 4, 0, 0, 0]),
+        ("-42m", Tetragonal, "4/mmm", [1, 3, 0, 0, 0, 0, 2, 0, 2, 0]),
+        ("4/mmm", Tetragonal, "4/mmm", [1, 5, 0, 2, 0, 1, 5, 0, 2, 0]),
+        ("3", Trigonal, "-3", [1, 0, 2, 0, 0, 0, 0, 0, 0, 0]),
+        ("-3", Trigonal, "-3", [1, 0, 2, 0, 0, 1, 0, 2, 0, 0]),
+        ("32", Trigonal, "-3m", [1, 3, 2, 0, 0, 0, 0, 0, 0, 0]),
+        ("3m", Trigonal, "-3m", [1, 0, 2, 0, 0, 0, 3, 0, 0, 0]),
+        ("-3m", Trigonal, "-3m", [1, 3, 2, 0, 0, 1, 3, 2, 0, 0]),
+        ("6", Hexagonal, "6/m", [1, 1, 2, 0, 2, 0, 0, 0, 0, 0]),
+        ("-6", Hexagonal, "6/m", [1, 0, 2, 0, 0, 0, 1, 0, 0, 2]),
+        ("6/m", Hexagonal, "6/m", [1, 1, 2, 0, 2, 1, 1, 2, 0, 2]),
+        ("622", Hexagonal, "6/mmm", [1, 7, 2, 0, 2, 0, 0, 0, 0, 0]),
+        ("6mm", Hexagonal, "6/mmm", [1, 1, 2, 0, 2, 0, 6, 0, 0, 0]),
+        ("-6m2", Hexagonal, "6/mmm", [1, 3, 2, 0, 0, 0, 4, 0, 0, 2]),
+        ("6/mmm", Hexagonal, "6/mmm", [1, 7, 2, 0, 2, 1, 7, 2, 0, 2]),
+        ("23", Cubic, "m-3", [1, 3, 8, 0, 0, 0, 0, 0, 0, 0]),
+        ("m-3", Cubic, "m-3", [1, 3, 8, 0, 0, 1, 3, 8, 0, 0]),
+        ("432", Cubic, "m-3m", [1, 9, 8, 6, 0, 0, 0, 0, 0, 0]),
+        ("-43m", Cubic, "m-3m", [1, 3, 8, 0, 0, 0, 6, 0, 6, 0]),
+        ("m-3m", Cubic, "m-3m", [1, 9, 8, 6, 0, 1, 9, 8, 6, 0]),
+    ]
+};
+
+/// one of the 32 crystallographic point groups
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrystalClass {
+    symbol: &'static str,
+    order: usize,
+    crystal_system: CrystalSystem,
+    laue_class: &'static str,
+    is_centrosymmetric: bool,
+    is_polar: bool,
+    is_enantiomorphic: bool,
+}
+
+impl CrystalClass {
+    /// returns the short Hermann-Mauguin symbol such as 4/mmm
+    pub fn symbol(&self) -> &str {
+        self.symbol
+    }
+
+    /// returns the number of elements of the point group
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// returns the crystal system
+    pub fn crystal_system(&self) -> CrystalSystem {
+        self.crystal_system
+    }
+
+    /// returns the symbol of the Laue class such as 6/mmm
+    pub fn laue_class(&self) -> &str {
+        self.laue_class
+    }
+
+    /// returns true if the point group contains the inversion
+    pub fn is_centrosymmetric(&self) -> bool {
+        self.is_centrosymmetric
+    }
+
+    /// returns true if a direction is left invariant by all elements, which allows for a
+    /// pyroelectric effect
+    pub fn is_polar(&self) -> bool {
+        self.is_polar
+    }
+
+    /// returns true if the point group only contains rotations, such that crystals can occur in
+    /// two mirror images
+    pub fn is_enantiomorphic(&self) -> bool {
+        self.is_enantiomorphic
+    }
+}
+
+impl Display for CrystalClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}, order {}, Laue class {})",
+            self.symbol, self.crystal_system, self.order, self.laue_class
+        )
+    }
+}
+
+impl PointGroup {
+    /// identifies the point group as one of the 32 crystallographic point groups.
+    /// returns Err if it is not crystallographic
+    pub fn crystal_class(&self) -> Result<CrystalClass> {
+        let mut counts = [0; 10];
+        let mut sum = Mat3::from([0; 9]);
+        for op in self.iter() {
+            let kind = rotation_type(op.mat()).ok_or(CrystalClassError::NotCrystallographic)?;
+            let i = ELEMENT_TYPES
+                .iter()
+                .position(|&t| t == kind)
+                .expect("all types are listed");
+            counts[i] += 1;
+            sum = sum + op.mat();
+        }
+        let &(symbol, crystal_system, laue_class, _) = CRYSTAL_CLASSES
+            .iter()
+            .find(|(_, _, _, class_counts)| *class_counts == counts)
+            .ok_or(CrystalClassError::NotCrystallographic)?;
+        Ok(CrystalClass {
+            symbol,
+            order: self.len(),
+            crystal_system,
+            laue_class,
+            is_centrosymmetric: self.is_centrosymmetric(),
+            // the sum of all elements projects onto the directions fixed by all of them
+            is_polar: sum != Mat3::from([0; 9]),
+            is_enantiomorphic: self.iter().all(|op| op.mat().determinant() == 1.into()),
+        })
+    }
+}
+
+impl IsometryGroup {
+    /// identifies the point group of the space group.
+    /// returns Err if it is not crystallographic
+    pub fn crystal_class(&self) -> Result<CrystalClass> {
+        self.reduce_to_point_group().crystal_class()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::symmetry::PointGroupElement;
+
+    fn class_of(name: &str) -> CrystalClass {
+        IsometryGroup::from_file(format!("../files/space_groups/{}", name))
+            .unwrap()
+            .crystal_class()
+            .unwrap()
+    }
+
+    #[test]
+    fn identify_space_groups() {
+        assert_eq!(class_of("P-1").symbol(), "-1");
+        assert_eq!(class_of("P2_1").symbol(), "2");
+        assert_eq!(class_of("C2|m").symbol(), "2/m");
+        assert_eq!(class_of("P2_12_12").symbol(), "222");
+        assert_eq!(class_of("Pmna").symbol(), "mmm");
+        let hexagonal = class_of("P6_3|mmc");
+        assert_eq!(hexagonal.symbol(), "6/mmm");
+        assert_eq!(hexagonal.crystal_system(), CrystalSystem::Hexagonal);
+        assert_eq!(hexagonal.order(), 24);
+        let trigonal = class_of("R-3m");
+        assert_eq!(trigonal.symbol(), "-3m");
+        assert_eq!(trigonal.laue_class(), "-3m");
+        let cubic = class_of("Fm-3m");
+        assert_eq!(cubic.symbol(), "m-3m");
+        assert!(cubic.is_centrosymmetric());
+        assert!(!cubic.is_polar());
+    }
+
+    #[test]
+    fn properties() {
+        let element = |mat: [i32; 9]| PointGroupElement::new(mat.into()).unwrap();
+        // mm2 on hexagonal axes with the mirrors x-y,-y,z and -x+y,y,z
+        let mm2 = PointGroup::from_generators(vec![
+            element([1, -1, 0, 0, -1, 0, 0, 0, 1]),
+            element([-1, 1, 0, 0, 1, 0, 0, 0, 1]),
+        ]);
+        let class = mm2.crystal_class().unwrap();
+        assert_eq!(class.symbol(), "mm2");
+        assert!(class.is_polar());
+        assert!(!class.is_enantiomorphic());
+        let p3 = PointGroup::from_generators(vec![element([0, -1, 0, 1, -1, 0, 0, 0, 1])]);
+        let class = p3.crystal_class().unwrap();
+        assert_eq!(class.symbol(), "3");
+        assert!(class.is_polar() && class.is_enantiomorphic());
+    }
+}
@@ -0,0 +1,212 @@
+//! # Diffuse Scattering
+//! This module calculates the diffuse scattering intensity of an occupational correlation model.
+//!
+//! The intensity is the Fourier transform of the 3D-ΔPDF calculated in the pdf module.
+//! As the equivalent vectors of a pair expansion form an orbit of the Laue class of the point
+//! group, the intensity has the Laue symmetry of the group and only the cosine terms remain.
+
+use std::{f64::consts::PI, fs::File, io::Write, path::Path};
+
+use anyhow::Result;
+
+use crate::{
+    objects::PairExpansion,
+    pdf::{weighted_vectors, WeightedSite},
+    symmetry::IsometryGroup,
+};
+
+/// a regular grid of points in reciprocal space
+/// axes with only one point are fixed at the minimum, which allows for planes and lines
+#[derive(Debug, Clone, Copy)]
+pub struct HklGrid {
+    min: [f64; 3],
+    max: [f64; 3],
+    shape: [usize; 3],
+}
+
+impl HklGrid {
+    /// creates a grid from min to max (both inclusive) with the given number of points per axis
+    pub fn new(min: [f64; 3], max: [f64; 3], shape: [usize; 3]) -> Self {
+        assert!(
+            shape.iter().all(|&n| n >= 1),
+            "a grid needs at least one point per axis"
+        );
+        Self { min, max, shape }
+    }
+
+    /// returns the number of points along each axis
+    pub fn shape(&self) -> [usize; 3] {
+        self.shape
+    }
+
+    /// returns the distance between neighbouring points along each axis
+    pub fn step(&self) -> [f64; 3] {
+        std::array::from_fn(|i| {
+            if self.shape[i] == 1 {
+                0.0
+            } else {
+                (self.max[i] - self.min[i]) / (self.shape[i] - 1) as f64
+            }
+        })
+    }
+
+    /// returns the hkl of the point at the index
+    pub fn hkl(&self, index: [usize; 3]) -> [f64; 3] {
+        let step = self.step();
+        std::array::from_fn(|i| self.min[i] + index[i] as f64 * step[i])
+    }
+
+    fn linear_index(&self, index: [usize; 3]) -> usize {
+        (index[0] * self.shape[1] + index[1]) * self.shape[2] + index[2]
+    }
+}
+
+/// the diffuse intensity sampled on a grid
+pub struct DiffuseMap {
+    grid: HklGrid,
+    values: Vec<f64>,
+}
+
+impl DiffuseMap {
+    /// returns the grid of the map
+    pub fn grid(&self) -> HklGrid {
+        self.grid
+    }
+
+    /// returns the intensity at the grid point
+    pub fn get(&self, index: [usize; 3]) -> f64 {
+        self.values[self.grid.linear_index(index)]
+    }
+
+    /// returns the values of all grid points with the last index varying fastest
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// writes the map to the same text volume format as the 3D-ΔPDF maps
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut file = File::create(path)?;
+        write!(file, "{}", self.to_volume_string())?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// returns the map in the text volume format used by DiffuseMap::write
+    pub fn to_volume_string(&self) -> String {
+        let [nx, ny, nz] = self.grid.shape;
+        let [ox, oy, oz] = self.grid.min;
+        let [sx, sy, sz] = self.grid.step();
+        let mut string = "# diffuse intensity volume".to_string();
+        string += &format!("\nshape {} {} {}", nx, ny, nz);
+        string += &format!("\norigin {} {} {}", ox, oy, oz);
+        string += &format!("\nstep {} {} {}", sx, sy, sz);
+        for value in &self.values {
+            string += &format!("\n{}", value);
+        }
+        string
+    }
+}
+
+/// calculates the diffuse intensity of the correlation model at the given hkl
+fn intensity_at(vectors: &[([f64; 3], f64)], hkl: [f64; 3]) -> f64 {
+    vectors
+        .iter()
+        .map(|(vec, weight)| {
+            let phase = 2.0 * PI * (hkl[0] * vec[0] + hkl[1] * vec[1] + hkl[2] * vec[2]);
+            weight * phase.cos()
+        })
+        .sum()
+}
+
+/// calculates the diffuse intensity per unit cell of the correlation model on the grid.
+/// correlations contains one value per pair expansion.
+/// returns Err if the number of correlations doesn't match or if a pair ends on a position which
+/// is not in any of the sites.
+pub fn calculate_diffuse_intensity(
+    group: &IsometryGroup,
+    sites: &[WeightedSite],
+    expansions: &[PairExpansion],
+    correlations: &[f64],
+    grid: HklGrid,
+) -> Result<DiffuseMap> {
+    let vectors = weighted_vectors(group, sites, expansions, correlations)?;
+    let [nx, ny, nz] = grid.shape;
+    let mut values = Vec::with_capacity(nx * ny * nz);
+    for i in 0..nx {
+        for j in 0..ny {
+            for k in 0..nz {
+                values.push(intensity_at(&vectors, grid.hkl([i, j, k])));
+            }
+        }
+    }
+    Ok(DiffuseMap { grid, values })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{objects::calculate_pairs, Pos3};
+    use nalgebra::Vector3;
+
+    #[test]
+    fn nearest_neighbour_chain() {
+        // a chain with only nearest neighbour correlations has I(h) = 1 + 2 alpha cos(2 pi h)
+        let group = IsometryGroup::from_generators(Vec::new());
+        let bounds = [5, 1, 1].into();
+        let expansions = calculate_pairs(group.clone(), vec![Pos3::origin()], bounds, false);
+        let sites = [WeightedSite::new(&group, Pos3::origin(), 1.0)];
+        let alpha = -0.3;
+        let correlations: Vec<f64> = expansions
+            .iter()
+            .map(|exp| {
+                let vec: Vector3<f64> = exp.vector().into();
+                match vec.x.abs() as i32 {
+                    0 => 1.0,
+                    1 => alpha,
+                    _ => 0.0,
+                }
+            })
+            .collect();
+        let grid = HklGrid::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [11, 1, 1]);
+        let map =
+            calculate_diffuse_intensity(&group, &sites, &expansions, &correlations, grid).unwrap();
+        for i in 0..11 {
+            let h = grid.hkl([i, 0, 0])[0];
+            let expected = 1.0 + 2.0 * alpha * (2.0 * PI * h).cos();
+            assert!((map.get([i, 0, 0]) - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn laue_symmetry() {
+        let config = crate::config::Config::from_file("../files/input/example2").unwrap();
+        let (group, positions) = (config.group().clone(), config.positions().to_vec());
+        let expansions = calculate_pairs(
+            group.clone(),
+            positions.clone(),
+            config.bounds()[0],
+            config.construct_ab_pairs(),
+        );
+        let sites: Vec<_> = positions
+            .iter()
+            .enumerate()
+            .map(|(i, &pos)| WeightedSite::new(&group, pos, 1.0 + i as f64))
+            .collect();
+        let correlations: Vec<f64> = (0..expansions.len())
+            .map(|i| 1.0 / (i + 1) as f64)
+            .collect();
+        let grid = HklGrid::new([-1.0, -0.5, -0.7], [1.0, 0.5, 0.7], [5, 3, 3]);
+        let map =
+            calculate_diffuse_intensity(&group, &sites, &expansions, &correlations, grid).unwrap();
+        for i in 0..5 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    // the group contains -x,y,-z and inversion
+                    let value = map.get([i, j, k]);
+                    assert!((value - map.get([4 - i, 2 - j, 2 - k])).abs() < 1e-9);
+                    assert!((value - map.get([4 - i, j, 2 - k])).abs() < 1e-9);
+                }
+            }
+        }
+    }
+}
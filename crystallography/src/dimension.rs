@@ -0,0 +1,181 @@
+//! # Dimension
+//! This module allows one and two dimensional problems, such as the line group p1m or the plane
+//! groups, to be given in their own dimension.
+//!
+//! The affine space and the groups are generic over the dimension, so these problems are
+//! computed directly with one or two coordinates. A group symbol describes a space group, it can
+//! be used in lower dimensions if its operations keep the missing coordinates fixed, then the
+//! operations are restricted to the remaining coordinates.
+
+use std::fmt::Display;
+
+use anyhow::Result;
+use pest::iterators::Pair as ParserPair;
+use thiserror::Error;
+
+use crate::{
+    affine_space::{coordinate_index, Affine, Matrix, Vector},
+    symmetry::{Isometry, IsometryGroup},
+    Rule,
+};
+
+#[derive(Error, Debug)]
+enum DimensionError {
+    #[error("'{0}' has {1} components but the problem is {2} dimensional")]
+    Components(String, usize, usize),
+    #[error("the operation {0} acts on a coordinate which is missing in {1} dimensions")]
+    Operation(String, usize),
+}
+
+/// the dimension of a problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dimension {
+    /// line groups
+    One,
+    /// plane groups
+    Two,
+    /// space groups
+    #[default]
+    Three,
+}
+
+impl Display for Dimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.count())
+    }
+}
+
+impl Dimension {
+    /// returns the number of coordinates
+    pub fn count(&self) -> usize {
+        match self {
+            Self::One => 1,
+            Self::Two => 2,
+            Self::Three => 3,
+        }
+    }
+
+    /// reads the parsed dimension
+    pub(crate) fn from_parser(pair: ParserPair<Rule>) -> Self {
+        match pair.as_str() {
+            "1" => Self::One,
+            "2" => Self::Two,
+            "3" => Self::Three,
+            _ => unreachable!("enforced by grammar"),
+        }
+    }
+}
+
+/// returns Err if the parsed operation, position or bounds doesn't have one component per
+/// coordinate or if an operation uses a coordinate missing in N dimensions.
+/// matrices always have three components, Seitz symbols are checked when they are restricted
+pub(crate) fn check_components<const N: usize>(pair: &ParserPair<Rule>) -> Result<()> {
+    let components = match pair.as_rule() {
+        Rule::seitz => return Ok(()),
+        Rule::matrix => 3,
+        _ => pair.clone().into_inner().count(),
+    };
+    if components != N {
+        return Err(DimensionError::Components(pair.as_str().to_string(), components, N).into());
+    }
+    let missing = pair
+        .clone()
+        .into_inner()
+        .flat_map(|coefficient| coefficient.into_inner())
+        .filter(|term| matches!(term.as_rule(), Rule::x | Rule::y | Rule::z))
+        .any(|term| coordinate_index(term.as_rule()) >= N);
+    if missing {
+        return Err(DimensionError::Operation(pair.as_str().to_string(), N).into());
+    }
+    Ok(())
+}
+
+impl Isometry {
+    /// returns the operation acting on the first N coordinates.
+    /// returns Err if it doesn't keep the other coordinates fixed or mixes them with the first N
+    pub fn restrict<const N: usize>(&self) -> Result<Isometry<N>> {
+        let (mat, translation) = (self.mat(), self.translation().components());
+        let embeds = (0..3).all(|i| {
+            (0..3).all(|j| (i < N && j < N) || mat.entry(i, j) == ((i == j) as i32).into())
+                && (i < N || translation[i] == 0.into())
+        });
+        if !embeds {
+            return Err(DimensionError::Operation(self.to_string(), N).into());
+        }
+        let mat = Matrix::from(std::array::from_fn(|i| {
+            std::array::from_fn(|j| mat.entry(i, j))
+        }));
+        let translation = Vector::from(std::array::from_fn(|i| translation[i]));
+        Isometry::new(Affine::new(mat, translation))
+    }
+}
+
+impl IsometryGroup {
+    /// returns the group acting on the first N coordinates, see Isometry::restrict
+    pub fn restrict<const N: usize>(&self) -> Result<IsometryGroup<N>> {
+        let operations = self
+            .get_operations()
+            .iter()
+            .map(Isometry::restrict)
+            .collect::<Result<_>>()?;
+        Ok(IsometryGroup::from_generators(operations))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        config::{Config, Input},
+        objects::{from_str, PairExpansion},
+    };
+
+    fn output(string: &str) -> String {
+        from_str(string).unwrap().produce_output()
+    }
+
+    fn multiplicities<const N: usize>(string: &str) -> Vec<usize> {
+        let config: Config<N> = string.parse().unwrap();
+        config.calculate_pairs()[0]
+            .iter()
+            .map(PairExpansion::multiplicity)
+            .collect()
+    }
+
+    #[test]
+    fn line_group() {
+        let string = "Dimension: 1;\nSpace Group:\n-x;\nPositions:\n0;\nBounds:\n4;";
+        let expected = [
+            "              Origin,               Vector, Multiplicity",
+            "                 [0],                  [0],            1",
+            "                 [0],                  [1],            2",
+            "                 [0],                  [2],            1",
+        ];
+        assert_eq!(output(string), expected.join("\n"));
+    }
+
+    #[test]
+    fn plane_group() {
+        let plane = "Dimension: 2;\nSpace Group:\n-y,x;\nPositions:\n0,0;\nBounds:\n3,3;";
+        let space = "Space Group:\n-y,x,z;\nPositions:\n0,0,0;\nBounds:\n3,3,1;";
+        assert_eq!(multiplicities::<2>(plane), multiplicities::<3>(space));
+        assert!(output(plane).contains("[0, 1]"));
+        // operations on the missing coordinate and wrong numbers of components are rejected
+        assert!(from_str(&plane.replace("-y,x;", "-y,z;")).is_err());
+        assert!(from_str(&plane.replace("-y,x;", "-y,x,z;")).is_err());
+        assert!(from_str(&plane.replace("3,3;", "3,3,3;")).is_err());
+        // the dimension of the file has to match the configuration
+        assert!(plane.parse::<Config>().is_err());
+    }
+
+    #[test]
+    fn restricted_symbol() {
+        let symbol = "Dimension: 2;\nSpace Group:\nP4mm;\nPositions:\n0,0;\nBounds:\n3,3;";
+        let operations =
+            "Dimension: 2;\nSpace Group:\n-y,x;\nx,-y;\nPositions:\n0,0;\nBounds:\n3,3;";
+        let input: Input = symbol.parse().unwrap();
+        assert_eq!(input.dimension().count(), 2);
+        assert_eq!(output(symbol), output(operations));
+        // the cubic group mixes the missing coordinate with the others
+        assert!(from_str(&symbol.replace("P4mm", "Pm-3m")).is_err());
+    }
+}
@@ -7,7 +7,7 @@ use std::{
 /// A type for rational numbers
 /// uses i32 internaly to represent the numerator and the denominator
 /// Is allways reduced
-#[derive(Debug, Clone, Copy, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Frac(i32, i32);
 
 impl Frac {
@@ -390,17 +390,9 @@ impl RemAssign<&Frac> for Frac {
     }
 }
 
-impl PartialEq for Frac {
-    fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0 && self.1 == other.1
-    }
-}
-
-impl Eq for Frac {}
-
 impl PartialOrd for Frac {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some((self.0 * other.1).cmp(&(other.0 * self.1)))
+        Some(self.cmp(other))
     }
 }
 
@@ -426,19 +418,20 @@ from_integer!(u16);
 from_integer!(i16);
 from_integer!(i32);
 
-impl Into<f32> for Frac {
-    fn into(self) -> f32 {
-        self.0 as f32 / self.1 as f32
+impl From<Frac> for f32 {
+    fn from(val: Frac) -> Self {
+        val.0 as f32 / val.1 as f32
     }
 }
 
-impl Into<f64> for Frac {
-    fn into(self) -> f64 {
-        self.0 as f64 / self.1 as f64
+impl From<Frac> for f64 {
+    fn from(val: Frac) -> Self {
+        val.0 as f64 / val.1 as f64
     }
 }
 
-fn gcd(a: i32, b: i32) -> i32 {
+/// greatest common divisor, always non-negative
+pub(crate) fn gcd(a: i32, b: i32) -> i32 {
     let mut a: u32 = a.unsigned_abs();
     let mut b: u32 = b.unsigned_abs();
     while b != 0 {
@@ -449,6 +442,14 @@ fn gcd(a: i32, b: i32) -> i32 {
     a as i32
 }
 
+/// least common multiple, always non-negative
+pub(crate) fn lcm(a: i32, b: i32) -> i32 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    (a / gcd(a, b) * b).abs()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
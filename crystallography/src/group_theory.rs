@@ -0,0 +1,460 @@
+//! # Group Theory
+//! This module provides the multiplication table of finite groups and the group theoretic
+//! properties derived from it, such as element orders, conjugacy classes and the centre.
+//!
+//! Space groups are infinite, their table is the table of the factor group modulo the unit
+//! translations, which is how IsometryGroup stores them.
+
+use std::{convert::Infallible, fmt::Display};
+
+use crate::{
+    affine_space::{Bounds, Matrix},
+    symmetry::{Isometry, IsometryGroup, PointGroup, PointGroupElement},
+};
+
+/// the multiplication table of a finite group, elements are referred to by their index
+#[derive(Debug, Clone)]
+pub struct CayleyTable<T> {
+    pub(crate) elements: Vec<T>,
+    pub(crate) table: Vec<Vec<usize>>,
+    identity: usize,
+    inverses: Vec<usize>,
+}
+
+impl<T: Copy + PartialEq> CayleyTable<T> {
+    /// calculates the table of the elements, which must be closed under the multiplication
+    fn new(elements: Vec<T>, mul: impl Fn(T, T) -> T) -> Self {
+        let table = elements
+            .iter()
+            .map(|&a| {
+                elements
+                    .iter()
+                    .map(|&b| {
+                        let product = mul(a, b);
+                        elements
+                            .iter()
+                            .position(|&c| c == product)
+                            .expect("the group is closed")
+                    })
+                    .collect()
+            })
+            .collect::<Vec<Vec<usize>>>();
+        let n = elements.len();
+        let identity = (0..n)
+            .find(|&i| (0..n).all(|j| table[i][j] == j))
+            .expect("a group contains the identity");
+        let inverses = table
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .position(|&product| product == identity)
+                    .expect("every element of a group has an inverse")
+            })
+            .collect();
+        Self {
+            elements,
+            table,
+            identity,
+            inverses,
+        }
+    }
+}
+
+impl<T> CayleyTable<T> {
+    /// returns the elements in the order of the table
+    pub fn elements(&self) -> &[T] {
+        &self.elements
+    }
+
+    /// returns the number of elements
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// returns true if the table has no elements, which never happens for a group
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// returns the index of the product of the elements i and j
+    pub fn product(&self, i: usize, j: usize) -> usize {
+        self.table[i][j]
+    }
+
+    /// returns the index of the identity
+    pub fn identity(&self) -> usize {
+        self.identity
+    }
+
+    /// returns the index of the inverse of the element i
+    pub fn inverse(&self, i: usize) -> usize {
+        self.inverses[i]
+    }
+
+    /// returns the order of the element i, the smallest n with i^n = 1
+    pub fn element_order(&self, i: usize) -> usize {
+        let mut power = i;
+        let mut order = 1;
+        while power != self.identity {
+            power = self.table[power][i];
+            order += 1;
+        }
+        order
+    }
+
+    /// returns the conjugacy classes, each sorted by index and the classes sorted by their first
+    /// element
+    pub fn conjugacy_classes(&self) -> Vec<Vec<usize>> {
+        let mut classes: Vec<Vec<usize>> = Vec::new();
+        for i in 0..self.len() {
+            if classes.iter().any(|class| class.contains(&i)) {
+                continue;
+            }
+            let mut class: Vec<usize> = (0..self.len())
+                .map(|g| self.table[self.table[g][i]][self.inverse(g)])
+                .collect();
+            class.sort();
+            class.dedup();
+            classes.push(class);
+        }
+        classes
+    }
+
+    /// returns the elements which commute with all elements
+    pub fn centre(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&i| (0..self.len()).all(|j| self.table[i][j] == self.table[j][i]))
+            .collect()
+    }
+
+    /// returns true if the elements form a subgroup
+    pub fn is_subgroup(&self, elements: &[usize]) -> bool {
+        !elements.is_empty()
+            && elements.iter().all(|&i| {
+                elements
+                    .iter()
+                    .all(|&j| elements.contains(&self.table[i][j]))
+            })
+    }
+
+    /// returns true if the elements form a normal subgroup, which is a union of conjugacy
+    /// classes
+    pub fn is_normal_subgroup(&self, elements: &[usize]) -> bool {
+        self.is_subgroup(elements)
+            && elements.iter().all(|&h| {
+                (0..self.len())
+                    .all(|g| elements.contains(&self.table[self.table[g][h]][self.inverse(g)]))
+            })
+    }
+
+    /// returns true if all elements commute
+    pub fn is_abelian(&self) -> bool {
+        self.centre().len() == self.len()
+    }
+
+    /// returns which elements belong to the subgroup generated by the elements
+    pub fn generated(&self, generators: &[usize]) -> Vec<bool> {
+        let mut contained = vec![false; self.len()];
+        let mut elements = vec![self.identity()];
+        contained[elements[0]] = true;
+        let mut k = 0;
+        while k < elements.len() {
+            for &g in generators {
+                let product = self.table[elements[k]][g];
+                if !contained[product] {
+                    contained[product] = true;
+                    elements.push(product);
+                }
+            }
+            k += 1;
+        }
+        contained
+    }
+
+    /// returns a generating set of the smallest possible size, which is empty for the trivial
+    /// group. generators of a high order are preferred
+    pub fn minimal_generators(&self) -> Vec<usize> {
+        // elements generating the same cyclic group are interchangeable
+        let mut candidates: Vec<(usize, Vec<bool>)> = Vec::new();
+        for i in 0..self.len() {
+            let cyclic = self.generated(&[i]);
+            if !candidates.iter().any(|(_, other)| *other == cyclic) {
+                candidates.push((i, cyclic));
+            }
+        }
+        candidates.sort_by_key(|&(i, _)| std::cmp::Reverse(self.element_order(i)));
+        // conjugating a generating set gives another one, so the first generator only needs
+        // to be taken from each class of conjugate cyclic groups
+        let classes = self.conjugacy_classes();
+        let mut first: Vec<usize> = Vec::new();
+        for (k, (i, _)) in candidates.iter().enumerate() {
+            let class = classes
+                .iter()
+                .find(|class| class.contains(i))
+                .expect("every element is in a class");
+            let is_conjugate = first.iter().any(|&l| {
+                class
+                    .iter()
+                    .any(|&j| candidates[l].1[j] && self.element_order(j) == self.element_order(*i))
+            });
+            if !is_conjugate {
+                first.push(k);
+            }
+        }
+        let mut count = 0;
+        loop {
+            let mut chosen = Vec::new();
+            if self.search_generators(&candidates, &first, count, &mut chosen) {
+                return chosen;
+            }
+            count += 1;
+        }
+    }
+
+    /// depth first search for a generating set with count more elements extending chosen
+    fn search_generators(
+        &self,
+        candidates: &[(usize, Vec<bool>)],
+        first: &[usize],
+        count: usize,
+        chosen: &mut Vec<usize>,
+    ) -> bool {
+        let generated = self.generated(chosen);
+        if count == 0 {
+            return generated.iter().all(|&is_contained| is_contained);
+        }
+        let start = chosen
+            .last()
+            .map(|last| {
+                candidates
+                    .iter()
+                    .position(|(i, _)| i == last)
+                    .expect("generators are candidates")
+                    + 1
+            })
+            .unwrap_or(0);
+        for k in start..candidates.len() {
+            let (i, _) = candidates[k];
+            if generated[i] || (chosen.is_empty() && !first.contains(&k)) {
+                continue;
+            }
+            chosen.push(i);
+            if self.search_generators(candidates, first, count - 1, chosen) {
+                return true;
+            }
+            chosen.pop();
+        }
+        false
+    }
+}
+
+/// closes the elements under the multiplication, which must already reduce the products such as
+/// modulo the unit translations. duplicated elements are removed.
+/// panics if this cannot be done within 10_000 iterations to prevent an infinite loop.
+pub fn close<T: PartialEq>(elements: Vec<T>, mul: impl Fn(&T, &T) -> T) -> Vec<T> {
+    match try_close(elements, mul, |_| Ok::<(), Infallible>(())) {
+        Ok(elements) => elements,
+        Err(never) => match never {},
+    }
+}
+
+/// closes the elements under the multiplication like close, every new product is checked first.
+/// returns the first Err of the check, such as for an element of infinite order.
+pub fn try_close<T: PartialEq, E>(
+    elements: Vec<T>,
+    mul: impl Fn(&T, &T) -> T,
+    check: impl Fn(&T) -> Result<(), E>,
+) -> Result<Vec<T>, E> {
+    let mut symmetries: Vec<T> = Vec::new();
+    for op in elements {
+        if !symmetries.contains(&op) {
+            symmetries.push(op)
+        }
+    }
+    let mut counter = 0;
+    let mut added_new = true;
+    while added_new && counter < 10_000 {
+        counter += 1;
+        added_new = false;
+        for i in 0..symmetries.len() {
+            for j in 0..symmetries.len() {
+                let op = mul(&symmetries[i], &symmetries[j]);
+                if !symmetries.contains(&op) {
+                    check(&op)?;
+                    symmetries.push(op);
+                    added_new = true;
+                }
+            }
+        }
+    }
+    if counter >= 10_000 {
+        panic!("didn't manage to close group within 10'000 iterations");
+    }
+    Ok(symmetries)
+}
+
+impl<T: Display> Display for CayleyTable<T> {
+    /// writes a legend of the elements with their order and conjugacy class followed by the
+    /// table of indices
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let classes = self.conjugacy_classes();
+        let width = self.len().to_string().len();
+        let names: Vec<String> = self.elements.iter().map(|el| el.to_string()).collect();
+        let name_width = names.iter().map(String::len).max().unwrap_or(0);
+        writeln!(
+            f,
+            "{:>width$}  {:<name_width$}  order  class",
+            "#", "element"
+        )?;
+        for (i, name) in names.iter().enumerate() {
+            let class = classes
+                .iter()
+                .position(|class| class.contains(&i))
+                .expect("every element is in a class");
+            writeln!(
+                f,
+                "{:>width$}  {:<name_width$}  {:>5}  {:>5}",
+                i + 1,
+                name,
+                self.element_order(i),
+                class + 1
+            )?;
+        }
+        writeln!(f)?;
+        write!(f, "{:>width$} |", "")?;
+        for j in 0..self.len() {
+            write!(f, " {:>width$}", j + 1)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}", "-".repeat((width + 1) * (self.len() + 1) + 1))?;
+        for (i, row) in self.table.iter().enumerate() {
+            write!(f, "{:>width$} |", i + 1)?;
+            for &k in row {
+                write!(f, " {:>width$}", k + 1)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl PointGroup {
+    /// returns the multiplication table of the point group
+    pub fn cayley_table(&self) -> CayleyTable<PointGroupElement> {
+        CayleyTable::new(self.iter().copied().collect(), |a, b| a * b)
+    }
+}
+
+impl<const N: usize> IsometryGroup<N> {
+    /// returns the multiplication table of the group modulo unit translations
+    pub fn cayley_table(&self) -> CayleyTable<Isometry<N>> {
+        CayleyTable::new(self.get_operations().to_vec(), |a, b| {
+            (a * b) % Bounds::splat(1)
+        })
+    }
+
+    /// returns the multiplication table of the distinct linear parts of the operations, which
+    /// form the point group in any dimension
+    pub fn linear_part_table(&self) -> CayleyTable<Matrix<N>> {
+        let mut mats: Vec<Matrix<N>> = self.get_operations().iter().map(Isometry::mat).collect();
+        mats.sort();
+        mats.dedup();
+        CayleyTable::new(mats, |a, b| a * b)
+    }
+
+    /// returns a smallest set of operations which generates the group modulo unit translations,
+    /// reading them with from_generators gives back the group.
+    /// the trivial group is generated by the identity
+    pub fn generators(&self) -> Vec<Isometry<N>> {
+        let table = self.cayley_table();
+        let generators = table.minimal_generators();
+        if generators.is_empty() {
+            return vec![table.elements()[table.identity()]];
+        }
+        generators.iter().map(|&i| table.elements()[i]).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn point_group_table() {
+        let sg = IsometryGroup::from_file("../files/space_groups/Fm-3m").unwrap();
+        let table = sg.reduce_to_point_group().cayley_table();
+        assert_eq!(table.len(), 48);
+        // m-3m has the ten classes 1, 3, 2 (x2), 4, 2 (x3), -1, -3, m (x2), -4, m (x3)
+        let mut sizes: Vec<usize> = table.conjugacy_classes().iter().map(Vec::len).collect();
+        sizes.sort();
+        assert_eq!(sizes, [1, 1, 3, 3, 6, 6, 6, 6, 8, 8]);
+        // the centre is generated by the inversion
+        assert_eq!(table.centre().len(), 2);
+        let mut orders: Vec<usize> = (0..48).map(|i| table.element_order(i)).collect();
+        orders.sort();
+        orders.dedup();
+        assert_eq!(orders, [1, 2, 3, 4, 6]);
+        assert!(!table.is_abelian());
+    }
+
+    #[test]
+    fn normal_subgroups() {
+        let sg = IsometryGroup::from_file("../files/space_groups/P6_3|mmc").unwrap();
+        let table = sg.cayley_table();
+        assert_eq!(table.len(), 24);
+        let translations: Vec<usize> = vec![table.identity()];
+        assert!(table.is_normal_subgroup(&translations));
+        // the operations with rotations around c form a normal subgroup
+        let rotations: Vec<usize> = (0..table.len())
+            .filter(|&i| {
+                let mat = table.elements()[i].mat();
+                mat.determinant() == 1.into()
+                    && mat * crate::Vec3::from([0, 0, 1]) == [0, 0, 1].into()
+            })
+            .collect();
+        assert_eq!(rotations.len(), 6);
+        assert!(table.is_normal_subgroup(&rotations));
+        // a single two-fold axis perpendicular to c is not normal
+        let two_fold = (0..table.len())
+            .find(|&i| {
+                table.element_order(i) == 2
+                    && table.elements()[i].mat().determinant() == 1.into()
+                    && table.elements()[i].mat() * crate::Vec3::from([0, 0, 1]) == [0, 0, -1].into()
+            })
+            .unwrap();
+        let subgroup = [table.identity(), two_fold];
+        assert!(table.is_subgroup(&subgroup));
+        assert!(!table.is_normal_subgroup(&subgroup));
+        let string = table.to_string();
+        assert!(string.starts_with(" #  element"));
+        assert_eq!(string.lines().count(), 1 + 24 + 1 + 2 + 24);
+    }
+
+    #[test]
+    fn generators() {
+        use crate::{symmetry::Notation, MyParser, Rule};
+        use pest::Parser;
+        for (name, count) in [
+            ("P-1", 1),
+            ("P2_12_12_1", 2),
+            ("Pmna", 3),
+            ("P6_3|mmc", 3),
+            ("Fm-3m", 2),
+        ] {
+            let sg = IsometryGroup::from_file(format!("../files/space_groups/{}", name)).unwrap();
+            let generators = sg.generators();
+            assert_eq!(generators.len(), count, "{}", name);
+            let string = sg.display_as(Notation::Seitz).generators_only().to_string();
+            assert_eq!(string.lines().count(), count);
+            let parsed = MyParser::parse(Rule::affine_list, &string)
+                .unwrap()
+                .next()
+                .unwrap();
+            let read = IsometryGroup::from_affine_list(parsed.into_inner()).unwrap();
+            assert_eq!(read.len(), sg.len());
+            assert!(read.get_operations().iter().all(|&op| sg.contains(op)));
+        }
+        let p1: IsometryGroup = IsometryGroup::from_generators(Vec::new());
+        assert_eq!(p1.generators().len(), 1);
+    }
+}
@@ -0,0 +1,509 @@
+//! # Lattice
+//! This module describes the metric of a unit cell, reduces cells to the Niggli reduced cell and
+//! determines the Bravais lattice.
+//!
+//! Lattice parameters are measured quantities, so the metric is given in floating point and
+//! compared with a tolerance, while the transformations between cells are exact integer
+//! matrices P with (a', b', c') = (a, b, c) P as in the transformation module.
+//! The Niggli reduction follows the algorithm of Krivy and Gruber (1976). The Bravais lattice is
+//! determined from the symmetry of the reduced cell, whose matrices only have the entries -1, 0
+//! and 1, and the conventional cell is built from its rotation axes.
+
+use std::fmt::Display;
+
+use anyhow::Result;
+use nalgebra::{Matrix3, SVector};
+use thiserror::Error;
+
+use crate::{
+    affine_space::Vector,
+    centering::Centering,
+    crystal_class::CrystalSystem,
+    frac::gcd,
+    symmetry::{Isometry, IsometryGroup},
+    Mat3, Vec3,
+};
+
+/// relative tolerance for comparing entries of metrics
+const TOLERANCE: f64 = 1e-5;
+
+#[derive(Error, Debug)]
+enum LatticeError {
+    #[error("the metric is not symmetric and positive definite")]
+    NotPositiveDefinite,
+    #[error("the Niggli reduction did not converge")]
+    NotConverged,
+    #[error("no conventional cell was found for the lattice symmetry of order {0}")]
+    NoConventionalCell(usize),
+}
+
+/// the metric tensor G of a unit cell, with the scalar products of the basis vectors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metric(Matrix3<f64>);
+
+impl Metric {
+    /// creates the metric from the lattice parameters, with the angles in degrees.
+    /// returns Err if they do not describe a cell
+    pub fn from_parameters(
+        a: f64,
+        b: f64,
+        c: f64,
+        alpha: f64,
+        beta: f64,
+        gamma: f64,
+    ) -> Result<Self> {
+        let [cos_alpha, cos_beta, cos_gamma] = [alpha, beta, gamma].map(|x| x.to_radians().cos());
+        Self::from_mat(Matrix3::new(
+            a * a,
+            a * b * cos_gamma,
+            a * c * cos_beta,
+            a * b * cos_gamma,
+            b * b,
+            b * c * cos_alpha,
+            a * c * cos_beta,
+            b * c * cos_alpha,
+            c * c,
+        ))
+    }
+
+    /// creates the metric from the matrix of scalar products.
+    /// returns Err if it is not symmetric and positive definite
+    pub fn from_mat(mat: Matrix3<f64>) -> Result<Self> {
+        let is_symmetric = (mat - mat.transpose()).abs().max() <= TOLERANCE * mat.abs().max();
+        // a flat cell has a vanishing volume
+        let is_flat = mat.determinant() <= TOLERANCE * mat.diagonal().product();
+        if !is_symmetric || is_flat || mat.cholesky().is_none() {
+            return Err(LatticeError::NotPositiveDefinite.into());
+        }
+        Ok(Self(mat))
+    }
+
+    /// returns the matrix of scalar products
+    pub fn mat(&self) -> Matrix3<f64> {
+        self.0
+    }
+
+    /// returns the lattice parameters a, b, c, alpha, beta, gamma with the angles in degrees
+    pub fn parameters(&self) -> [f64; 6] {
+        let g = &self.0;
+        let [a, b, c] = [g[(0, 0)], g[(1, 1)], g[(2, 2)]].map(f64::sqrt);
+        let angle = |dot: f64, x: f64, y: f64| (dot / (x * y)).acos().to_degrees();
+        [
+            a,
+            b,
+            c,
+            angle(g[(1, 2)], b, c),
+            angle(g[(0, 2)], a, c),
+            angle(g[(0, 1)], a, b),
+        ]
+    }
+
+    /// returns the volume of the cell
+    pub fn volume(&self) -> f64 {
+        self.0.determinant().sqrt()
+    }
+
+    /// returns the length of the vector, vectors of lower dimensions use the upper left block of
+    /// the metric
+    pub fn length<const N: usize>(&self, vec: Vector<N>) -> f64 {
+        let vec: SVector<f64, N> = vec.into();
+        vec.dot(&(self.0.fixed_view::<N, N>(0, 0) * vec)).sqrt()
+    }
+
+    /// returns the metric of the cell with the basis (a, b, c) P
+    pub fn transform(&self, mat: Mat3) -> Self {
+        let mat: Matrix3<f64> = mat.into();
+        Self(mat.transpose() * self.0 * mat)
+    }
+
+    /// returns the Niggli reduced cell and the integer matrix P with determinant 1 which
+    /// transforms this cell to it.
+    /// returns Err if the reduction does not converge
+    pub fn niggli_reduce(&self) -> Result<(Metric, Mat3)> {
+        let eps = TOLERANCE * self.0.trace() / 3.0;
+        let sign = |x: f64| {
+            if x > eps {
+                1
+            } else if x < -eps {
+                -1
+            } else {
+                0
+            }
+        };
+        let mut transformation = Mat3::identity();
+        for _ in 0..1000 {
+            let g = self.transform(transformation).0;
+            let (a, b, c) = (g[(0, 0)], g[(1, 1)], g[(2, 2)]);
+            let (xi, eta, zeta) = (2.0 * g[(1, 2)], 2.0 * g[(0, 2)], 2.0 * g[(0, 1)]);
+            let (l, m, n) = (sign(xi), sign(eta), sign(zeta));
+            let step: Option<[i32; 9]> =
+                if a > b + eps || ((a - b).abs() <= eps && xi.abs() > eta.abs() + eps) {
+                    Some([0, -1, 0, -1, 0, 0, 0, 0, -1])
+                } else if b > c + eps || ((b - c).abs() <= eps && eta.abs() > zeta.abs() + eps) {
+                    Some([-1, 0, 0, 0, 0, -1, 0, -1, 0])
+                } else if l * m * n == 1 {
+                    // all angles acute
+                    let flip = |s: i32| if s == -1 { -1 } else { 1 };
+                    let (i, j, k) = (flip(l), flip(m), flip(n));
+                    ((i, j, k) != (1, 1, 1)).then_some([i, 0, 0, 0, j, 0, 0, 0, k])
+                } else {
+                    // all angles obtuse or right
+                    let mut signs = [l, m, n].map(|s| if s == 1 { -1 } else { 1 });
+                    if signs.iter().product::<i32>() == -1 {
+                        let zero = [l, m, n]
+                            .iter()
+                            .position(|&s| s == 0)
+                            .expect("an odd number of acute angles requires a right angle");
+                        signs[zero] = -1;
+                    }
+                    (signs != [1, 1, 1]).then_some([signs[0], 0, 0, 0, signs[1], 0, 0, 0, signs[2]])
+                };
+            let step = step.or_else(|| {
+                let s = |x: f64| if x > 0.0 { 1 } else { -1 };
+                if xi.abs() > b + eps
+                    || ((xi - b).abs() <= eps && 2.0 * eta < zeta - eps)
+                    || ((xi + b).abs() <= eps && zeta < -eps)
+                {
+                    Some([1, 0, 0, 0, 1, -s(xi), 0, 0, 1])
+                } else if eta.abs() > a + eps
+                    || ((eta - a).abs() <= eps && 2.0 * xi < zeta - eps)
+                    || ((eta + a).abs() <= eps && zeta < -eps)
+                {
+                    Some([1, 0, -s(eta), 0, 1, 0, 0, 0, 1])
+                } else if zeta.abs() > a + eps
+                    || ((zeta - a).abs() <= eps && 2.0 * xi < eta - eps)
+                    || ((zeta + a).abs() <= eps && eta < -eps)
+                {
+                    Some([1, -s(zeta), 0, 0, 1, 0, 0, 0, 1])
+                } else if xi + eta + zeta + a + b < -eps
+                    || ((xi + eta + zeta + a + b).abs() <= eps && 2.0 * (a + eta) + zeta > eps)
+                {
+                    Some([1, 0, 1, 0, 1, 1, 0, 0, 1])
+                } else {
+                    None
+                }
+            });
+            match step {
+                Some(step) => transformation = transformation * Mat3::from(step),
+                None => return Ok((self.transform(transformation), transformation)),
+            }
+        }
+        Err(LatticeError::NotConverged.into())
+    }
+
+    /// returns the matrices with entries -1, 0 and 1 which leave the metric invariant, for a
+    /// reduced cell these are all symmetries of the lattice
+    fn symmetries(&self) -> Vec<Mat3> {
+        let eps = TOLERANCE * self.0.trace() / 3.0;
+        (0..3_i32.pow(9))
+            .map(|i| {
+                Mat3::from(std::array::from_fn::<i32, 9, _>(|k| {
+                    (i / 3_i32.pow(k as u32)) % 3 - 1
+                }))
+            })
+            .filter(|mat| {
+                mat.determinant().abs() == 1.into()
+                    && (self.transform(*mat).0 - self.0).abs().max() <= eps
+            })
+            .collect()
+    }
+
+    /// determines the Bravais lattice and returns it together with the integer matrix P which
+    /// transforms this cell to the conventional cell, rhombohedral lattices are given in the
+    /// obverse setting on hexagonal axes.
+    /// returns Err if the cell can't be reduced
+    pub fn bravais_lattice(&self) -> Result<(BravaisLattice, Mat3)> {
+        let (reduced, to_reduced) = self.niggli_reduce()?;
+        let symmetries = reduced.symmetries();
+        let rotations: Vec<Mat3> = symmetries
+            .iter()
+            .copied()
+            .filter(|mat| mat.determinant() == 1.into())
+            .collect();
+        let axes = |order: usize| -> Vec<(Mat3, Vec3)> {
+            let mut axes: Vec<(Mat3, Vec3)> = Vec::new();
+            for &mat in &rotations {
+                if rotation_order(mat) != order {
+                    continue;
+                }
+                let axis = rotation_axis(mat);
+                if !axes
+                    .iter()
+                    .any(|(_, other)| *other == axis || *other == -axis)
+                {
+                    axes.push((mat, axis));
+                }
+            }
+            axes
+        };
+        let system = match symmetries.len() {
+            2 => CrystalSystem::Triclinic,
+            4 => CrystalSystem::Monoclinic,
+            8 => CrystalSystem::Orthorhombic,
+            16 => CrystalSystem::Tetragonal,
+            12 => CrystalSystem::Trigonal,
+            24 => CrystalSystem::Hexagonal,
+            48 => CrystalSystem::Cubic,
+            order => return Err(LatticeError::NoConventionalCell(order).into()),
+        };
+        // candidates for the conventional basis as columns, the one with the smallest cell is
+        // taken
+        let candidates: Vec<Mat3> = match system {
+            CrystalSystem::Triclinic => vec![Mat3::identity()],
+            CrystalSystem::Monoclinic => {
+                let (two_fold, b) = axes(2)[0];
+                // the shortest lattice vectors perpendicular to the two-fold axis
+                let mut perpendicular: Vec<Vec3> = small_vectors()
+                    .into_iter()
+                    .filter(|&vec| two_fold * vec == -vec)
+                    .collect();
+                perpendicular.sort_by(|&u, &v| reduced.length(u).total_cmp(&reduced.length(v)));
+                let a = perpendicular[0];
+                let c = *perpendicular
+                    .iter()
+                    .find(|&&vec| vec != a && vec != -a)
+                    .expect("the plane is two dimensional");
+                vec![basis(a, b, c)]
+            }
+            CrystalSystem::Orthorhombic => {
+                let axes = axes(2);
+                vec![basis(axes[0].1, axes[1].1, axes[2].1)]
+            }
+            CrystalSystem::Tetragonal | CrystalSystem::Trigonal | CrystalSystem::Hexagonal => {
+                let main_order = match system {
+                    CrystalSystem::Tetragonal => 4,
+                    CrystalSystem::Trigonal => 3,
+                    _ => 6,
+                };
+                let (main, c) = axes(main_order)[0];
+                // the rotation by 90 or 120 degrees which relates a and b
+                let rotation = if main_order == 6 { main * main } else { main };
+                axes(2)
+                    .into_iter()
+                    .filter(|&(_, axis)| axis != c && axis != -c)
+                    .flat_map(|(_, a)| [a, -a])
+                    .map(|a| basis(a, rotation * a, c))
+                    .collect()
+            }
+            CrystalSystem::Cubic => {
+                let axes = axes(4);
+                vec![basis(axes[0].1, axes[1].1, axes[2].1)]
+            }
+        };
+        let (centering, conventional) = candidates
+            .into_iter()
+            .filter_map(|mat| {
+                let centering = centering_of(mat)?;
+                let standard = standard_centering(system, centering, mat)?;
+                Some((centering_of(standard)?, standard))
+            })
+            .min_by_key(|(centering, _)| centering.lattice_points())
+            .ok_or(LatticeError::NoConventionalCell(symmetries.len()))?;
+        Ok((
+            BravaisLattice { system, centering },
+            to_reduced * conventional,
+        ))
+    }
+}
+
+/// one of the 14 Bravais lattices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BravaisLattice {
+    system: CrystalSystem,
+    centering: Centering,
+}
+
+impl BravaisLattice {
+    /// returns the crystal system of the conventional cell, trigonal for rhombohedral lattices
+    pub fn crystal_system(&self) -> CrystalSystem {
+        self.system
+    }
+
+    /// returns the centering of the conventional cell
+    pub fn centering(&self) -> Centering {
+        self.centering
+    }
+}
+
+impl Display for BravaisLattice {
+    /// writes the Pearson symbol such as cF or hR, C centering is written as S
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let family = match self.system {
+            CrystalSystem::Triclinic => "a",
+            CrystalSystem::Monoclinic => "m",
+            CrystalSystem::Orthorhombic => "o",
+            CrystalSystem::Tetragonal => "t",
+            CrystalSystem::Trigonal | CrystalSystem::Hexagonal => "h",
+            CrystalSystem::Cubic => "c",
+        };
+        let centering = match self.centering {
+            Centering::A | Centering::B | Centering::C => "S".to_string(),
+            centering => centering.to_string(),
+        };
+        write!(f, "{}{}", family, centering)
+    }
+}
+
+/// returns the matrix with the vectors as columns, with a positive determinant
+fn basis(a: Vec3, b: Vec3, c: Vec3) -> Mat3 {
+    let mat = Mat3::from_columns(a, b, c);
+    if mat.determinant() < 0.into() {
+        Mat3::from_columns(-a, -b, -c)
+    } else {
+        mat
+    }
+}
+
+/// returns the order of the rotation
+fn rotation_order(mat: Mat3) -> usize {
+    let mut power = mat;
+    let mut order = 1;
+    while power != Mat3::identity() {
+        power = power * mat;
+        order += 1;
+    }
+    order
+}
+
+/// returns the shortest lattice vector along the axis of the rotation
+fn rotation_axis(mat: Mat3) -> Vec3 {
+    small_vectors()
+        .into_iter()
+        .filter(|&vec| mat * vec == vec)
+        .min_by_key(|vec| {
+            [vec.x(), vec.y(), vec.z()]
+                .iter()
+                .map(|x| x.abs().get_numerator())
+                .sum::<i32>()
+        })
+        .expect("the axes of lattice rotations in a reduced cell have small indices")
+}
+
+/// returns the nonzero vectors with entries between -2 and 2 and coprime entries
+fn small_vectors() -> Vec<Vec3> {
+    (0..125)
+        .map(|i| [i / 25 - 2, i / 5 % 5 - 2, i % 5 - 2])
+        .filter(|v| v.iter().fold(0, |acc, &x: &i32| gcd(acc, x.abs())) == 1)
+        .map(Vec3::from)
+        .collect()
+}
+
+/// returns the centering of the cell with the basis vectors as columns
+fn centering_of(mat: Mat3) -> Option<Centering> {
+    let inverse = mat.inverse()?;
+    let translations = [[1, 0, 0], [0, 1, 0], [0, 0, 1]]
+        .map(|vec| Isometry::from_translation(inverse * Vec3::from(vec)))
+        .to_vec();
+    IsometryGroup::from_generators(translations)
+        .centering()
+        .ok()
+}
+
+/// changes A, B and I centered monoclinic and A and B centered orthorhombic cells to C
+/// centered ones, returns None for centerings which are not used by the crystal system
+fn standard_centering(system: CrystalSystem, centering: Centering, mat: Mat3) -> Option<Mat3> {
+    let column = |i: usize| {
+        let mut vec = [0; 3];
+        vec[i] = 1;
+        mat * Vec3::from(vec)
+    };
+    let (a, b, c) = (column(0), column(1), column(2));
+    match (system, centering) {
+        (CrystalSystem::Triclinic, Centering::P) => Some(mat),
+        (CrystalSystem::Monoclinic, Centering::P | Centering::C) => Some(mat),
+        (CrystalSystem::Monoclinic, Centering::A) => Some(basis(c, -b, a)),
+        (CrystalSystem::Monoclinic, Centering::I) => Some(basis(a + c, b, c)),
+        (CrystalSystem::Orthorhombic, Centering::A) => Some(basis(b, c, a)),
+        (CrystalSystem::Orthorhombic, Centering::B) => Some(basis(c, a, b)),
+        (CrystalSystem::Orthorhombic, Centering::C)
+        | (CrystalSystem::Orthorhombic, Centering::P)
+        | (CrystalSystem::Orthorhombic, Centering::I)
+        | (CrystalSystem::Orthorhombic, Centering::F)
+        | (CrystalSystem::Tetragonal, Centering::P)
+        | (CrystalSystem::Tetragonal, Centering::I)
+        | (CrystalSystem::Trigonal, Centering::R)
+        | (CrystalSystem::Hexagonal, Centering::P)
+        | (CrystalSystem::Cubic, Centering::P)
+        | (CrystalSystem::Cubic, Centering::I)
+        | (CrystalSystem::Cubic, Centering::F) => Some(mat),
+        _ => None,
+    }
+}
+
+impl From<Metric> for Matrix3<f64> {
+    fn from(val: Metric) -> Self {
+        val.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_parameters(metric: &Metric, expected: [f64; 6]) {
+        let parameters = metric.parameters();
+        assert!(
+            parameters
+                .iter()
+                .zip(expected)
+                .all(|(x, y)| (x - y).abs() < 1e-6),
+            "{:?} != {:?}",
+            parameters,
+            expected
+        );
+    }
+
+    #[test]
+    fn niggli() {
+        // a primitive cell of the fcc lattice given by an odd choice of basis vectors
+        let cubic = Metric::from_parameters(4.0, 4.0, 4.0, 90.0, 90.0, 90.0).unwrap();
+        let odd = cubic
+            .transform(Centering::F.to_primitive().mat() * Mat3::from([1, 2, 0, 0, 1, 1, 0, 0, 1]));
+        let (reduced, transformation) = odd.niggli_reduce().unwrap();
+        let edge = 8.0_f64.sqrt();
+        assert_parameters(&reduced, [edge, edge, edge, 60.0, 60.0, 60.0]);
+        assert_eq!(transformation.determinant(), 1.into());
+        assert_eq!(odd.transform(transformation), reduced);
+        assert!((reduced.volume() - odd.volume()).abs() < 1e-9);
+        // a reduced cell stays unchanged
+        let triclinic = Metric::from_parameters(3.0, 4.0, 5.0, 80.0, 85.0, 70.0).unwrap();
+        let (reduced, transformation) = triclinic.niggli_reduce().unwrap();
+        assert_eq!(transformation, Mat3::identity());
+        assert_eq!(reduced, triclinic);
+        assert!(Metric::from_parameters(1.0, 1.0, 1.0, 120.0, 120.0, 120.0).is_err());
+    }
+
+    #[test]
+    fn bravais_lattices() {
+        let lattice = |[a, b, c, alpha, beta, gamma]: [f64; 6], centering: Centering| {
+            let conventional = Metric::from_parameters(a, b, c, alpha, beta, gamma).unwrap();
+            // a primitive cell with an odd choice of basis vectors
+            let odd = Mat3::from([1, 1, 0, 0, 1, 1, 0, 0, 1]);
+            let primitive = conventional.transform(centering.to_primitive().mat() * odd);
+            let (bravais, transformation) = primitive.bravais_lattice().unwrap();
+            let found = primitive.transform(transformation);
+            assert!((found.volume() - conventional.volume()).abs() < 1e-6);
+            bravais.to_string()
+        };
+        let triclinic = [3.0, 4.0, 5.0, 80.0, 85.0, 70.0];
+        let monoclinic = [3.0, 4.0, 5.0, 90.0, 100.0, 90.0];
+        let orthorhombic = [3.0, 4.0, 5.0, 90.0, 90.0, 90.0];
+        let tetragonal = [3.0, 3.0, 5.0, 90.0, 90.0, 90.0];
+        let hexagonal = [3.0, 3.0, 7.0, 90.0, 90.0, 120.0];
+        let cubic = [3.0, 3.0, 3.0, 90.0, 90.0, 90.0];
+        assert_eq!(lattice(triclinic, Centering::P), "aP");
+        assert_eq!(lattice(monoclinic, Centering::P), "mP");
+        assert_eq!(lattice(monoclinic, Centering::C), "mS");
+        assert_eq!(lattice(orthorhombic, Centering::P), "oP");
+        assert_eq!(lattice(orthorhombic, Centering::A), "oS");
+        assert_eq!(lattice(orthorhombic, Centering::I), "oI");
+        assert_eq!(lattice(orthorhombic, Centering::F), "oF");
+        assert_eq!(lattice(tetragonal, Centering::P), "tP");
+        assert_eq!(lattice(tetragonal, Centering::I), "tI");
+        assert_eq!(lattice(hexagonal, Centering::P), "hP");
+        assert_eq!(lattice(hexagonal, Centering::R), "hR");
+        assert_eq!(lattice(cubic, Centering::P), "cP");
+        assert_eq!(lattice(cubic, Centering::I), "cI");
+        assert_eq!(lattice(cubic, Centering::F), "cF");
+    }
+}
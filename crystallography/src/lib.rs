@@ -1,12 +1,34 @@
 //! This crate provides types and methods to work with crystallographic groups
 #![warn(missing_docs)]
 pub mod affine_space;
+pub mod centering;
+pub mod config;
+pub mod crystal_class;
+pub mod diffuse;
+pub mod dimension;
 mod frac;
+pub mod group_theory;
+pub mod lattice;
 pub(crate) mod macros;
+pub mod normalizer;
+pub mod numeric;
 pub mod objects;
+pub mod orbits;
 pub(crate) mod parsers;
+pub mod pdf;
+pub mod reflections;
+pub mod representations;
+pub mod snapping;
+pub mod space_group_symbols;
+pub mod subgroups;
+pub mod superspace;
 pub mod symmetry;
+pub mod symmetry_elements;
+pub mod transformation;
+pub mod validation;
 
-pub use affine_space::{Affine3, Bounds3, Mat3, Pos3, Vec3};
+pub use affine_space::{
+    Affine, Affine3, Bounds, Bounds3, Mat3, Matrix, Pos3, Position, RecVec3, Vec3, Vector,
+};
 pub use frac::Frac;
 pub(crate) use parsers::{MyParser, Rule};
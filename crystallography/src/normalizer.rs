@@ -0,0 +1,467 @@
+//! # Euclidean Normalizer
+//! This module calculates the Euclidean normalizer of a space group, the isometries which map
+//! the group onto itself, and uses it to bring inputs into a canonical form.
+//!
+//! Descriptions of a structure related by an element of the normalizer, such as an origin
+//! shift by 1/2,1/2,1/2 in P-1, are equivalent. The canonical form is the description with the
+//! smallest sorted list of site representatives, each site being represented by the smallest
+//! position of its orbit, so equivalent inputs give identical pair tables.
+//!
+//! The normalizer is calculated for a generic metric of the crystal family, special metrics
+//! with a higher symmetry are not considered. Its translations are found by solving the
+//! congruences which state that conjugating the generators gives elements of the group. The
+//! continuous translations of polar groups are returned separately and used to move a position
+//! onto the origin along the polar directions.
+
+use crate::{
+    affine_space::{Affine, Bounds, Matrix, Position, Vector},
+    subgroups::is_integral,
+    symmetry::{Isometry, IsometryGroup},
+    Frac,
+};
+
+/// a positive definite metric without any symmetry, averaging it over a point group gives a
+/// generic metric of the crystal family. lower dimensions use its upper left block
+const GENERIC_METRIC: [[i32; 3]; 3] = [[7, 2, 1], [2, 5, 3], [1, 3, 11]];
+
+/// the solutions t of a system of congruences A t = c modulo integer vectors with integral A
+struct Congruences<const N: usize> {
+    /// the solutions modulo unit translations without a continuous part
+    discrete: Vec<Vector<N>>,
+    /// the unimodular basis in which the system is diagonal, the columns from the rank on span
+    /// the continuous solutions
+    basis: [[i32; N]; N],
+    rank: usize,
+}
+
+impl<const N: usize> Congruences<N> {
+    /// diagonalizes the system with integral row and column operations and returns None if
+    /// there is no solution
+    fn solve(equations: &[(Matrix<N>, Vector<N>)]) -> Option<Self> {
+        let mut rows: Vec<[i32; N]> = Vec::new();
+        let mut rhs: Vec<Frac> = Vec::new();
+        for &(mat, vec) in equations {
+            for (i, c) in vec.components().into_iter().enumerate() {
+                rows.push(std::array::from_fn(|j| {
+                    debug_assert_eq!(mat.entry(i, j).get_denominator(), 1);
+                    mat.entry(i, j).get_numerator()
+                }));
+                rhs.push(c);
+            }
+        }
+        let mut basis: [[i32; N]; N] =
+            std::array::from_fn(|i| std::array::from_fn(|j| (i == j) as i32));
+        let mut rank = 0;
+        while rank < N {
+            let k = rank;
+            let Some((i, j)) = (k..rows.len())
+                .flat_map(|i| (k..N).map(move |j| (i, j)))
+                .filter(|&(i, j)| rows[i][j] != 0)
+                .min_by_key(|&(i, j)| rows[i][j].abs())
+            else {
+                break;
+            };
+            rows.swap(k, i);
+            rhs.swap(k, i);
+            rows.iter_mut().for_each(|row| row.swap(k, j));
+            basis.iter_mut().for_each(|row| row.swap(k, j));
+            // reduces the row and column of the pivot, a smaller remainder becomes the next pivot
+            let pivot = rows[k][k];
+            let mut reduced = true;
+            for i in k + 1..rows.len() {
+                let factor = rows[i][k] / pivot;
+                let pivot_row = rows[k];
+                for (val, pivot_val) in rows[i].iter_mut().zip(pivot_row) {
+                    *val -= factor * pivot_val;
+                }
+                rhs[i] = rhs[i] - Frac::from(factor) * rhs[k];
+                reduced &= rows[i][k] == 0;
+            }
+            for j in k + 1..N {
+                let factor = rows[k][j] / pivot;
+                rows.iter_mut().for_each(|row| row[j] -= factor * row[k]);
+                basis.iter_mut().for_each(|row| row[j] -= factor * row[k]);
+                reduced &= rows[k][j] == 0;
+            }
+            if reduced {
+                rank += 1;
+            }
+        }
+        if rhs[rank..].iter().any(|c| c.get_denominator() != 1) {
+            return None;
+        }
+        // d y = c has the solutions (c + n) / d modulo 1 in the diagonal coordinates y
+        let mut solutions = vec![[Frac::from(0); N]];
+        for k in 0..rank {
+            let (pivot, c) = (rows[k][k], rhs[k]);
+            solutions = solutions
+                .into_iter()
+                .flat_map(|y| {
+                    (0..pivot.abs()).map(move |n| {
+                        let mut y = y;
+                        y[k] = (c + Frac::from(n)) / Frac::from(pivot);
+                        y
+                    })
+                })
+                .collect();
+        }
+        let mut congruences = Self {
+            discrete: Vec::new(),
+            basis,
+            rank,
+        };
+        let mat = congruences.basis_mat();
+        congruences.discrete = solutions
+            .into_iter()
+            .map(|y| (mat * Vector::from(y)) % Bounds::splat(1))
+            .collect();
+        Some(congruences)
+    }
+
+    fn basis_mat(&self) -> Matrix<N> {
+        Matrix::from(self.basis)
+    }
+
+    /// returns the directions of the continuous solutions
+    fn continuous(&self) -> Vec<Vector<N>> {
+        let mat = self.basis_mat();
+        (self.rank..N).map(|k| mat.column(k)).collect()
+    }
+
+    /// returns the component of the vector along the continuous solutions, using the basis
+    /// of the diagonal system to split it
+    fn continuous_part(&self, vec: Vector<N>) -> Vector<N> {
+        let mat = self.basis_mat();
+        let y = (mat.inverse().expect("the basis is unimodular") * vec).components();
+        mat * Vector::from(std::array::from_fn(|k| match k < self.rank {
+            true => 0.into(),
+            false => y[k],
+        }))
+    }
+}
+
+impl<const N: usize> IsometryGroup<N> {
+    /// returns the translation vectors of the group in the first unit cell
+    fn lattice_translations(&self) -> Vec<Vector<N>> {
+        self.get_operations()
+            .iter()
+            .filter(|op| op.mat() == Matrix::identity())
+            .map(Isometry::translation)
+            .collect()
+    }
+
+    /// returns operations generating the group together with the unit translations, made of
+    /// one operation for each generator of the point group and the centering translations.
+    /// this is cheaper than the generators of the group, as it uses the smaller point group
+    fn normalizer_generators(&self) -> Vec<Isometry<N>> {
+        let table = self.linear_part_table();
+        let mut generators: Vec<Isometry<N>> = table
+            .minimal_generators()
+            .into_iter()
+            .map(|i| {
+                let mat = table.elements()[i];
+                *self
+                    .get_operations()
+                    .iter()
+                    .find(|op| op.mat() == mat)
+                    .expect("the point group is made of the linear parts")
+            })
+            .collect();
+        generators.extend(
+            self.lattice_translations()
+                .into_iter()
+                .filter(|&vec| vec != Vector::zero())
+                .map(Isometry::from_translation),
+        );
+        generators
+    }
+
+    /// solves the congruences for the translation part m of an element (M, m) of the
+    /// normalizer, for every choice of lattice translations the conjugated generators may
+    /// differ by
+    fn normalizer_congruences(
+        &self,
+        generators: &[Isometry<N>],
+        mat: Matrix<N>,
+    ) -> Vec<Congruences<N>> {
+        let inverse = mat.inverse().expect("isometries are invertible");
+        let lattice = self.lattice_translations();
+        // (M, m) (W, w) (M, m)^-1 = (W', M w + (I - W') m) must equal (W', w') modulo the
+        // lattice, where W' = M W M^-1
+        let mut equations: Vec<Vec<(Matrix<N>, Vector<N>)>> = vec![Vec::new()];
+        for generator in generators {
+            let conjugate = mat * generator.mat() * inverse;
+            if conjugate == Matrix::identity() {
+                // conjugating a translation doesn't depend on m
+                if !self.contains(Isometry::from_translation(mat * generator.translation())) {
+                    return Vec::new();
+                }
+                continue;
+            }
+            let Some(image) = self
+                .get_operations()
+                .iter()
+                .find(|op| op.mat() == conjugate)
+            else {
+                return Vec::new();
+            };
+            let lhs = Matrix::identity() - conjugate;
+            let rhs = image.translation() - mat * generator.translation();
+            equations = equations
+                .into_iter()
+                .flat_map(|system| {
+                    lattice.iter().map(move |&vec| {
+                        let mut system = system.clone();
+                        system.push((lhs, rhs + vec));
+                        system
+                    })
+                })
+                .collect();
+        }
+        equations
+            .iter()
+            .filter_map(|system| Congruences::solve(system))
+            .collect()
+    }
+
+    /// returns a basis of the continuous translations of the Euclidean normalizer, which are
+    /// the polar directions of the group
+    pub fn continuous_translations(&self) -> Vec<Vector<N>> {
+        self.normalizer_congruences(&self.normalizer_generators(), Matrix::identity())
+            .first()
+            .expect("the identity is in the normalizer")
+            .continuous()
+    }
+
+    /// returns representatives of the cosets of the group in its Euclidean normalizer modulo
+    /// unit translations and the continuous translations, the first is the identity
+    pub fn euclidean_normalizer(&self) -> Vec<Isometry<N>> {
+        let point_group = self.linear_part_table().elements().to_vec();
+        let generic = Matrix::from(std::array::from_fn(|i| {
+            std::array::from_fn(|j| GENERIC_METRIC[i][j])
+        }));
+        let metric = point_group
+            .iter()
+            .fold(Matrix::from([[0; N]; N]), |acc, mat| {
+                acc + mat.transpose() * generic * *mat
+            });
+        let generators = self.normalizer_generators();
+        // the translations in the normalizer, modulo the translations of the group
+        let mut translations: Vec<Vector<N>> = Vec::new();
+        for solutions in self.normalizer_congruences(&generators, Matrix::identity()) {
+            for vec in solutions.discrete {
+                if !translations
+                    .iter()
+                    .any(|&other| self.contains(Isometry::from_translation(vec - other)))
+                {
+                    translations.push(vec);
+                }
+            }
+        }
+        // the linear parts which preserve the metric and normalize the point group, one for
+        // each coset of the point group, together with a fitting translation
+        let mut linear_parts: Vec<Isometry<N>> = vec![Isometry::from_translation(Vector::zero())];
+        // the columns of such a matrix have the lengths of the basis vectors and components
+        // -1, 0 or 1
+        let length = |vec: Vector<N>| vec.dot(&(metric * vec));
+        let vectors: Vec<Vector<N>> = (0..3_i32.pow(N as u32))
+            .map(|i| {
+                Vector::from(std::array::from_fn(|k| {
+                    i / 3_i32.pow((N - 1 - k) as u32) % 3 - 1
+                }))
+            })
+            .collect();
+        let mut columns: Vec<Vec<Vector<N>>> = vec![Vec::new()];
+        for unit in (0..N).map(|j| Matrix::<N>::identity().column(j)) {
+            let candidates: Vec<Vector<N>> = vectors
+                .iter()
+                .filter(|&&vec| length(vec) == length(unit))
+                .copied()
+                .collect();
+            columns = columns
+                .into_iter()
+                .flat_map(|cols| {
+                    candidates.iter().map(move |&col| {
+                        let mut cols = cols.clone();
+                        cols.push(col);
+                        cols
+                    })
+                })
+                .collect();
+        }
+        for cols in columns {
+            let mat = Matrix::from(std::array::from_fn(|i| {
+                std::array::from_fn(|j| cols[j].components()[i])
+            }));
+            if mat.determinant().abs() != 1.into()
+                || mat.transpose() * metric * mat != metric
+                || linear_parts.iter().any(|op| {
+                    let inverse = op.mat().inverse().expect("isometries are invertible");
+                    point_group.contains(&(inverse * mat))
+                })
+            {
+                continue;
+            }
+            let found = self
+                .normalizer_congruences(&generators, mat)
+                .into_iter()
+                .find_map(|solutions| solutions.discrete.first().copied());
+            if let Some(vec) = found {
+                linear_parts
+                    .push(Isometry::new(Affine::new(mat, vec)).expect("the determinant is 1"));
+            }
+        }
+        linear_parts
+            .iter()
+            .flat_map(|&op| {
+                translations
+                    .iter()
+                    .map(move |&vec| (op * Isometry::from_translation(vec)) % Bounds::splat(1))
+            })
+            .collect()
+    }
+
+    /// returns the canonical description of the structure given by the positions, as the group
+    /// with sorted operations and one sorted representative per site.
+    /// only the elements of the normalizer which map the supercell onto itself are used
+    pub fn canonicalize(
+        &self,
+        positions: &[Position<N>],
+        bounds: Bounds<N>,
+    ) -> (IsometryGroup<N>, Vec<Position<N>>) {
+        let lengths = bounds.lengths();
+        let scale = Matrix::from(std::array::from_fn(|i| {
+            std::array::from_fn(|j| if i == j { lengths[i] } else { 0 })
+        }));
+        let inverse_scale = scale.inverse().expect("bounds are positive");
+        let keeps_bounds = |op: &Isometry<N>| is_integral(inverse_scale * op.mat() * scale);
+        let polar = self
+            .normalizer_congruences(&self.normalizer_generators(), Matrix::identity())
+            .swap_remove(0);
+        let best = self
+            .euclidean_normalizer()
+            .into_iter()
+            .filter(keeps_bounds)
+            .flat_map(|op| {
+                let moved: Vec<Position<N>> = positions.iter().map(|&pos| op * pos).collect();
+                // the continuous translations move an image of a position onto the origin along
+                // the polar directions
+                let mut shifts: Vec<Vector<N>> = match polar.rank == N {
+                    true => vec![Vector::zero()],
+                    false => moved
+                        .iter()
+                        .flat_map(|&pos| self.get_operations().iter().map(move |&g| g * pos))
+                        .map(|image| -polar.continuous_part(image.into()) % Bounds::splat(1))
+                        .collect(),
+                };
+                shifts.sort();
+                shifts.dedup();
+                shifts.into_iter().map(move |shift| {
+                    self.site_representatives(moved.iter().map(|&pos| pos + shift))
+                })
+            })
+            .min()
+            .expect("the identity is in the normalizer");
+        (self.sorted(), best)
+    }
+
+    /// returns the sorted smallest positions of the orbits of the positions
+    fn site_representatives(
+        &self,
+        positions: impl Iterator<Item = Position<N>>,
+    ) -> Vec<Position<N>> {
+        let mut representatives: Vec<Position<N>> = positions
+            .map(|pos| {
+                self.get_operations()
+                    .iter()
+                    .map(|&op| (op * pos) % Bounds::splat(1))
+                    .min()
+                    .expect("a group contains the identity")
+            })
+            .collect();
+        representatives.sort();
+        representatives.dedup();
+        representatives
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        objects::{calculate_pairs, produce_output_string},
+        Bounds3, Pos3, Vec3,
+    };
+
+    fn group(name: &str) -> IsometryGroup {
+        IsometryGroup::from_file(format!("../files/space_groups/{}", name)).unwrap()
+    }
+
+    #[test]
+    fn normalizers() {
+        // P-1 has the 8 inversion centres 0 and 1/2 in each direction
+        assert_eq!(group("P-1").euclidean_normalizer().len(), 8);
+        // the normalizer of Fm-3m is Pm-3m with a/2, b/2 and c/2
+        assert_eq!(group("Fm-3m").euclidean_normalizer().len(), 2);
+        // the normalizer of P6_3/mmc is P6/mmm with c/2
+        assert_eq!(group("P6_3|mmc").euclidean_normalizer().len(), 2);
+        // the normalizer of Pmna is Pmmm with a/2, b/2 and c/2
+        assert_eq!(group("Pmna").euclidean_normalizer().len(), 8);
+    }
+
+    #[test]
+    fn equivalent_inputs() {
+        let sg = group("P-1");
+        let half = Frac::new(1, 2);
+        let third = Frac::new(1, 3);
+        let positions = vec![Pos3::new(third, 0.into(), 0.into())];
+        // shifting the origin to 1/2,1/2,1/2 and choosing another representative
+        let shifted = vec![Pos3::new(-third + half, half, half)];
+        let bounds = Bounds3::splat(3);
+        let output = |positions: &[Pos3]| {
+            let (group, positions) = sg.canonicalize(positions, bounds);
+            produce_output_string(&calculate_pairs(group, positions, bounds, false))
+        };
+        assert_ne!(
+            produce_output_string(&calculate_pairs(
+                sg.clone(),
+                positions.clone(),
+                bounds,
+                false
+            )),
+            produce_output_string(&calculate_pairs(sg.clone(), shifted.clone(), bounds, false))
+        );
+        assert_eq!(output(&positions), output(&shifted));
+    }
+
+    #[test]
+    fn polar_groups() {
+        // P1 has no fixed origin, the origin of P2_1 is free along the screw axis
+        let p1 = IsometryGroup::from_symbol("P1").unwrap();
+        assert_eq!(p1.continuous_translations().len(), 3);
+        assert_eq!(
+            group("P2_1").continuous_translations(),
+            [Vec3::from([0, 1, 0])]
+        );
+        let fifth = Frac::new(1, 5);
+        let third = Frac::new(1, 3);
+        let bounds = Bounds3::splat(3);
+        let positions = vec![
+            Pos3::new(fifth, 0.into(), 0.into()),
+            Pos3::new(third, 0.into(), 0.into()),
+        ];
+        let shifted = vec![Pos3::origin(), Pos3::new(third - fifth, 0.into(), 0.into())];
+        assert_eq!(
+            p1.canonicalize(&positions, bounds),
+            p1.canonicalize(&shifted, bounds)
+        );
+        let sg = group("P2_1");
+        let seventh = Frac::new(1, 7);
+        let positions = vec![Pos3::new(fifth, third, 0.into())];
+        let shifted = vec![Pos3::new(fifth, third + seventh, 0.into())];
+        assert_eq!(
+            sg.canonicalize(&positions, bounds),
+            sg.canonicalize(&shifted, bounds)
+        );
+    }
+}
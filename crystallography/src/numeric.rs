@@ -0,0 +1,376 @@
+//! # Floating Point Positions
+//! This module calculates sites and pairs for positions with floating point coordinates, such as
+//! the irrational coordinates of refined general positions.
+//!
+//! The operations stay exact, only the positions are floating point numbers. Two positions are
+//! equal if they agree up to the tolerance modulo the bounds, so positions closer than the
+//! tolerance to a special position are treated as lying on it. Special positions are better
+//! given exactly, this backend is meant for general positions.
+
+use std::{fmt::Display, ops::Mul};
+
+use nalgebra::{Matrix3, Vector3};
+
+use crate::{
+    copy_mul_impl,
+    orbits::{self, Action},
+    symmetry::{Isometry, IsometryGroup},
+    Bounds3, Pos3,
+};
+
+/// the tolerance used if none is given, large enough for rounding errors of the operations
+pub const DEFAULT_EQUALITY_TOLERANCE: f64 = 1e-8;
+
+/// a position with floating point coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatPos3(Vector3<f64>);
+
+impl FloatPos3 {
+    /// constructor
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self(Vector3::new(x, y, z))
+    }
+
+    /// returns the coordinates
+    pub fn coordinates(&self) -> [f64; 3] {
+        [self.0.x, self.0.y, self.0.z]
+    }
+
+    /// puts the position into the bounds, the coordinates are in [0, bound)
+    pub fn reduce(&self, bounds: Bounds3) -> Self {
+        let bounds = bounds_vector(bounds);
+        Self(self.0.zip_map(&bounds, |val, bound| val.rem_euclid(bound)))
+    }
+
+    /// returns true if the positions agree up to the tolerance modulo the bounds
+    pub fn approx_eq(&self, other: &Self, bounds: Bounds3, tolerance: f64) -> bool {
+        let bounds = bounds_vector(bounds);
+        (self.0 - other.0)
+            .zip_map(&bounds, |diff, bound| {
+                let diff = diff.rem_euclid(bound);
+                diff.min(bound - diff)
+            })
+            .iter()
+            .all(|&diff| diff <= tolerance)
+    }
+
+    /// returns the vector from this position to the other, reduced into (-bound/2, bound/2] like
+    /// the exact vectors. components within the tolerance of 0 or bound/2 are set to these values
+    /// so rounding errors don't flip the sign
+    pub fn vector_to(&self, other: &Self, bounds: Bounds3, tolerance: f64) -> [f64; 3] {
+        let bounds = bounds_vector(bounds);
+        let vec = (other.0 - self.0).zip_map(&bounds, |diff, bound| {
+            let mut diff = diff.rem_euclid(bound);
+            if diff > bound / 2.0 + tolerance {
+                diff -= bound
+            }
+            if diff.abs() <= tolerance {
+                0.0
+            } else if (diff - bound / 2.0).abs() <= tolerance {
+                bound / 2.0
+            } else {
+                diff
+            }
+        });
+        [vec.x, vec.y, vec.z]
+    }
+}
+
+impl From<Pos3> for FloatPos3 {
+    fn from(pos: Pos3) -> Self {
+        Self::new(pos.x().into(), pos.y().into(), pos.z().into())
+    }
+}
+
+impl Display for FloatPos3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{:.4}, {:.4}, {:.4}]", self.0.x, self.0.y, self.0.z)
+    }
+}
+
+impl Mul<FloatPos3> for Isometry {
+    type Output = FloatPos3;
+
+    fn mul(self, rhs: FloatPos3) -> Self::Output {
+        let mat: Matrix3<f64> = self.mat().into();
+        let translation: Vector3<f64> = self.translation().into();
+        FloatPos3(mat * rhs.0 + translation)
+    }
+}
+
+copy_mul_impl!(Isometry, FloatPos3);
+
+fn bounds_vector(bounds: Bounds3) -> Vector3<f64> {
+    Vector3::new(bounds.x().into(), bounds.y().into(), bounds.z().into())
+}
+
+/// returns true if the position is in the list up to the tolerance
+fn contains_approx(
+    list: &[FloatPos3],
+    position: &FloatPos3,
+    bounds: Bounds3,
+    tolerance: f64,
+) -> bool {
+    list.iter()
+        .any(|other| other.approx_eq(position, bounds, tolerance))
+}
+
+/// the action of an exact group on floating point positions, positions closer than the tolerance
+/// are identified
+struct ApproxAction<'a> {
+    group: &'a IsometryGroup,
+    tolerance: f64,
+}
+
+impl Action for ApproxAction<'_> {
+    type Position = FloatPos3;
+    type Operation = Isometry;
+
+    fn operations(&self, bounds: Bounds3) -> Box<dyn Iterator<Item = Isometry> + '_> {
+        Box::new(self.group.iter_with_bounds(bounds))
+    }
+
+    fn reduce(&self, position: &FloatPos3, bounds: Bounds3) -> FloatPos3 {
+        position.reduce(bounds)
+    }
+
+    fn apply(&self, op: &Isometry, position: &FloatPos3, bounds: Bounds3) -> FloatPos3 {
+        (op * position).reduce(bounds)
+    }
+
+    fn same(&self, a: &FloatPos3, b: &FloatPos3, bounds: Bounds3) -> bool {
+        a.approx_eq(b, bounds, self.tolerance)
+    }
+}
+
+/// the floating point version of Site, the orbit of a position in the unit cell
+pub struct FloatSite {
+    position: FloatPos3,
+    orbit: Vec<FloatPos3>,
+}
+
+impl FloatSite {
+    /// calculates the orbit of the position, images closer than the tolerance are identified
+    pub fn new(group: &IsometryGroup, position: FloatPos3, tolerance: f64) -> Self {
+        let action = ApproxAction { group, tolerance };
+        let orbit = orbits::orbit(&action, &position, Bounds3::splat(1));
+        Self {
+            position: orbit[0],
+            orbit,
+        }
+    }
+
+    /// returns the representative position
+    pub fn position(&self) -> FloatPos3 {
+        self.position
+    }
+
+    /// returns how many symmetry related positions there are
+    pub fn multiplicity(&self) -> usize {
+        self.orbit.len()
+    }
+
+    /// returns true if the position is in the orbit of the site up to the tolerance
+    pub fn contains_pos(&self, position: &FloatPos3, tolerance: f64) -> bool {
+        contains_approx(&self.orbit, position, Bounds3::splat(1), tolerance)
+    }
+
+    /// returns the orbit expanded to the bounds
+    /// in the same order as Site::orbit_in_bounds, so both backends choose the same representative
+    /// pairs
+    fn orbit_in_bounds(&self, bounds: Bounds3) -> Vec<FloatPos3> {
+        let mut out = Vec::new();
+        for pos in &self.orbit {
+            for i in 0..bounds.x() {
+                for j in 0..bounds.y() {
+                    for k in 0..bounds.z() {
+                        let shift = Vector3::new(i as f64, j as f64, k as f64);
+                        out.push(FloatPos3(pos.0 + shift));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// the floating point version of PairExpansion
+pub struct FloatPairExpansion {
+    origin_position: FloatPos3,
+    origin_multiplicity: usize,
+    vec: [f64; 3],
+    expansion: Vec<FloatPos3>,
+    is_ab_pair: bool,
+}
+
+impl FloatPairExpansion {
+    /// calculates the pair expansion like PairExpansion::from_positions
+    fn from_positions(
+        origin_site: &FloatSite,
+        end_position: FloatPos3,
+        is_ab_pair: bool,
+        action: &ApproxAction,
+        bounds: Bounds3,
+    ) -> Self {
+        let origin_position = origin_site.position;
+        let expansion = orbits::pair_expansion(action, &origin_position, &end_position, bounds);
+        Self {
+            origin_position,
+            origin_multiplicity: origin_site.multiplicity(),
+            vec: origin_position.vector_to(&end_position, bounds, action.tolerance),
+            expansion,
+            is_ab_pair,
+        }
+    }
+
+    /// returns the representative origin position of the pair
+    pub fn origin_position(&self) -> FloatPos3 {
+        self.origin_position
+    }
+
+    /// returns the representative pair vector
+    pub fn vector(&self) -> [f64; 3] {
+        self.vec
+    }
+
+    /// return how many ordered pairs of this type can be formed from positions within a unitcell
+    pub fn multiplicity(&self) -> usize {
+        self.origin_multiplicity * self.expansion.len() * if self.is_ab_pair { 2 } else { 1 }
+    }
+
+    /// returns an array of three Strings [origin_position, pair vector, multiplicity]
+    pub fn to_string(&self) -> (String, String, String) {
+        let [x, y, z] = self.vec;
+        (
+            self.origin_position.to_string(),
+            format!("[{:.4}, {:.4}, {:.4}]", x, y, z),
+            self.multiplicity().to_string(),
+        )
+    }
+}
+
+/// constructs all pairs from the floating point positions like calculate_pairs, positions equal
+/// up to the tolerance are identified
+pub fn calculate_float_pairs(
+    group: &IsometryGroup,
+    positions: &[FloatPos3],
+    bounds: Bounds3,
+    construct_ab_pairs: bool,
+    tolerance: f64,
+) -> Vec<FloatPairExpansion> {
+    let mut sites: Vec<FloatSite> = Vec::new();
+    for position in positions {
+        if !sites
+            .iter()
+            .any(|site| site.contains_pos(position, tolerance))
+        {
+            sites.push(FloatSite::new(group, *position, tolerance))
+        }
+    }
+    let action = ApproxAction { group, tolerance };
+    let end_orbits: Vec<Vec<FloatPos3>> = sites
+        .iter()
+        .map(|site| site.orbit_in_bounds(bounds))
+        .collect();
+    orbits::representative_pairs(
+        &end_orbits,
+        construct_ab_pairs,
+        |i, j, &pos| FloatPairExpansion::from_positions(&sites[i], pos, i != j, &action, bounds),
+        |expansion, pos| action.contains(&expansion.expansion, pos, bounds),
+    )
+}
+
+/// produces a string table of the floating point results
+pub fn produce_float_output_string(expansions: &[FloatPairExpansion]) -> String {
+    let mut string = format!(
+        "{: >26}, {: >26}, {: >12}",
+        "Origin", "Vector", "Multiplicity"
+    );
+    for (a, b, c) in expansions.iter().map(FloatPairExpansion::to_string) {
+        string += &format!("\n{: >26}, {: >26}, {: >12}", a, b, c);
+    }
+    string
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{objects::calculate_pairs, Frac, Vec3};
+
+    fn group(name: &str) -> IsometryGroup {
+        IsometryGroup::from_file(format!("../files/space_groups/{}", name)).unwrap()
+    }
+
+    #[test]
+    fn same_as_exact() {
+        let sg = group("Pmna");
+        let bounds = Bounds3::splat(2);
+        let positions = vec![
+            Pos3::new(Frac::new(1, 7), Frac::new(2, 9), Frac::new(3, 11)),
+            Pos3::new(0.into(), Frac::new(1, 2), 0.into()),
+        ];
+        let exact: Vec<(String, usize)> =
+            calculate_pairs(sg.clone(), positions.clone(), bounds, true)
+                .iter()
+                .map(|pair| {
+                    let vec = pair.vector();
+                    let [x, y, z] = [vec.x(), vec.y(), vec.z()].map(f64::from);
+                    (
+                        format!("[{:.4}, {:.4}, {:.4}]", x, y, z),
+                        pair.multiplicity(),
+                    )
+                })
+                .collect();
+        let float_positions: Vec<FloatPos3> = positions.into_iter().map(FloatPos3::from).collect();
+        let float: Vec<(String, usize)> = calculate_float_pairs(
+            &sg,
+            &float_positions,
+            bounds,
+            true,
+            DEFAULT_EQUALITY_TOLERANCE,
+        )
+        .iter()
+        .map(|pair| (pair.to_string().1, pair.multiplicity()))
+        .collect();
+        // the same representative pairs in the same order
+        assert_eq!(exact, float);
+    }
+
+    #[test]
+    fn vectors_like_exact() {
+        let bounds = Bounds3::splat(2);
+        let origin = FloatPos3::new(0.1, 0.2, 0.3);
+        let end = FloatPos3::new(1.1 + 1e-12, 0.2 - 1e-12, 0.3 - 1.0 - 1e-12);
+        // like Vec3 % bounds the vector is in (-1, 1], the second component isn't -0.0000
+        assert_eq!(
+            origin.vector_to(&end, bounds, DEFAULT_EQUALITY_TOLERANCE),
+            [1.0, 0.0, 1.0]
+        );
+        let exact = Vec3::from([1, 0, -1]) % bounds;
+        assert_eq!(exact, Vec3::from([1, 0, 1]));
+        let end = FloatPos3::new(0.1 + 0.75, 0.2 - 0.75, 0.3);
+        assert_eq!(
+            origin.vector_to(&end, bounds, DEFAULT_EQUALITY_TOLERANCE),
+            [0.75, -0.75, 0.0]
+        );
+    }
+
+    #[test]
+    fn irrational_positions() {
+        let sg = group("P-1");
+        let general = FloatSite::new(&sg, FloatPos3::new(0.1 * 2_f64.sqrt(), 0.3, 0.7), 1e-8);
+        assert_eq!(general.multiplicity(), 2);
+        // within the tolerance of the inversion centre at 1/2,0,0
+        let special = FloatSite::new(&sg, FloatPos3::new(0.5 + 1e-10, 0.0, -1e-10), 1e-8);
+        assert_eq!(special.multiplicity(), 1);
+        let pairs = calculate_float_pairs(
+            &sg,
+            &[general.position()],
+            Bounds3::splat(2),
+            false,
+            DEFAULT_EQUALITY_TOLERANCE,
+        );
+        let total: usize = pairs.iter().map(FloatPairExpansion::multiplicity).sum();
+        assert_eq!(total, 2 * 2 * 8);
+    }
+}
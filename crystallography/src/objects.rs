@@ -1,36 +1,36 @@
-//! This modules contains the structs necessary to represent Wyckoff positions and Pairs
+//! This modules contains the structs necessary to represent Wyckoff positions, Pairs and Clusters
 
-use std::{fs::read_to_string, path::Path};
+use std::{collections::BTreeSet, path::Path};
 
 use anyhow::Result;
-use pest::{iterators::Pairs, Parser};
+use pest::iterators::Pairs;
+use thiserror::Error;
 
 use crate::{
-    symmetry::{IsometryGroup, IsometryIter},
-    Bounds3, MyParser, Pos3, Rule, Vec3,
+    affine_space::{Bounds, Position, Vector},
+    config::Input,
+    orbits,
+    symmetry::{IsometryGroup, IsometryIter, MagneticGroup, MagneticIsometry},
+    Bounds3, Pos3, Rule, Vec3,
 };
 
+#[derive(Error, Debug)]
+enum SpinError {
+    #[error("the axis {1} at {0} is not mapped onto itself or its negative by the site symmetry")]
+    IncompatibleAxis(Pos3, Vec3),
+}
+
 /// this struct represents a collection of sites within the given bounds
-pub struct Site {
-    position: Pos3,
-    orbit: Vec<Pos3>,
+pub struct Site<const N: usize = 3> {
+    position: Position<N>,
+    orbit: Vec<Position<N>>,
 }
 
-impl Site {
-    /// create a new site calculating the orbit and the stabilizer
-    pub fn new(group: &IsometryGroup, position: Pos3) -> Self {
-        let position = position % Bounds3::splat(1.into()); // put site in first unit cell
-        let mut orbit = vec![position];
-        let mut stabilizer = Vec::new();
-        for op in group.iter_with_bounds(Bounds3::splat(1)) {
-            let new_pos = (op * position) % Bounds3::splat(1);
-            if new_pos == position {
-                stabilizer.push(op)
-            }
-            if !orbit.contains(&new_pos) {
-                orbit.push(new_pos)
-            }
-        }
+impl<const N: usize> Site<N> {
+    /// create a new site calculating the orbit
+    pub fn new(group: &IsometryGroup<N>, position: Position<N>) -> Self {
+        let position = position % Bounds::splat(1); // put site in first unit cell
+        let orbit = orbits::orbit(group, &position, Bounds::splat(1));
         Self { position, orbit }
     }
 
@@ -40,17 +40,17 @@ impl Site {
     }
 
     /// retruns the orbit as expanded to the bounds
-    pub fn orbit_in_bounds<'a>(&'a self, bounds: Bounds3) -> IsometryIter<'a, Pos3> {
+    pub fn orbit_in_bounds<'a>(&'a self, bounds: Bounds<N>) -> IsometryIter<'a, Position<N>, N> {
         IsometryIter::new(&self.orbit, bounds)
     }
 
     /// returns true if the position in the orbit of the site
-    pub fn contains_pos(&self, position: Pos3) -> bool {
-        self.orbit.contains(&(position % Bounds3::splat(1)))
+    pub fn contains_pos(&self, position: Position<N>) -> bool {
+        self.orbit.contains(&(position % Bounds::splat(1)))
     }
 
     /// produces a reduced representation of the site.
-    pub fn to_reduced_site(&self) -> ReducedSite {
+    pub fn to_reduced_site(&self) -> ReducedSite<N> {
         ReducedSite {
             position: self.position,
             multiplicity: self.multiplicity(),
@@ -58,24 +58,24 @@ impl Site {
     }
 }
 
-impl PartialEq for Site {
+impl<const N: usize> PartialEq for Site<N> {
     fn eq(&self, other: &Self) -> bool {
         self.orbit.contains(&other.position)
     }
 }
 
-impl Eq for Site {}
+impl<const N: usize> Eq for Site<N> {}
 
 /// a reduced representation of the site for situations where only position and multiplicity are
 /// required.
-pub struct ReducedSite {
-    position: Pos3,
+pub struct ReducedSite<const N: usize = 3> {
+    position: Position<N>,
     multiplicity: usize,
 }
 
-impl ReducedSite {
+impl<const N: usize> ReducedSite<N> {
     /// returns the representative position
-    pub fn position(&self) -> Pos3 {
+    pub fn position(&self) -> Position<N> {
         self.position
     }
 
@@ -86,47 +86,36 @@ impl ReducedSite {
 }
 
 /// a struct representing a pair of symmetry related positions
-pub struct PairExpansion {
-    origin_site: ReducedSite,
-    vec: Vec3,
-    expansion: Vec<Pos3>,
+pub struct PairExpansion<const N: usize = 3> {
+    origin_site: ReducedSite<N>,
+    vec: Vector<N>,
+    expansion: Vec<Position<N>>,
     is_ab_pair: bool,
 }
 
-impl PairExpansion {
+impl<const N: usize> PairExpansion<N> {
     /// returns true if the site Pair expansion contains the pair.
     /// note this only works if the origin is the origin position of the pair expansion
-    fn contains_pair(&self, origin_position: Pos3, end_position: Pos3) -> bool {
+    fn contains_pair(&self, origin_position: Position<N>, end_position: Position<N>) -> bool {
         assert_eq!(self.origin_site.position, origin_position);
         for pos in &self.expansion {
             if *pos == end_position {
                 return true;
             }
         }
-        return false;
+        false
     }
 
     /// this function calculates the pair expansion of this pair.
     /// note that the pair must be between symmetry equivalent positions.
     pub fn from_positions(
-        origin_site: &Site,
-        end_position: Pos3,
-        group: &IsometryGroup,
-        bounds: Bounds3,
+        origin_site: &Site<N>,
+        end_position: Position<N>,
+        group: &IsometryGroup<N>,
+        bounds: Bounds<N>,
     ) -> Self {
         let origin_position = origin_site.position;
-
-        let mut expansion = Vec::new();
-        for op in group.iter_with_bounds(bounds) {
-            let new_p1 = (op * origin_position) % bounds;
-            let new_p2 = (op * end_position) % bounds;
-            if new_p1 == origin_position && !(expansion.contains(&new_p2)) {
-                expansion.push(new_p2)
-            }
-            if new_p2 == origin_position && !(expansion.contains(&new_p1)) {
-                expansion.push(new_p1)
-            }
-        }
+        let expansion = orbits::pair_expansion(group, &origin_position, &end_position, bounds);
         Self {
             is_ab_pair: !origin_site.contains_pos(end_position),
             origin_site: origin_site.to_reduced_site(),
@@ -135,6 +124,16 @@ impl PairExpansion {
         }
     }
 
+    /// returns the representative origin position of the pair
+    pub fn origin_position(&self) -> Position<N> {
+        self.origin_site.position
+    }
+
+    /// returns the representative pair vector
+    pub fn vector(&self) -> Vector<N> {
+        self.vec
+    }
+
     /// return how many ordered pairs of this type can be formed from positions within a unitcell
     pub fn multiplicity(&self) -> usize {
         self.origin_site.multiplicity() * self.expansion.len() * if self.is_ab_pair { 2 } else { 1 }
@@ -150,101 +149,370 @@ impl PairExpansion {
     }
 }
 
+impl PairExpansion {
+    /// returns all distinct vectors of the ordered pairs of this type, the orbit of the pair
+    /// vector under the Laue group.
+    /// as the expansion contains the pairs in both orientations the negative vectors are included
+    pub fn equivalent_vectors(&self, group: &IsometryGroup) -> Vec<Vec3> {
+        let mut vectors = Vec::new();
+        for op in group.laue_group().iter() {
+            let vec = op * self.vec;
+            if !vectors.contains(&vec) {
+                vectors.push(vec)
+            }
+        }
+        vectors
+    }
+}
+
+/// a site of magnetic moments, each position of the orbit carries a local axis along which the
+/// spin is measured. the axes are transported from the representative position by the group
+struct SpinSite {
+    position: Pos3,
+    orbit: Vec<(Pos3, Vec3)>,
+    multiplicity: usize,
+}
+
+impl SpinSite {
+    /// calculates the orbit within the bounds together with the transported axes.
+    /// returns Err if the site symmetry maps the axis to neither itself nor its negative
+    fn new(group: &MagneticGroup, position: Pos3, axis: Vec3, bounds: Bounds3) -> Result<Self> {
+        let position = position % Bounds3::splat(1);
+        let mut orbit = vec![(position, axis)];
+        for (op, new_pos) in orbits::images(group, &position, bounds) {
+            let new_axis = op.transform_moment(axis);
+            if new_pos == position && new_axis != axis && new_axis != -axis {
+                return Err(SpinError::IncompatibleAxis(position, axis).into());
+            }
+            if !orbit.iter().any(|(pos, _)| *pos == new_pos) {
+                orbit.push((new_pos, new_axis))
+            }
+        }
+        let cells = bounds.x() * bounds.y() * bounds.z();
+        Ok(Self {
+            position,
+            multiplicity: orbit.len() / cells as usize,
+            orbit,
+        })
+    }
+
+    /// returns the local axis at the position or None if it is not in the orbit
+    fn axis(&self, position: Pos3) -> Option<Vec3> {
+        self.orbit
+            .iter()
+            .find(|(pos, _)| *pos == position)
+            .map(|(_, axis)| *axis)
+    }
+
+    /// returns +1 if the operation maps the axis at the position onto the axis at the image and
+    /// -1 if it reverses it
+    fn sign(&self, op: MagneticIsometry, position: Pos3, bounds: Bounds3) -> i32 {
+        let axis = self.axis(position).expect("position is in the orbit");
+        let image = self
+            .axis((op * position) % bounds)
+            .expect("the orbit is closed");
+        if op.transform_moment(axis) == image {
+            1
+        } else {
+            -1
+        }
+    }
+}
+
+/// a struct representing a pair of magnetic moments, the correlation of the spins along the local
+/// axes is the same for all pairs of the expansion up to a sign given by the primed operations
+pub struct SpinPairExpansion {
+    origin_position: Pos3,
+    origin_multiplicity: usize,
+    vec: Vec3,
+    expansion: Vec<(Pos3, i32)>,
+    is_ab_pair: bool,
+    is_forbidden: bool,
+}
+
+impl SpinPairExpansion {
+    /// calculates the pair expansion of the pair and the sign of the correlation of each pair
+    /// relative to the representative pair
+    fn from_positions(
+        origin_site: &SpinSite,
+        end_site: &SpinSite,
+        end_position: Pos3,
+        group: &MagneticGroup,
+        bounds: Bounds3,
+    ) -> Self {
+        let origin_position = origin_site.position;
+        let mut expansion: Vec<(Pos3, i32)> = Vec::new();
+        let mut is_forbidden = false;
+        for (op, end) in orbits::stabilizing_images(group, &origin_position, &end_position, bounds)
+        {
+            let sign = origin_site.sign(op, origin_position, bounds)
+                * end_site.sign(op, end_position, bounds);
+            match expansion.iter().find(|(pos, _)| *pos == end) {
+                Some((_, other)) => is_forbidden |= *other != sign,
+                None => expansion.push((end, sign)),
+            }
+        }
+        Self {
+            origin_position,
+            origin_multiplicity: origin_site.multiplicity,
+            vec: (end_position - origin_position) % bounds,
+            expansion,
+            is_ab_pair: origin_site.position != end_site.position,
+            is_forbidden,
+        }
+    }
+
+    /// returns true if the pair is contained in the expansion, the origin must be the origin of
+    /// the expansion
+    fn contains_pair(&self, origin_position: Pos3, end_position: Pos3) -> bool {
+        assert_eq!(self.origin_position, origin_position);
+        self.expansion.iter().any(|(pos, _)| *pos == end_position)
+    }
+
+    /// returns the representative origin position of the pair
+    pub fn origin_position(&self) -> Pos3 {
+        self.origin_position
+    }
+
+    /// returns the representative pair vector
+    pub fn vector(&self) -> Vec3 {
+        self.vec
+    }
+
+    /// returns true if the symmetry forces the correlation to vanish, as an operation maps the
+    /// pair onto itself while reversing the correlation
+    pub fn is_forbidden(&self) -> bool {
+        self.is_forbidden
+    }
+
+    /// return how many ordered pairs of this type can be formed from positions within a unitcell
+    pub fn multiplicity(&self) -> usize {
+        self.origin_multiplicity * self.expansion.len() * if self.is_ab_pair { 2 } else { 1 }
+    }
+
+    /// returns how many of the ordered pairs counted by the multiplicity have a correlation of
+    /// the opposite sign as the representative pair
+    pub fn reversed_multiplicity(&self) -> usize {
+        let reversed = self.expansion.iter().filter(|(_, sign)| *sign < 0).count();
+        self.origin_multiplicity * reversed * if self.is_ab_pair { 2 } else { 1 }
+    }
+
+    /// returns an array of four Strings [origin_position, pair vector, multiplicity, reversed]
+    /// where reversed is "forbidden" if the correlation vanishes
+    pub fn to_string(&self) -> (String, String, String, String) {
+        (
+            self.origin_position.to_string(),
+            self.vec.to_string(),
+            self.multiplicity().to_string(),
+            if self.is_forbidden {
+                "forbidden".to_string()
+            } else {
+                self.reversed_multiplicity().to_string()
+            },
+        )
+    }
+}
+
+/// a struct representing a cluster of n symmetry related positions
+/// the cluster is represented by the origin position and the vectors to the other positions
+pub struct ClusterExpansion {
+    origin_site: ReducedSite,
+    vecs: Vec<Vec3>,
+    expansion: BTreeSet<Vec<Pos3>>,
+    multiplicity: usize,
+}
+
+impl ClusterExpansion {
+    /// returns true if the cluster expansion contains the cluster.
+    /// the cluster must be sorted and contain the origin position of the expansion
+    fn contains_cluster(&self, cluster: &[Pos3]) -> bool {
+        self.expansion.contains(cluster)
+    }
+
+    /// this function calculates the cluster expansion of the cluster made up of the origin
+    /// position of the site and the end positions.
+    /// the positions must be distinct modulo the bounds.
+    pub fn from_positions(
+        origin_site: &Site,
+        end_positions: &[Pos3],
+        group: &IsometryGroup,
+        bounds: Bounds3,
+    ) -> Self {
+        let origin_position = origin_site.position;
+        let mut cluster: Vec<Pos3> = end_positions.iter().map(|&p| p % bounds).collect();
+        cluster.push(origin_position);
+        cluster.sort();
+
+        let mut orbit = BTreeSet::new();
+        for op in group.iter_with_bounds(bounds) {
+            let mut image: Vec<Pos3> = cluster.iter().map(|p| (op * p) % bounds).collect();
+            image.sort();
+            orbit.insert(image);
+        }
+        let expansion = orbit
+            .iter()
+            .filter(|image| image.contains(&origin_position))
+            .cloned()
+            .collect();
+        Self {
+            origin_site: origin_site.to_reduced_site(),
+            vecs: end_positions
+                .iter()
+                .map(|&p| (p - origin_position) % bounds)
+                .collect(),
+            expansion,
+            multiplicity: cluster.len() * orbit.len() / bounds.volume() as usize,
+        }
+    }
+
+    /// returns the number of positions in the cluster
+    pub fn order(&self) -> usize {
+        self.vecs.len() + 1
+    }
+
+    /// returns the vectors from the origin position to the other positions of the cluster
+    pub fn vectors(&self) -> &[Vec3] {
+        &self.vecs
+    }
+
+    /// return how many pairs of a position and a cluster of this type containing it can be formed
+    /// from positions within a unitcell.
+    /// for n = 2 this is the same as the pair multiplicity
+    pub fn multiplicity(&self) -> usize {
+        self.multiplicity
+    }
+
+    /// returns an array of three Strings [origin_position, cluster vectors, multiplicity]
+    pub fn to_string(&self) -> (String, String, String) {
+        (
+            self.origin_site.position.to_string(),
+            self.vecs
+                .iter()
+                .map(Vec3::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            self.multiplicity().to_string(),
+        )
+    }
+}
+
 /// tests if the position is contained within any of the orbits of the sites given
-fn contains_position(sites: &[Site], position: Pos3) -> bool {
+fn contains_position<const N: usize>(sites: &[Site<N>], position: Position<N>) -> bool {
     for site in sites {
         if site.contains_pos(position) {
             return true;
         }
     }
-    return false;
+    false
 }
 
 /// constructs all pairs from the positions. The positions are deduplicated using the space
 /// group befor applying the algorithm.
 /// If construct ab pairs is set to true the pairs of different sites are constructed to.
-pub fn calculate_pairs(
-    group: IsometryGroup,
-    mut positions: Vec<Pos3>,
+pub fn calculate_pairs<const N: usize>(
+    group: IsometryGroup<N>,
+    mut positions: Vec<Position<N>>,
+    bounds: Bounds<N>,
+    construct_ab_pairs: bool,
+) -> Vec<PairExpansion<N>> {
+    let sites = construct_sites(&group, &mut positions);
+    let end_orbits: Vec<Vec<Position<N>>> = sites
+        .iter()
+        .map(|site| site.orbit_in_bounds(bounds).collect())
+        .collect();
+    orbits::representative_pairs(
+        &end_orbits,
+        construct_ab_pairs,
+        |i, _, &pos| PairExpansion::from_positions(&sites[i], pos, &group, bounds),
+        |expansion, &pos| expansion.contains_pair(expansion.origin_position(), pos),
+    )
+}
+
+/// constructs all pairs of magnetic moments from the positions, each given with the local axis
+/// along which its spin is measured. positions in the orbit of an earlier position are skipped.
+/// If construct ab pairs is set to true the pairs of different sites are constructed to.
+/// returns Err if an axis is not compatible with the magnetic site symmetry
+pub fn calculate_spin_pairs(
+    group: &MagneticGroup,
+    positions: &[(Pos3, Vec3)],
     bounds: Bounds3,
     construct_ab_pairs: bool,
-) -> Vec<PairExpansion> {
-    positions
-        .iter_mut()
-        .for_each(|p| *p = *p % Bounds3::splat(1));
-    let mut sites = Vec::new();
-    for pos in positions {
-        if !contains_position(&sites, pos) {
-            sites.push(Site::new(&group, pos))
+) -> Result<Vec<SpinPairExpansion>> {
+    let mut sites: Vec<SpinSite> = Vec::new();
+    for &(pos, axis) in positions {
+        let pos = pos % Bounds3::splat(1);
+        if !sites.iter().any(|site| site.axis(pos).is_some()) {
+            sites.push(SpinSite::new(group, pos, axis, bounds)?)
         }
     }
-    let mut expansions = Vec::new();
+    let end_orbits: Vec<Vec<Pos3>> = sites
+        .iter()
+        .map(|site| site.orbit.iter().map(|&(pos, _)| pos).collect())
+        .collect();
+    Ok(orbits::representative_pairs(
+        &end_orbits,
+        construct_ab_pairs,
+        |i, j, &pos| SpinPairExpansion::from_positions(&sites[i], &sites[j], pos, group, bounds),
+        |expansion, &pos| expansion.contains_pair(expansion.origin_position, pos),
+    ))
+}
 
-    for site in &sites {
-        expansions.append(&mut construct_site_pairs(site, site, bounds, &group));
+/// constructs all clusters of `n` distinct positions from the positions. The positions are
+/// deduplicated using the space group befor applying the algorithm.
+/// If mixed is set to false only clusters of positions from the same site are constructed.
+/// panics if n is smaller than 2
+pub fn calculate_clusters(
+    group: IsometryGroup,
+    mut positions: Vec<Pos3>,
+    bounds: Bounds3,
+    n: usize,
+    mixed: bool,
+) -> Vec<ClusterExpansion> {
+    assert!(n >= 2, "a cluster must contain at least two positions");
+    let sites = construct_sites(&group, &mut positions);
+    let mut expansions = Vec::new();
+    for (i, site) in sites.iter().enumerate() {
+        let end_sites = if mixed { &sites[i..] } else { &sites[i..=i] };
+        expansions.append(&mut construct_site_clusters(
+            site, end_sites, n, bounds, &group,
+        ));
     }
+    expansions
+}
 
-    if construct_ab_pairs {
-        for (i, site_1) in sites.iter().enumerate() {
-            for site_2 in &sites[i + 1..] {
-                expansions.append(&mut construct_site_pairs(site_1, site_2, bounds, &group))
-            }
+/// puts the positions in the first unit cell and deduplicates them into sites
+fn construct_sites<const N: usize>(
+    group: &IsometryGroup<N>,
+    positions: &mut [Position<N>],
+) -> Vec<Site<N>> {
+    positions.iter_mut().for_each(|p| *p %= Bounds::splat(1));
+    let mut sites = Vec::new();
+    for &pos in positions.iter() {
+        if !contains_position(&sites, pos) {
+            sites.push(Site::new(group, pos))
         }
     }
-    expansions
+    sites
 }
 
-/// parses the file into the arguments for calculate pairs
-pub fn from_file(path: impl AsRef<Path>) -> Result<(IsometryGroup, Vec<Pos3>, Bounds3, bool)> {
-    let string = read_to_string(path)?;
-    from_str(&string)
+/// parses the file into the configuration of the calculation in its dimension
+pub fn from_file(path: impl AsRef<Path>) -> Result<Input> {
+    Input::from_file(path)
 }
 
-/// parses the string into the arguments for calculate pairs
-pub fn from_str(string: &str) -> Result<(IsometryGroup, Vec<Pos3>, Bounds3, bool)> {
-    let parsed = MyParser::parse(Rule::file, string)?;
-    tree_to_args(parsed)
+/// parses the string into the configuration of the calculation in its dimension
+pub fn from_str(string: &str) -> Result<Input> {
+    string.parse()
 }
 
-/// parses the pairs into the arguments for calculate pairs
-pub fn tree_to_args(mut pairs: Pairs<Rule>) -> Result<(IsometryGroup, Vec<Pos3>, Bounds3, bool)> {
-    let pairs = pairs.next().expect("must contain file").into_inner();
-    let mut group = None;
-    let mut positions = Vec::new();
-    let mut bounds = None;
-    let mut construct_ab_pairs = false;
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::affine_list => {
-                group = Some(IsometryGroup::from_affine_list(pair.into_inner())?);
-            }
-            Rule::vector => {
-                positions.push(Pos3::from_parser_vector(pair));
-            }
-            Rule::int_vector => {
-                bounds = Some(Bounds3::from_parser_int_vector(pair));
-            }
-            Rule::bool => {
-                construct_ab_pairs = match pair.as_str() {
-                    "true" => true,
-                    "false" => false,
-                    _ => unreachable!("unreachable by grammar"),
-                };
-            }
-            Rule::EOI => (),
-            _ => unreachable!("unreachable by grammar but got: {:?}", pair.as_rule()),
-        }
-    }
-    Ok((
-        group.expect("enforced by grammar"),
-        positions,
-        bounds.expect("enforced by grammar"),
-        construct_ab_pairs,
-    ))
+/// validates the pairs and parses them into the configuration of the calculation.
+/// returns Err with all problems and their locations if the input is invalid
+pub fn tree_to_args(pairs: Pairs<Rule>) -> Result<Input> {
+    Input::from_tree(pairs)
 }
 
 /// produces a string table of the results
-pub fn produce_output_string(expansions: &[PairExpansion]) -> String {
+pub fn produce_output_string<const N: usize>(expansions: &[PairExpansion<N>]) -> String {
     let mut string = format!(
         "{: >20}, {: >20}, {: >12}",
         "Origin", "Vector", "Multiplicity"
@@ -255,30 +523,158 @@ pub fn produce_output_string(expansions: &[PairExpansion]) -> String {
     string
 }
 
-/// retruns true if the pair equal to one of the pairs in the site.
-fn contains_pair(expansions: &[PairExpansion], origin_position: Pos3, end_position: Pos3) -> bool {
-    for expansion in expansions {
-        if expansion.contains_pair(origin_position, end_position) {
-            return true;
-        }
+/// produces a string table of the spin pair results
+pub fn produce_spin_output_string(expansions: &[SpinPairExpansion]) -> String {
+    let mut string = format!(
+        "{: >20}, {: >20}, {: >12}, {: >12}",
+        "Origin", "Vector", "Multiplicity", "Reversed"
+    );
+    for (a, b, c, d) in expansions.iter().map(SpinPairExpansion::to_string) {
+        string += &format!("\n{: >20}, {: >20}, {: >12}, {: >12}", a, b, c, d);
     }
-    return false;
+    string
 }
 
-/// constructs all pairs which have their origin at site_1 and their end point at one of the
-/// positions of site_2
-fn construct_site_pairs(
+/// produces a string table of the cluster results
+pub fn produce_cluster_output_string(expansions: &[ClusterExpansion]) -> String {
+    let mut string = format!(
+        "{: >20}, {: >20}, {: >12}",
+        "Origin", "Vectors", "Multiplicity"
+    );
+    for (a, b, c) in expansions.iter().map(ClusterExpansion::to_string) {
+        string += &format!("\n{: >20}, {: >20}, {: >12}", a, b, c);
+    }
+    string
+}
+
+/// constructs all clusters which have their origin at the position of site_1 and the other
+/// positions in the orbits of the end sites
+fn construct_site_clusters(
     site_1: &Site,
-    site_2: &Site,
+    end_sites: &[Site],
+    n: usize,
     bounds: Bounds3,
     group: &IsometryGroup,
-) -> Vec<PairExpansion> {
-    let mut out = Vec::new();
+) -> Vec<ClusterExpansion> {
+    let mut out: Vec<ClusterExpansion> = Vec::new();
     let origin_position = site_1.position;
-    for pos in site_2.orbit_in_bounds(bounds) {
-        if !contains_pair(&out, origin_position, pos) {
-            out.push(PairExpansion::from_positions(site_1, pos, group, bounds))
+    let mut candidates = Vec::new();
+    for site in end_sites {
+        for pos in site.orbit_in_bounds(bounds) {
+            if pos != origin_position && !candidates.contains(&pos) {
+                candidates.push(pos)
+            }
+        }
+    }
+    let mut indices: Vec<usize> = (0..n - 1).collect();
+    if indices.len() > candidates.len() {
+        return out;
+    }
+    loop {
+        let end_positions: Vec<Pos3> = indices.iter().map(|&i| candidates[i]).collect();
+        let mut cluster = end_positions.clone();
+        cluster.push(origin_position);
+        cluster.sort();
+        if !out.iter().any(|exp| exp.contains_cluster(&cluster)) {
+            out.push(ClusterExpansion::from_positions(
+                site_1,
+                &end_positions,
+                group,
+                bounds,
+            ))
+        }
+        // advance to the next combination of candidate indices
+        let Some(i) = (0..indices.len())
+            .rev()
+            .find(|&i| indices[i] < candidates.len() - indices.len() + i)
+        else {
+            return out;
+        };
+        indices[i] += 1;
+        for j in i + 1..indices.len() {
+            indices[j] = indices[j - 1] + 1;
         }
     }
-    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::Config, symmetry::Isometry, Frac, Mat3};
+
+    #[test]
+    fn two_clusters_are_pairs() {
+        let config: Config = Config::from_file("../files/input/example2").unwrap();
+        let (group, positions) = (config.group().clone(), config.positions().to_vec());
+        let (bounds, mixed) = (config.bounds()[0], config.construct_ab_pairs());
+        let pairs = calculate_pairs(group.clone(), positions.clone(), bounds, mixed);
+        let clusters = calculate_clusters(group, positions, bounds, 2, mixed);
+        let mut pair_multiplicities: Vec<_> = pairs
+            .iter()
+            .filter(|pair| pair.vec != Vec3::zero())
+            .map(|pair| {
+                (
+                    pair.origin_site.position,
+                    pair.vec % bounds,
+                    pair.multiplicity(),
+                )
+            })
+            .collect();
+        let mut cluster_multiplicities: Vec<_> = clusters
+            .iter()
+            .map(|cluster| {
+                (
+                    cluster.origin_site.position,
+                    cluster.vecs[0],
+                    cluster.multiplicity(),
+                )
+            })
+            .collect();
+        pair_multiplicities.sort();
+        cluster_multiplicities.sort();
+        assert_eq!(pair_multiplicities, cluster_multiplicities);
+    }
+
+    #[test]
+    fn triplets_in_p1() {
+        let group = IsometryGroup::from_generators(Vec::new());
+        let clusters = calculate_clusters(group, vec![Pos3::origin()], [3, 1, 1].into(), 3, false);
+        // in a periodic chain of three positions there is only one triplet
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].multiplicity(), 1);
+        assert_eq!(clusters[0].order(), 3);
+    }
+
+    #[test]
+    fn spin_pairs() {
+        let two_fold = Isometry::from_mat(Mat3::from([-1, 0, 0, 0, -1, 0, 0, 0, 1])).unwrap();
+        let group = MagneticGroup::from_generators(vec![MagneticIsometry::new(two_fold, true)]);
+        let half = Frac::new(1, 2);
+        let quarter = Frac::new(1, 4);
+        let positions = [
+            (Pos3::origin(), Vec3::from([0, 0, 1])),
+            (Pos3::new(0.into(), 0.into(), half), Vec3::from([1, 0, 0])),
+            (
+                Pos3::new(quarter, 0.into(), 0.into()),
+                Vec3::from([0, 0, 1]),
+            ),
+        ];
+        let pairs = calculate_spin_pairs(&group, &positions, Bounds3::splat(1), true).unwrap();
+        let find = |origin: Pos3, end: Pos3| {
+            pairs
+                .iter()
+                .find(|pair| pair.origin_position() == origin && pair.contains_pair(origin, end))
+                .unwrap()
+        };
+        // the primed 2-fold reverses the z component at the origin but not the x component
+        assert!(find(Pos3::origin(), positions[1].0).is_forbidden());
+        assert!(!find(Pos3::origin(), Pos3::origin()).is_forbidden());
+        let reversed = find(Pos3::origin(), positions[2].0);
+        assert!(!reversed.is_forbidden());
+        assert_eq!(reversed.multiplicity(), 4);
+        assert_eq!(reversed.reversed_multiplicity(), 2);
+        // an axis which is neither kept nor reversed by the site symmetry
+        let tilted = [(Pos3::origin(), Vec3::from([1, 0, 1]))];
+        assert!(calculate_spin_pairs(&group, &tilted, Bounds3::splat(1), false).is_err());
+    }
 }
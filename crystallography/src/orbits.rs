@@ -0,0 +1,250 @@
+//! # Orbits
+//! This module contains the orbit and pair expansion algorithms shared by the exact, magnetic,
+//! floating point and superspace positions.
+//!
+//! The algorithms only need to know how the operations of a group act on the positions modulo
+//! the bounds, which is described by the Action trait.
+
+use crate::{
+    affine_space::{Bounds, Position},
+    symmetry::{Isometry, IsometryGroup, MagneticGroup, MagneticIsometry},
+    Bounds3, Pos3,
+};
+
+/// a group acting on positions in N dimensions modulo the lattice translations of the bounds
+pub trait Action<const N: usize = 3> {
+    /// the positions the group acts on
+    type Position: Clone;
+    /// the operations of the group
+    type Operation;
+
+    /// returns the operations of the group combined with the translations within the bounds
+    fn operations(&self, bounds: Bounds<N>) -> Box<dyn Iterator<Item = Self::Operation> + '_>;
+
+    /// returns the position reduced into the bounds
+    fn reduce(&self, position: &Self::Position, bounds: Bounds<N>) -> Self::Position;
+
+    /// returns the image of the position under the operation reduced into the bounds
+    fn apply(
+        &self,
+        op: &Self::Operation,
+        position: &Self::Position,
+        bounds: Bounds<N>,
+    ) -> Self::Position;
+
+    /// returns true if the positions are the same modulo the bounds
+    fn same(&self, a: &Self::Position, b: &Self::Position, bounds: Bounds<N>) -> bool;
+
+    /// returns true if the position is in the list modulo the bounds
+    fn contains(
+        &self,
+        list: &[Self::Position],
+        position: &Self::Position,
+        bounds: Bounds<N>,
+    ) -> bool {
+        list.iter().any(|other| self.same(other, position, bounds))
+    }
+}
+
+impl<const N: usize> Action<N> for IsometryGroup<N> {
+    type Position = Position<N>;
+    type Operation = Isometry<N>;
+
+    fn operations(&self, bounds: Bounds<N>) -> Box<dyn Iterator<Item = Isometry<N>> + '_> {
+        Box::new(self.iter_with_bounds(bounds))
+    }
+
+    fn reduce(&self, position: &Position<N>, bounds: Bounds<N>) -> Position<N> {
+        *position % bounds
+    }
+
+    fn apply(&self, op: &Isometry<N>, position: &Position<N>, bounds: Bounds<N>) -> Position<N> {
+        (op * position) % bounds
+    }
+
+    fn same(&self, a: &Position<N>, b: &Position<N>, _bounds: Bounds<N>) -> bool {
+        a == b
+    }
+}
+
+impl Action for MagneticGroup {
+    type Position = Pos3;
+    type Operation = MagneticIsometry;
+
+    fn operations(&self, bounds: Bounds3) -> Box<dyn Iterator<Item = MagneticIsometry> + '_> {
+        Box::new(self.iter_with_bounds(bounds))
+    }
+
+    fn reduce(&self, position: &Pos3, bounds: Bounds3) -> Pos3 {
+        *position % bounds
+    }
+
+    fn apply(&self, op: &MagneticIsometry, position: &Pos3, bounds: Bounds3) -> Pos3 {
+        (*op * *position) % bounds
+    }
+
+    fn same(&self, a: &Pos3, b: &Pos3, _bounds: Bounds3) -> bool {
+        a == b
+    }
+}
+
+/// returns the operations within the bounds together with the image of the position
+pub fn images<'a, A: Action<N>, const N: usize>(
+    action: &'a A,
+    position: &'a A::Position,
+    bounds: Bounds<N>,
+) -> impl Iterator<Item = (A::Operation, A::Position)> + 'a {
+    action.operations(bounds).map(move |op| {
+        let image = action.apply(&op, position, bounds);
+        (op, image)
+    })
+}
+
+/// returns the orbit of the position within the bounds, the reduced position comes first
+pub fn orbit<A: Action<N>, const N: usize>(
+    action: &A,
+    position: &A::Position,
+    bounds: Bounds<N>,
+) -> Vec<A::Position> {
+    let position = action.reduce(position, bounds);
+    let mut orbit = vec![position.clone()];
+    for (_, image) in images(action, &position, bounds) {
+        if !action.contains(&orbit, &image, bounds) {
+            orbit.push(image)
+        }
+    }
+    orbit
+}
+
+/// puts the positions into the first unit cell and deduplicates them into sites, returns the
+/// orbits of the sites in the unit cell each starting with its representative position
+pub fn sites<A: Action<N>, const N: usize>(
+    action: &A,
+    positions: &[A::Position],
+) -> Vec<Vec<A::Position>> {
+    let unit = Bounds::splat(1);
+    let mut sites: Vec<Vec<A::Position>> = Vec::new();
+    for position in positions {
+        let position = &action.reduce(position, unit);
+        if !sites
+            .iter()
+            .any(|orbit| action.contains(orbit, position, unit))
+        {
+            sites.push(orbit(action, position, unit))
+        }
+    }
+    sites
+}
+
+/// returns the operations which map one end of the pair onto the origin together with the
+/// image of the other end, as the pairs are unordered both ends are tried
+pub fn stabilizing_images<A: Action<N>, const N: usize>(
+    action: &A,
+    origin: &A::Position,
+    end: &A::Position,
+    bounds: Bounds<N>,
+) -> Vec<(A::Operation, A::Position)>
+where
+    A::Operation: Clone,
+{
+    let mut out = Vec::new();
+    for op in action.operations(bounds) {
+        let new_p1 = action.apply(&op, origin, bounds);
+        let new_p2 = action.apply(&op, end, bounds);
+        if action.same(&new_p1, origin, bounds) {
+            out.push((op.clone(), new_p2.clone()))
+        }
+        if action.same(&new_p2, origin, bounds) {
+            out.push((op, new_p1))
+        }
+    }
+    out
+}
+
+/// returns the distinct end positions of all pairs at the origin which are symmetry equivalent
+/// to the pair, the expansion of the pair
+pub fn pair_expansion<A: Action<N>, const N: usize>(
+    action: &A,
+    origin: &A::Position,
+    end: &A::Position,
+    bounds: Bounds<N>,
+) -> Vec<A::Position>
+where
+    A::Operation: Clone,
+{
+    let mut expansion: Vec<A::Position> = Vec::new();
+    for (_, image) in stabilizing_images(action, origin, end, bounds) {
+        if !action.contains(&expansion, &image, bounds) {
+            expansion.push(image)
+        }
+    }
+    expansion
+}
+
+/// calls expand for every representative pair and collects the results.
+/// the pairs have their origin at the first position of a site and end in the orbit of the same
+/// site, or of a later one if construct ab pairs is set, given within the bounds. An end
+/// position is only expanded if it is not contained in an expansion of the same two sites.
+/// expand is called with the indices of the two sites and the end position.
+/// the pairs of the same site come first followed by the ab pairs.
+pub fn representative_pairs<P, E>(
+    end_orbits: &[Vec<P>],
+    construct_ab_pairs: bool,
+    mut expand: impl FnMut(usize, usize, &P) -> E,
+    contains: impl Fn(&E, &P) -> bool,
+) -> Vec<E> {
+    let mut site_pairs: Vec<(usize, usize)> = (0..end_orbits.len()).map(|i| (i, i)).collect();
+    if construct_ab_pairs {
+        for i in 0..end_orbits.len() {
+            site_pairs.extend((i + 1..end_orbits.len()).map(|j| (i, j)));
+        }
+    }
+    let mut expansions = Vec::new();
+    for (i, j) in site_pairs {
+        let first = expansions.len();
+        for pos in &end_orbits[j] {
+            if !expansions[first..]
+                .iter()
+                .any(|expansion| contains(expansion, pos))
+            {
+                expansions.push(expand(i, j, pos))
+            }
+        }
+    }
+    expansions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Frac;
+
+    #[test]
+    fn orbits_and_sites() {
+        let group = IsometryGroup::from_file("../files/space_groups/P-1").unwrap();
+        let general = Pos3::new(Frac::new(1, 8), Frac::new(1, 4), Frac::new(3, 8));
+        let shifted = Pos3::new(Frac::new(9, 8), Frac::new(1, 4), Frac::new(3, 8));
+        let orbit = orbit(&group, &shifted, [2, 1, 1].into());
+        assert_eq!(orbit.len(), 4);
+        assert_eq!(orbit[0], shifted);
+        // the inverted position belongs to the same site
+        let inverted = Pos3::new(Frac::new(-1, 8), Frac::new(-1, 4), Frac::new(-3, 8));
+        let sites = sites(&group, &[general, inverted]);
+        assert_eq!(sites.len(), 1);
+        assert_eq!(sites[0][0], general);
+    }
+
+    #[test]
+    fn pairs_of_the_same_site_first() {
+        let orbits = vec![vec![0, 1], vec![2]];
+        let pairs = representative_pairs(&orbits, true, |i, j, &end| (i, j, end), |_, _| false);
+        assert_eq!(pairs, vec![(0, 0, 0), (0, 0, 1), (1, 1, 2), (0, 1, 2)]);
+        let pairs = representative_pairs(
+            &orbits,
+            false,
+            |i, j, &end| (i, j, end),
+            |pair, &end| pair.2 + end == 1,
+        );
+        assert_eq!(pairs, vec![(0, 0, 0), (1, 1, 2)]);
+    }
+}
@@ -0,0 +1,326 @@
+//! # 3D-ΔPDF
+//! This module calculates occupational 3D-ΔPDF maps from a correlation model.
+//!
+//! Every pair expansion contributes a delta peak at each of its equivalent pair vectors.
+//! The weight of a pair type is the product of the scattering weights of the two sites, the
+//! correlation value of the pair and its multiplicity, which is shared evenly between the
+//! equivalent vectors.
+//!
+//! All coordinates are given in lattice units, as the cell metric is not known.
+
+use std::{fs::File, io::Write, path::Path};
+
+use anyhow::Result;
+use nalgebra::Vector3;
+use thiserror::Error;
+
+use crate::{
+    objects::{PairExpansion, Site},
+    symmetry::IsometryGroup,
+    Pos3,
+};
+
+#[derive(Error, Debug)]
+enum PdfError {
+    #[error("got {0} correlation values for {1} pair expansions")]
+    CorrelationCount(usize, usize),
+    #[error("the position {0} is not part of any weighted site")]
+    UnknownPosition(Pos3),
+}
+
+/// a site together with the scattering weight of the species occupying it
+pub struct WeightedSite {
+    site: Site,
+    weight: f64,
+}
+
+impl WeightedSite {
+    /// constructor calculating the orbit of the position
+    pub fn new(group: &IsometryGroup, position: Pos3, weight: f64) -> Self {
+        Self {
+            site: Site::new(group, position),
+            weight,
+        }
+    }
+
+    /// returns the scattering weight of the site
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+}
+
+/// looks up the weight of the site containing the position
+fn weight_of(sites: &[WeightedSite], position: Pos3) -> Result<f64> {
+    for site in sites {
+        if site.site.contains_pos(position) {
+            return Ok(site.weight);
+        }
+    }
+    Err(PdfError::UnknownPosition(position).into())
+}
+
+/// a regular grid of voxels centered on the origin of the Patterson space
+#[derive(Debug, Clone, Copy)]
+pub struct PdfGrid {
+    extent: [f64; 3],
+    shape: [usize; 3],
+}
+
+impl PdfGrid {
+    /// creates a grid spanning -extent to +extent along each axis with the given number of voxel
+    /// centers. odd numbers of voxels place a voxel on the origin.
+    pub fn new(extent: [f64; 3], shape: [usize; 3]) -> Self {
+        assert!(
+            shape.iter().all(|&n| n >= 2),
+            "a grid needs at least two voxels per axis"
+        );
+        assert!(
+            extent.iter().all(|&e| e > 0.0),
+            "the extent of the grid must be positive"
+        );
+        Self { extent, shape }
+    }
+
+    /// returns the distance between neighbouring voxel centers along each axis
+    pub fn step(&self) -> [f64; 3] {
+        std::array::from_fn(|i| 2.0 * self.extent[i] / (self.shape[i] - 1) as f64)
+    }
+
+    /// returns the number of voxels along each axis
+    pub fn shape(&self) -> [usize; 3] {
+        self.shape
+    }
+
+    /// returns the position of the voxel center at the index
+    pub fn voxel_center(&self, index: [usize; 3]) -> [f64; 3] {
+        let step = self.step();
+        let mut center = [0.0; 3];
+        for i in 0..3 {
+            center[i] = -self.extent[i] + index[i] as f64 * step[i];
+        }
+        center
+    }
+
+    fn linear_index(&self, index: [usize; 3]) -> usize {
+        (index[0] * self.shape[1] + index[1]) * self.shape[2] + index[2]
+    }
+
+    /// returns the (possibly out of bounds) index of the voxel closest to the point
+    fn nearest_index(&self, point: [f64; 3]) -> [i64; 3] {
+        let step = self.step();
+        let mut index = [0; 3];
+        for i in 0..3 {
+            index[i] = ((point[i] + self.extent[i]) / step[i]).round() as i64;
+        }
+        index
+    }
+
+    /// converts the index to an index into the grid if it lies within the grid
+    fn checked_index(&self, index: [i64; 3]) -> Option<[usize; 3]> {
+        let mut out = [0; 3];
+        for i in 0..3 {
+            if index[i] < 0 || index[i] >= self.shape[i] as i64 {
+                return None;
+            }
+            out[i] = index[i] as usize;
+        }
+        Some(out)
+    }
+}
+
+/// a 3D-ΔPDF map sampled on a grid
+pub struct PdfMap {
+    grid: PdfGrid,
+    values: Vec<f64>,
+}
+
+impl PdfMap {
+    /// creates an empty map on the grid
+    pub fn new(grid: PdfGrid) -> Self {
+        Self {
+            grid,
+            values: vec![0.0; grid.shape.iter().product()],
+        }
+    }
+
+    /// returns the grid of the map
+    pub fn grid(&self) -> PdfGrid {
+        self.grid
+    }
+
+    /// returns the value at the voxel
+    pub fn get(&self, index: [usize; 3]) -> f64 {
+        self.values[self.grid.linear_index(index)]
+    }
+
+    /// returns the values of all voxels with the last index varying fastest
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// adds a delta peak with the given weight to the voxel closest to the point
+    /// peaks outside of the grid are ignored
+    pub fn add_peak(&mut self, point: [f64; 3], weight: f64) {
+        if let Some(index) = self.grid.checked_index(self.grid.nearest_index(point)) {
+            let i = self.grid.linear_index(index);
+            self.values[i] += weight;
+        }
+    }
+
+    /// adds an isotropic gaussian peak with the given weight and standard deviation, both in
+    /// lattice units. The peak is cut off at three standard deviations and normalized such that
+    /// the sum over all voxels equals the weight if it lies fully within the grid.
+    pub fn add_gaussian_peak(&mut self, point: [f64; 3], weight: f64, sigma: f64) {
+        let step = self.grid.step();
+        let center = self.grid.nearest_index(point);
+        let mut reach = [0; 3];
+        for i in 0..3 {
+            reach[i] = (3.0 * sigma / step[i]).ceil() as i64;
+        }
+        let mut contributions = Vec::new();
+        let mut total = 0.0;
+        for dx in -reach[0]..=reach[0] {
+            for dy in -reach[1]..=reach[1] {
+                for dz in -reach[2]..=reach[2] {
+                    let index = [center[0] + dx, center[1] + dy, center[2] + dz];
+                    let mut dist_sq = 0.0;
+                    for i in 0..3 {
+                        let coord = -self.grid.extent[i] + index[i] as f64 * step[i];
+                        dist_sq += (coord - point[i]).powi(2);
+                    }
+                    let value = (-dist_sq / (2.0 * sigma * sigma)).exp();
+                    total += value;
+                    contributions.push((index, value));
+                }
+            }
+        }
+        for (index, value) in contributions {
+            if let Some(index) = self.grid.checked_index(index) {
+                let i = self.grid.linear_index(index);
+                self.values[i] += weight * value / total;
+            }
+        }
+    }
+
+    /// writes the map to a simple text volume format.
+    /// the header contains the shape, the position of the first voxel and the step size,
+    /// followed by one value per line with the last index varying fastest.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut file = File::create(path)?;
+        write!(file, "{}", self.to_volume_string())?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// returns the map in the text volume format used by PdfMap::write
+    pub fn to_volume_string(&self) -> String {
+        let [nx, ny, nz] = self.grid.shape;
+        let [ox, oy, oz] = self.grid.voxel_center([0; 3]);
+        let [sx, sy, sz] = self.grid.step();
+        let mut string = "# 3D-DeltaPDF volume".to_string();
+        string += &format!("\nshape {} {} {}", nx, ny, nz);
+        string += &format!("\norigin {} {} {}", ox, oy, oz);
+        string += &format!("\nstep {} {} {}", sx, sy, sz);
+        for value in &self.values {
+            string += &format!("\n{}", value);
+        }
+        string
+    }
+}
+
+/// calculates all equivalent vectors of the pair expansions together with the weight of the
+/// delta peak at each of them.
+/// returns Err if the number of correlations doesn't match or if a pair ends on a position which
+/// is not in any of the sites.
+pub(crate) fn weighted_vectors(
+    group: &IsometryGroup,
+    sites: &[WeightedSite],
+    expansions: &[PairExpansion],
+    correlations: &[f64],
+) -> Result<Vec<([f64; 3], f64)>> {
+    if expansions.len() != correlations.len() {
+        return Err(PdfError::CorrelationCount(correlations.len(), expansions.len()).into());
+    }
+    let mut out = Vec::new();
+    for (expansion, &correlation) in expansions.iter().zip(correlations) {
+        let origin_weight = weight_of(sites, expansion.origin_position())?;
+        let end_weight = weight_of(sites, expansion.origin_position() + expansion.vector())?;
+        let vectors = expansion.equivalent_vectors(group);
+        let weight = origin_weight * end_weight * correlation * expansion.multiplicity() as f64
+            / vectors.len() as f64;
+        for vec in vectors {
+            let vec: Vector3<f64> = vec.into();
+            out.push(([vec.x, vec.y, vec.z], weight));
+        }
+    }
+    Ok(out)
+}
+
+/// calculates the 3D-ΔPDF of the correlation model.
+/// correlations contains one value per pair expansion.
+/// if sigma is given the peaks are broadened by a gaussian with this standard deviation in lattice
+/// units, otherwise they are added to the closest voxel.
+/// returns Err if the number of correlations doesn't match or if a pair ends on a position which
+/// is not in any of the sites.
+pub fn calculate_delta_pdf(
+    group: &IsometryGroup,
+    sites: &[WeightedSite],
+    expansions: &[PairExpansion],
+    correlations: &[f64],
+    grid: PdfGrid,
+    sigma: Option<f64>,
+) -> Result<PdfMap> {
+    let mut map = PdfMap::new(grid);
+    for (point, weight) in weighted_vectors(group, sites, expansions, correlations)? {
+        match sigma {
+            Some(sigma) => map.add_gaussian_peak(point, weight, sigma),
+            None => map.add_peak(point, weight),
+        }
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::objects::calculate_pairs;
+
+    #[test]
+    fn chain_peaks() {
+        let group = IsometryGroup::from_generators(Vec::new());
+        let bounds = [5, 1, 1].into();
+        let expansions = calculate_pairs(group.clone(), vec![Pos3::origin()], bounds, false);
+        let sites = [WeightedSite::new(&group, Pos3::origin(), 2.0)];
+        let correlations: Vec<f64> = (0..expansions.len()).map(|i| i as f64).collect();
+        let grid = PdfGrid::new([2.0, 1.0, 1.0], [5, 3, 3]);
+        let map =
+            calculate_delta_pdf(&group, &sites, &expansions, &correlations, grid, None).unwrap();
+        for (i, expansion) in expansions.iter().enumerate() {
+            let vec: Vector3<f64> = expansion.vector().into();
+            let index = [(vec.x + 2.0) as usize, 1, 1];
+            // the weight is shared between the vector and its negative
+            let expected = 4.0 * i as f64 * expansion.multiplicity() as f64
+                / expansion.equivalent_vectors(&group).len() as f64;
+            assert_eq!(map.get(index), expected);
+        }
+    }
+
+    #[test]
+    fn gaussian_preserves_weight() {
+        let grid = PdfGrid::new([2.0, 2.0, 2.0], [41, 41, 41]);
+        let mut map = PdfMap::new(grid);
+        map.add_gaussian_peak([0.25, 0.0, -0.5], 3.0, 0.2);
+        let total: f64 = map.values().iter().sum();
+        assert!((total - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn wrong_correlation_count() {
+        let group = IsometryGroup::from_generators(Vec::new());
+        let expansions =
+            calculate_pairs(group.clone(), vec![Pos3::origin()], [3, 1, 1].into(), false);
+        let sites = [WeightedSite::new(&group, Pos3::origin(), 1.0)];
+        let grid = PdfGrid::new([1.0; 3], [3; 3]);
+        assert!(calculate_delta_pdf(&group, &sites, &expansions, &[1.0], grid, None).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! # Reflection Conditions
+//! This module derives the reflection conditions of a space group from its operations.
+//!
+//! An operation (R, t) leaves all reflections h with h R = h invariant and multiplies their
+//! structure factor by exp(2 pi i h.t). Thus, these reflections are systematically absent unless
+//! h.t is an integer. The set of reflections with h R = h is called the zone of the operation.
+//! The zone of the pure translations is the whole reciprocal lattice and gives the general
+//! conditions, all other zones give special conditions.
+//! Conditions which follow from conditions of a larger zone are not listed.
+
+use std::fmt::Display;
+
+use crate::{
+    affine_space::{primitive_integer, reduced_row_echelon},
+    frac::{gcd, lcm},
+    symmetry::IsometryGroup,
+    Frac, Mat3, RecVec3, Vec3,
+};
+
+/// a reflection condition, the zone of reflections it applies to and the condition they must
+/// fulfill to be present
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReflectionCondition {
+    zone: String,
+    condition: String,
+    is_general: bool,
+}
+
+impl ReflectionCondition {
+    /// returns the zone of the condition such as h0l
+    pub fn zone(&self) -> &str {
+        &self.zone
+    }
+
+    /// returns the condition such as l=2n
+    pub fn condition(&self) -> &str {
+        &self.condition
+    }
+
+    /// returns true if the condition applies to all reflections
+    pub fn is_general(&self) -> bool {
+        self.is_general
+    }
+}
+
+impl Display for ReflectionCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.zone, self.condition)
+    }
+}
+
+/// a zone of reciprocal space together with the translations of the operations leaving it
+/// invariant
+struct Zone {
+    /// the basis in reduced row echelon form, unique for each zone
+    basis: Vec<RecVec3>,
+    /// the basis scaled to primitive integer vectors
+    lattice_basis: Vec<RecVec3>,
+    translations: Vec<Vec3>,
+}
+
+impl Zone {
+    fn new(basis: Vec<RecVec3>) -> Self {
+        let lattice_basis = basis.iter().map(|&b| primitive_integer_vector(b)).collect();
+        Self {
+            basis,
+            lattice_basis,
+            translations: Vec::new(),
+        }
+    }
+
+    /// returns true if the zone is a subspace of the other zone
+    fn is_contained_in(&self, other: &Zone) -> bool {
+        let rows = other
+            .basis
+            .iter()
+            .chain(self.basis.iter())
+            .map(|b| [b.h(), b.k(), b.l()])
+            .collect();
+        reduced_row_echelon(rows).len() == other.basis.len()
+    }
+
+    /// returns the phase factors of the basis vectors for the translation modulo 1
+    fn coefficients(&self, translation: Vec3) -> Vec<Frac> {
+        self.lattice_basis
+            .iter()
+            .map(|b| b.dot(&translation) % Frac::from(1))
+            .collect()
+    }
+
+    /// the letter naming the parameter of each basis vector, given by the pivot
+    fn letters(&self) -> Vec<char> {
+        self.basis
+            .iter()
+            .map(|b| {
+                let i = [b.h(), b.k(), b.l()]
+                    .iter()
+                    .position(|val| *val != 0.into())
+                    .expect("basis vectors are nonzero");
+                ['h', 'k', 'l'][i]
+            })
+            .collect()
+    }
+
+    /// returns the name of the zone such as h0l or hhl
+    fn name(&self) -> String {
+        let letters = self.letters();
+        let components: Vec<String> = (0..3)
+            .map(|i| {
+                let coefficients = self.lattice_basis.iter().map(|b| [b.h(), b.k(), b.l()][i]);
+                format_linear_combination(coefficients, &letters)
+            })
+            .collect();
+        let is_compact = components
+            .iter()
+            .all(|c| c.trim_start_matches('-').chars().count() == 1);
+        if is_compact {
+            components.concat()
+        } else {
+            components.join(",")
+        }
+    }
+}
+
+/// scales the vector to the shortest integer vector in the same direction
+fn primitive_integer_vector(vec: RecVec3) -> RecVec3 {
+    primitive_integer([vec.h(), vec.k(), vec.l()]).into()
+}
+
+/// formats the linear combination such as h+2l, 0 if all coefficients are zero
+fn format_linear_combination(coefficients: impl Iterator<Item = Frac>, letters: &[char]) -> String {
+    let mut string = String::new();
+    for (coeff, letter) in coefficients.zip(letters) {
+        if coeff != 0.into() {
+            string.push_str(&format!("{}{}", coeff.as_signed_prefactor(), letter));
+        }
+    }
+    if string.is_empty() {
+        return "0".to_string();
+    }
+    string.strip_prefix('+').unwrap_or(&string).to_string()
+}
+
+/// formats the condition that the sum of the coefficients times the parameters is an integer
+fn format_condition(coefficients: &[Frac], letters: &[char]) -> String {
+    let mut modulus = coefficients
+        .iter()
+        .fold(1, |acc, val| lcm(acc, val.get_denominator()));
+    let mut integers: Vec<i32> = coefficients
+        .iter()
+        .map(|val| {
+            let int = (*val * Frac::from(modulus)).get_numerator();
+            if 2 * int > modulus {
+                int - modulus
+            } else {
+                int
+            }
+        })
+        .collect();
+    let divisor = integers.iter().fold(modulus, |acc, &val| gcd(acc, val));
+    integers.iter_mut().for_each(|val| *val /= divisor);
+    modulus /= divisor;
+    if integers
+        .iter()
+        .find(|&&val| val != 0)
+        .is_some_and(|&val| val < 0)
+    {
+        integers.iter_mut().for_each(|val| *val = -*val);
+    }
+    format!(
+        "{}={}n",
+        format_linear_combination(integers.into_iter().map(Frac::from), letters),
+        modulus
+    )
+}
+
+/// returns the subgroup of (Q/Z)^n generated by the coefficient vectors
+fn generate_subgroup(generators: &[Vec<Frac>], dimension: usize) -> Vec<Vec<Frac>> {
+    let mut elements = vec![vec![Frac::from(0); dimension]];
+    let mut added_new = true;
+    while added_new {
+        added_new = false;
+        for i in 0..elements.len() {
+            for generator in generators {
+                let sum: Vec<Frac> = elements[i]
+                    .iter()
+                    .zip(generator)
+                    .map(|(a, b)| (*a + *b) % Frac::from(1))
+                    .collect();
+                if !elements.contains(&sum) {
+                    elements.push(sum);
+                    added_new = true;
+                }
+            }
+        }
+    }
+    elements
+}
+
+impl IsometryGroup {
+    /// groups the operations by the zone of reflections they leave invariant
+    fn zones(&self) -> Vec<Zone> {
+        let mut zones: Vec<Zone> = Vec::new();
+        for op in self.get_operations() {
+            let basis = (op.mat() - Mat3::identity()).left_kernel();
+            if basis.is_empty() {
+                continue;
+            }
+            let index = match zones.iter().position(|zone| zone.basis == basis) {
+                Some(index) => index,
+                None => {
+                    zones.push(Zone::new(basis));
+                    zones.len() - 1
+                }
+            };
+            zones[index].translations.push(op.translation());
+        }
+        zones.sort_by(|a, b| {
+            b.basis
+                .len()
+                .cmp(&a.basis.len())
+                .then_with(|| a.name().cmp(&b.name()))
+        });
+        zones
+    }
+
+    /// returns the general and special reflection conditions of the group.
+    /// conditions which are implied by the conditions of a larger zone are omitted.
+    pub fn reflection_conditions(&self) -> Vec<ReflectionCondition> {
+        let zones = self.zones();
+        let mut conditions = Vec::new();
+        for zone in &zones {
+            let mut implied = Vec::new();
+            for other in &zones {
+                if other.basis.len() > zone.basis.len() && zone.is_contained_in(other) {
+                    implied.extend(other.translations.iter().map(|&t| zone.coefficients(t)));
+                }
+            }
+            let mut own: Vec<Vec<Frac>> = zone
+                .translations
+                .iter()
+                .map(|&t| zone.coefficients(t))
+                .collect();
+            // prefer conditions on few indices
+            own.sort_by_key(|c| c.iter().filter(|val| **val != 0.into()).count());
+            let dimension = zone.basis.len();
+            let mut subgroup = generate_subgroup(&implied, dimension);
+            for coefficients in own {
+                if subgroup.contains(&coefficients) {
+                    continue;
+                }
+                conditions.push(ReflectionCondition {
+                    zone: zone.name(),
+                    condition: format_condition(&coefficients, &zone.letters()),
+                    is_general: dimension == 3,
+                });
+                implied.push(coefficients);
+                subgroup = generate_subgroup(&implied, dimension);
+            }
+        }
+        conditions
+    }
+
+    /// returns true if the reflection is systematically absent
+    pub fn is_systematically_absent(&self, hkl: RecVec3) -> bool {
+        self.get_operations().iter().any(|op| {
+            op.mat().row_mul(hkl) == hkl && hkl.dot(&op.translation()).get_denominator() != 1
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn condition_strings(path: &str) -> Vec<String> {
+        IsometryGroup::from_file(path)
+            .unwrap()
+            .reflection_conditions()
+            .iter()
+            .map(ReflectionCondition::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn conditions_test() {
+        assert_eq!(
+            condition_strings("../files/space_groups/P-1"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            condition_strings("../files/space_groups/P2_1"),
+            ["0k0: k=2n"]
+        );
+        assert_eq!(
+            condition_strings("../files/space_groups/Pmna"),
+            // h00: h=2n and 00l: l=2n are implied
+            ["h0l: h+l=2n", "hk0: h=2n"]
+        );
+        assert_eq!(
+            condition_strings("../files/space_groups/Cmcm"),
+            ["hkl: h+k=2n", "h0l: l=2n"]
+        );
+    }
+
+    #[test]
+    fn absence_test() {
+        let sg = IsometryGroup::from_file("../files/space_groups/Cmcm").unwrap();
+        assert!(sg.is_systematically_absent([1, 0, 0].into()));
+        assert!(sg.is_systematically_absent([2, 0, 1].into()));
+        assert!(sg.is_systematically_absent([0, 0, 1].into()));
+        assert!(!sg.is_systematically_absent([1, 1, 0].into()));
+        assert!(!sg.is_systematically_absent([2, 0, 2].into()));
+        assert!(!sg.is_systematically_absent([1, 1, 1].into()));
+        let sg = IsometryGroup::from_file("../files/space_groups/Fm-3m").unwrap();
+        assert!(sg.is_systematically_absent([1, 0, 0].into()));
+        assert!(sg.is_systematically_absent([1, 1, 0].into()));
+        assert!(!sg.is_systematically_absent([1, 1, 1].into()));
+        assert!(!sg.is_systematically_absent([2, 0, 0].into()));
+    }
+}
@@ -0,0 +1,474 @@
+//! # Representations
+//! This module calculates the character tables of point groups and decomposes the
+//! representation of atomic displacements into irreducible representations (irreps), as needed
+//! for a symmetry-mode analysis of displacive disorder.
+//!
+//! The irreps are found from the regular representation: a generic hermitian combination of the
+//! class sums has one eigenspace per irrep, and the projector P onto it gives the characters as
+//! chi(g) = tr(L(g) P) / d with the dimension d = sqrt(tr(P)).
+//! Characters of crystallographic point groups can be complex, they are calculated in floating
+//! point, as are the symmetry-adapted modes.
+
+use std::fmt::Display;
+
+use anyhow::Result;
+use nalgebra::{Complex, DMatrix, Matrix3, Vector3};
+use thiserror::Error;
+
+use crate::{
+    group_theory::CayleyTable,
+    symmetry::{IsometryGroup, PointGroup, PointGroupElement},
+    Bounds3, Pos3,
+};
+
+/// tolerance for comparing floating point characters
+const TOLERANCE: f64 = 1e-6;
+
+#[derive(Error, Debug)]
+enum RepresentationError {
+    #[error("the characters do not belong to a representation, the multiplicity of {0} is {1}")]
+    NotARepresentation(String, f64),
+}
+
+/// the character table of a point group, the irreps are named Γ1, Γ2, ... with Γ1 the trivial
+/// irrep and the others sorted by dimension
+#[derive(Debug, Clone)]
+pub struct CharacterTable {
+    table: CayleyTable<PointGroupElement>,
+    classes: Vec<Vec<usize>>,
+    characters: Vec<Vec<Complex<f64>>>,
+}
+
+impl CharacterTable {
+    fn new(group: &PointGroup) -> Self {
+        let table = group.cayley_table();
+        let order = table.len();
+        let identity = table.identity();
+        let mut classes = table.conjugacy_classes();
+        classes.sort_by_key(|class| !class.contains(&identity));
+        // the class sums in the regular representation, where L(g) e_h = e_gh
+        let regular = |class: &[usize]| {
+            let mut mat = DMatrix::<f64>::zeros(order, order);
+            for &g in class {
+                for h in 0..order {
+                    mat[(table.product(g, h), h)] += 1.0;
+                }
+            }
+            mat
+        };
+        let mut hermitian = DMatrix::<Complex<f64>>::zeros(order, order);
+        for (r, class) in classes.iter().enumerate() {
+            let sum = regular(class);
+            // irrational coefficients separate all irreps
+            let a = ((r + 2) as f64).sqrt();
+            let b = ((r + 3) as f64).ln();
+            let symmetric = &sum + sum.transpose();
+            let antisymmetric = &sum - sum.transpose();
+            hermitian += symmetric.map(|x| Complex::new(a * x, 0.0))
+                + antisymmetric.map(|x| Complex::new(0.0, b * x));
+        }
+        let eigen = hermitian.symmetric_eigen();
+        let mut indices: Vec<usize> = (0..order).collect();
+        indices.sort_by(|&i, &j| eigen.eigenvalues[i].total_cmp(&eigen.eigenvalues[j]));
+        let mut eigenspaces: Vec<Vec<usize>> = Vec::new();
+        for i in indices {
+            match eigenspaces.last_mut() {
+                Some(space)
+                    if (eigen.eigenvalues[space[0]] - eigen.eigenvalues[i]).abs()
+                        < TOLERANCE * order as f64 =>
+                {
+                    space.push(i)
+                }
+                _ => eigenspaces.push(vec![i]),
+            }
+        }
+        let mut characters: Vec<Vec<Complex<f64>>> = eigenspaces
+            .iter()
+            .map(|space| {
+                let vectors = eigen.eigenvectors.select_columns(space);
+                let projector = &vectors * vectors.adjoint();
+                let dimension = (space.len() as f64).sqrt().round();
+                classes
+                    .iter()
+                    .map(|class| {
+                        let g = table.inverse(class[0]);
+                        let trace: Complex<f64> = (0..order)
+                            .map(|k| projector[(table.product(g, k), k)])
+                            .sum();
+                        clean(trace / dimension)
+                    })
+                    .collect()
+            })
+            .collect();
+        characters.sort_by(|a, b| {
+            let is_trivial = |chars: &[Complex<f64>]| {
+                chars
+                    .iter()
+                    .all(|c| (c - Complex::new(1.0, 0.0)).l1_norm() < TOLERANCE)
+            };
+            is_trivial(b)
+                .cmp(&is_trivial(a))
+                .then(a[0].re.total_cmp(&b[0].re))
+                .then_with(|| {
+                    a.iter()
+                        .zip(b)
+                        .map(|(x, y)| y.re.total_cmp(&x.re).then(y.im.total_cmp(&x.im)))
+                        .find(|ord| ord.is_ne())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+        });
+        Self {
+            table,
+            classes,
+            characters,
+        }
+    }
+
+    /// returns the elements of the point group
+    pub fn elements(&self) -> &[PointGroupElement] {
+        self.table.elements()
+    }
+
+    /// returns the conjugacy classes as indices into the elements, the first class is the
+    /// identity
+    pub fn classes(&self) -> &[Vec<usize>] {
+        &self.classes
+    }
+
+    /// returns the number of irreps, which is the number of classes
+    pub fn irrep_count(&self) -> usize {
+        self.characters.len()
+    }
+
+    /// returns the name of the irrep
+    pub fn irrep_name(&self, irrep: usize) -> String {
+        format!("Γ{}", irrep + 1)
+    }
+
+    /// returns the dimension of the irrep
+    pub fn dimension(&self, irrep: usize) -> usize {
+        self.characters[irrep][0].re.round() as usize
+    }
+
+    /// returns the characters of the irrep for each class
+    pub fn class_characters(&self, irrep: usize) -> &[Complex<f64>] {
+        &self.characters[irrep]
+    }
+
+    /// returns the character of the irrep for the element with the given index
+    pub fn character(&self, irrep: usize, element: usize) -> Complex<f64> {
+        let class = self
+            .classes
+            .iter()
+            .position(|class| class.contains(&element))
+            .expect("every element is in a class");
+        self.characters[irrep][class]
+    }
+
+    /// returns true if all characters of the irrep are real
+    pub fn is_real(&self, irrep: usize) -> bool {
+        self.characters[irrep]
+            .iter()
+            .all(|c| c.im.abs() < TOLERANCE)
+    }
+
+    /// returns how often each irrep is contained in the representation with the given
+    /// characters.
+    /// returns Err if the multiplicities are not non-negative integers
+    pub fn decompose(&self, character: impl Fn(PointGroupElement) -> f64) -> Result<Vec<usize>> {
+        let order = self.table.len() as f64;
+        let characters: Vec<f64> = self.elements().iter().map(|&el| character(el)).collect();
+        (0..self.irrep_count())
+            .map(|irrep| {
+                let multiplicity = characters
+                    .iter()
+                    .enumerate()
+                    .map(|(el, chi)| self.character(irrep, el).conj() * chi)
+                    .sum::<Complex<f64>>()
+                    / order;
+                let rounded = multiplicity.re.round();
+                if (multiplicity - rounded).l1_norm() > TOLERANCE || rounded < 0.0 {
+                    return Err(RepresentationError::NotARepresentation(
+                        self.irrep_name(irrep),
+                        multiplicity.re,
+                    )
+                    .into());
+                }
+                Ok(rounded as usize)
+            })
+            .collect()
+    }
+
+    /// returns how the displacements of an atom whose site symmetry is this point group decompose
+    /// into irreps
+    pub fn displacement_decomposition(&self) -> Vec<usize> {
+        self.decompose(|el| el.mat().trace().into())
+            .expect("displacements form a representation")
+    }
+
+    /// returns the real projector onto the displacements transforming like the irrep, in
+    /// fractional coordinates.
+    /// for complex irreps this is the projector P + P* onto the irrep and its complex conjugate
+    /// together, as only their sum is realised by real displacements
+    pub fn projector(&self, irrep: usize) -> Matrix3<f64> {
+        let order = self.table.len() as f64;
+        // the imaginary parts of P and P* cancel, the real parts add up
+        let dimension = if self.is_real(irrep) { 1.0 } else { 2.0 } * self.dimension(irrep) as f64;
+        self.elements()
+            .iter()
+            .enumerate()
+            .map(|(el, op)| {
+                let mat: Matrix3<f64> = op.mat().into();
+                mat * self.character(irrep, el).re
+            })
+            .sum::<Matrix3<f64>>()
+            * (dimension / order)
+    }
+
+    /// returns a basis of the symmetry-adapted displacement modes of the irrep in fractional
+    /// coordinates, each scaled such that its largest component is 1
+    pub fn symmetry_adapted_modes(&self, irrep: usize) -> Vec<Vector3<f64>> {
+        let projector = self.projector(irrep);
+        let mut orthogonal: Vec<Vector3<f64>> = Vec::new();
+        let mut modes = Vec::new();
+        for column in projector.column_iter() {
+            let mut residual: Vector3<f64> = column.into();
+            for vec in &orthogonal {
+                residual -= vec * vec.dot(&residual);
+            }
+            if residual.norm() > TOLERANCE {
+                orthogonal.push(residual.normalize());
+                let max = column.iter().fold(0.0_f64, |acc, x| {
+                    if x.abs() > acc.abs() + TOLERANCE {
+                        *x
+                    } else {
+                        acc
+                    }
+                });
+                modes.push(column / max);
+            }
+        }
+        modes
+    }
+}
+
+impl Display for CharacterTable {
+    /// writes the table with one column per class, headed by its size and a representative
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let headers: Vec<String> = self
+            .classes
+            .iter()
+            .map(|class| format!("{} {}", class.len(), self.elements()[class[0]]))
+            .collect();
+        let rows: Vec<Vec<String>> = self
+            .characters
+            .iter()
+            .map(|chars| chars.iter().map(|&c| complex_string(c)).collect())
+            .collect();
+        let widths: Vec<usize> = (0..headers.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([headers[i].chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let name_width = self.irrep_name(self.irrep_count() - 1).chars().count();
+        write!(f, "{:name_width$}", "")?;
+        for (header, width) in headers.iter().zip(&widths) {
+            write!(f, "  {:>width$}", header)?;
+        }
+        writeln!(f)?;
+        for (irrep, row) in rows.iter().enumerate() {
+            write!(f, "{:name_width$}", self.irrep_name(irrep))?;
+            for (entry, width) in row.iter().zip(&widths) {
+                write!(f, "  {:>width$}", entry)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// rounds values close to integers to remove numerical noise
+fn clean(c: Complex<f64>) -> Complex<f64> {
+    let round = |x: f64| {
+        if (x - x.round()).abs() < TOLERANCE {
+            x.round() + 0.0
+        } else {
+            x
+        }
+    };
+    Complex::new(round(c.re), round(c.im))
+}
+
+/// writes a complex number as e.g. 1, -i or -0.5+0.866i
+fn complex_string(c: Complex<f64>) -> String {
+    let real = format!("{:.3}", c.re);
+    let real = real.trim_end_matches('0').trim_end_matches('.');
+    if c.im.abs() < TOLERANCE {
+        return real.to_string();
+    }
+    let imag = match format!("{:.3}", c.im.abs())
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+    {
+        "1" => String::new(),
+        imag => imag.to_string(),
+    };
+    let sign = if c.im < 0.0 { "-" } else { "+" };
+    if c.re.abs() < TOLERANCE {
+        format!("{}{}i", sign.trim_start_matches('+'), imag)
+    } else {
+        format!("{}{}{}i", real, sign, imag)
+    }
+}
+
+impl PointGroup {
+    /// returns the character table of the point group
+    pub fn character_table(&self) -> CharacterTable {
+        CharacterTable::new(self)
+    }
+}
+
+impl IsometryGroup {
+    /// returns the site symmetry group of the position, the linear parts of all operations
+    /// which leave it invariant
+    pub fn site_symmetry(&self, position: Pos3) -> PointGroup {
+        let position = position % Bounds3::splat(1);
+        let symmetries = self
+            .get_operations()
+            .iter()
+            .filter(|&&op| (op * position) % Bounds3::splat(1) == position)
+            .map(|op| op.reduce_to_point_group_element())
+            .collect();
+        PointGroup::from_closed_symmetries(symmetries)
+            .expect("the stabilizer of a position is a group")
+    }
+
+    /// decomposes the displacements of all atoms in the orbit of the position, within a
+    /// primitive cell, into irreps of the point group, whose table is
+    /// `self.reduce_to_point_group().character_table()`
+    pub fn displacement_decomposition(&self, position: Pos3) -> Result<Vec<usize>> {
+        let position = position % Bounds3::splat(1);
+        let mut orbit: Vec<Pos3> = Vec::new();
+        for op in self.get_operations() {
+            let new_pos = (*op * position) % Bounds3::splat(1);
+            if !orbit.contains(&new_pos) {
+                orbit.push(new_pos);
+            }
+        }
+        // every operation is counted once per centering translation, which compensates for
+        // the centered copies of the atoms in the orbit
+        let centerings = self
+            .get_operations()
+            .iter()
+            .filter(|op| op.mat() == crate::Mat3::identity())
+            .count() as f64;
+        let table = self.reduce_to_point_group().character_table();
+        table.decompose(|el| {
+            let fixed = self
+                .get_operations()
+                .iter()
+                .filter(|op| op.reduce_to_point_group_element() == el)
+                .map(|&op| {
+                    orbit
+                        .iter()
+                        .filter(|&&pos| (op * pos) % Bounds3::splat(1) == pos)
+                        .count()
+                })
+                .sum::<usize>() as f64;
+            fixed / centerings * f64::from(el.mat().trace())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Frac;
+
+    fn point_group(name: &str) -> PointGroup {
+        IsometryGroup::from_file(format!("../files/space_groups/{}", name))
+            .unwrap()
+            .reduce_to_point_group()
+    }
+
+    #[test]
+    fn character_tables() {
+        let mmm = point_group("Pmna").character_table();
+        assert_eq!(mmm.irrep_count(), 8);
+        assert!((0..8).all(|irrep| mmm.dimension(irrep) == 1 && mmm.is_real(irrep)));
+        let m3m = point_group("Fm-3m").character_table();
+        let mut dimensions: Vec<usize> = (0..10).map(|irrep| m3m.dimension(irrep)).collect();
+        dimensions.sort();
+        assert_eq!(dimensions, [1, 1, 1, 1, 2, 2, 3, 3, 3, 3]);
+        // the sum of the squared dimensions is the order
+        assert_eq!(dimensions.iter().map(|d| d * d).sum::<usize>(), 48);
+        // the point group 6 has complex irreps on hexagonal axes
+        let generator = PointGroupElement::new([1, -1, 0, 1, 0, 0, 0, 0, 1].into()).unwrap();
+        let six = PointGroup::from_generators(vec![generator]).character_table();
+        assert_eq!(six.irrep_count(), 6);
+        assert_eq!((0..6).filter(|&irrep| !six.is_real(irrep)).count(), 4);
+        let string = six.to_string();
+        assert_eq!(string.lines().count(), 7);
+        assert!(string.starts_with("    1 x,y,z"));
+        assert!(string.contains("-0.5+0.866i"));
+    }
+
+    #[test]
+    fn displacements() {
+        let sg = IsometryGroup::from_file("../files/space_groups/Fm-3m").unwrap();
+        let table = sg.reduce_to_point_group().character_table();
+        // an atom with full cubic site symmetry moves like a vector, which is irreducible
+        assert_eq!(sg.site_symmetry(Pos3::origin()).len(), 48);
+        let decomposition = table.displacement_decomposition();
+        assert_eq!(decomposition.iter().sum::<usize>(), 1);
+        let vector = decomposition.iter().position(|&m| m == 1).unwrap();
+        assert_eq!(table.dimension(vector), 3);
+        assert_eq!(table.symmetry_adapted_modes(vector).len(), 3);
+        assert_eq!(
+            sg.displacement_decomposition(Pos3::origin()).unwrap(),
+            decomposition
+        );
+        // the two anions of fluorite in the primitive cell give T1u + T2g
+        let quarter = Frac::new(1, 4);
+        let position = Pos3::from([quarter, quarter, quarter]);
+        assert_eq!(sg.site_symmetry(position).len(), 24);
+        let decomposition = sg.displacement_decomposition(position).unwrap();
+        assert_eq!(decomposition.iter().sum::<usize>(), 2);
+        assert_eq!(decomposition[vector], 1);
+        let other = (0..table.irrep_count())
+            .find(|&irrep| irrep != vector && decomposition[irrep] == 1)
+            .unwrap();
+        assert_eq!(table.dimension(other), 3);
+        // the characters of a reducible representation are rejected
+        assert!(table.decompose(|_| 0.5).is_err());
+    }
+
+    #[test]
+    fn modes() {
+        // on a site with symmetry 2 along c, Γ1 moves along c and Γ2 in the plane
+        let two = PointGroupElement::new([-1, 0, 0, 0, -1, 0, 0, 0, 1].into()).unwrap();
+        let table = PointGroup::from_generators(vec![two]).character_table();
+        assert_eq!(table.displacement_decomposition(), [1, 2]);
+        assert_eq!(
+            table.symmetry_adapted_modes(0),
+            [Vector3::new(0.0, 0.0, 1.0)]
+        );
+        assert_eq!(
+            table.symmetry_adapted_modes(1),
+            [Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)]
+        );
+        // in the point group 3 the in-plane displacements form a pair of complex irreps
+        let three = PointGroupElement::new([0, -1, 0, 1, -1, 0, 0, 0, 1].into()).unwrap();
+        let table = PointGroup::from_generators(vec![three]).character_table();
+        for irrep in 0..table.irrep_count() {
+            let projector = table.projector(irrep);
+            assert!((projector * projector - projector).norm() < TOLERANCE);
+        }
+        let complex = (0..3).find(|&irrep| !table.is_real(irrep)).unwrap();
+        assert_eq!(table.symmetry_adapted_modes(complex).len(), 2);
+    }
+}
@@ -0,0 +1,232 @@
+//! # Decimal Positions
+//! This module converts positions given as decimals, as they come from refined structures, to
+//! exact rational positions.
+//!
+//! Decimal coordinates are kept as the closest fraction with the denominator MAX_DENOMINATOR.
+//! A coordinate within the tolerance of a fraction with one of the denominators of special
+//! positions, such as 0.3333 for 1/3, is only snapped to it if this raises the site symmetry of
+//! the position, otherwise general coordinates close to such a fraction would move silently.
+//! Snapping changes the multiplicity of the position, so snapped positions are reported.
+
+use std::fmt::Display;
+
+use pest::iterators::Pair as ParserPair;
+
+use crate::{
+    affine_space::{Bounds, Position},
+    symmetry::IsometryGroup,
+    Frac, Rule,
+};
+
+/// returns the number of operations of the group which leave the position invariant
+fn site_symmetry_order<const N: usize>(group: &IsometryGroup<N>, position: Position<N>) -> usize {
+    let position = position % Bounds::splat(1);
+    group
+        .get_operations()
+        .iter()
+        .filter(|&op| (op * position) % Bounds::splat(1) == position)
+        .count()
+}
+
+/// the tolerance used if none is given
+pub const DEFAULT_TOLERANCE: f64 = 1e-3;
+
+/// the denominators of the coordinates of special positions
+const SPECIAL_DENOMINATORS: [i32; 7] = [1, 2, 3, 4, 6, 8, 12];
+
+/// the denominator of general coordinates, four decimal places are kept
+const MAX_DENOMINATOR: i32 = 10_000;
+
+/// returns the special fraction within the tolerance of the value with the smallest denominator
+/// or None if there is none
+pub fn snap(value: f64, tolerance: f64) -> Option<Frac> {
+    SPECIAL_DENOMINATORS.iter().find_map(|&den| {
+        let num = (value * den as f64).round();
+        ((value - num / den as f64).abs() <= tolerance).then(|| Frac::new(num as i32, den))
+    })
+}
+
+/// returns the closest fraction with the denominator MAX_DENOMINATOR
+pub fn rationalize(value: f64) -> Frac {
+    Frac::new(
+        (value * MAX_DENOMINATOR as f64).round() as i32,
+        MAX_DENOMINATOR,
+    )
+}
+
+/// a position read from the input together with the position it would have without snapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnappedPosition<const N: usize = 3> {
+    position: Position<N>,
+    unsnapped: Position<N>,
+}
+
+impl<const N: usize> SnappedPosition<N> {
+    /// reads a parsed position, exact coordinates are kept and decimal coordinates are reduced
+    /// into the unit cell and snapped with the tolerance if this raises the site symmetry in the
+    /// group.
+    /// of the snapped positions with the highest site symmetry the one snapping the fewest
+    /// coordinates is used
+    pub(crate) fn from_parser(
+        pair: ParserPair<Rule>,
+        tolerance: f64,
+        group: &IsometryGroup<N>,
+    ) -> Self {
+        debug_assert_eq!(pair.as_rule(), Rule::position);
+        let mut snapped: [Option<Frac>; N] = [None; N];
+        let mut unsnapped = [Frac::from(0); N];
+        for (i, coefficient) in pair.into_inner().enumerate() {
+            let mut minus = false;
+            for term in coefficient.into_inner() {
+                match term.as_rule() {
+                    Rule::sign => minus = term.as_str() == "-",
+                    Rule::decimal => {
+                        let value: f64 = term.as_str().parse().expect("enforced by grammar");
+                        // positions are taken modulo the unit cell, reducing the decimal first
+                        // keeps large coordinates within the range of the fractions
+                        let value = if minus { -value } else { value }.rem_euclid(1.0);
+                        unsnapped[i] = rationalize(value) % Frac::from(1);
+                        snapped[i] = snap(value, tolerance)
+                            .map(|frac| frac % Frac::from(1))
+                            .filter(|&frac| frac != unsnapped[i]);
+                    }
+                    Rule::p_rational_num => {
+                        let value: Frac = term.as_str().parse().expect("enforced by grammar");
+                        unsnapped[i] = if minus { -value } else { value };
+                    }
+                    _ => unreachable!("enforced by grammar"),
+                }
+            }
+        }
+        // every subset of the snappable coordinates, the empty one keeps the position as it is
+        let (_, _, position) = (0..1_u32 << N)
+            .filter(|mask| (0..N).all(|i| mask & 1 << i == 0 || snapped[i].is_some()))
+            .map(|mask| {
+                let position = Position::from(std::array::from_fn(|i| match mask & 1 << i {
+                    0 => unsnapped[i],
+                    _ => snapped[i].expect("filtered above"),
+                }));
+                let order = site_symmetry_order(group, position);
+                (order, std::cmp::Reverse(mask.count_ones()), position)
+            })
+            .max_by_key(|&(order, snapped_count, _)| (order, snapped_count))
+            .expect("the position itself is a candidate");
+        Self {
+            position,
+            unsnapped: unsnapped.into(),
+        }
+    }
+
+    /// returns the position used in the calculations
+    pub fn position(&self) -> Position<N> {
+        self.position
+    }
+
+    /// returns the position without snapping
+    pub fn unsnapped(&self) -> Position<N> {
+        self.unsnapped
+    }
+
+    /// returns a warning if snapping the position raises its site symmetry in the group
+    pub fn check_site_symmetry(&self, group: &IsometryGroup<N>) -> Option<SnappingWarning<N>> {
+        let warning = SnappingWarning {
+            position: *self,
+            unsnapped_order: site_symmetry_order(group, self.unsnapped),
+            order: site_symmetry_order(group, self.position),
+        };
+        (warning.order != warning.unsnapped_order).then_some(warning)
+    }
+}
+
+/// a warning that snapping changed the site symmetry of a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnappingWarning<const N: usize = 3> {
+    position: SnappedPosition<N>,
+    unsnapped_order: usize,
+    order: usize,
+}
+
+impl<const N: usize> SnappingWarning<N> {
+    /// returns the position which was snapped
+    pub fn position(&self) -> SnappedPosition<N> {
+        self.position
+    }
+}
+
+impl<const N: usize> Display for SnappingWarning<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "snapping {} to {} changes the order of the site symmetry from {} to {}",
+            self.position.unsnapped, self.position.position, self.unsnapped_order, self.order
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::Config, Pos3};
+
+    #[test]
+    fn snapping() {
+        assert_eq!(snap(0.3333, DEFAULT_TOLERANCE), Some(Frac::new(1, 3)));
+        assert_eq!(snap(-0.1249, DEFAULT_TOLERANCE), Some(Frac::new(-1, 8)));
+        assert_eq!(snap(0.3127, DEFAULT_TOLERANCE), None);
+        assert_eq!(snap(0.3127, 0.03), Some(Frac::new(1, 3)));
+        assert_eq!(rationalize(0.3127), Frac::new(3127, 10_000));
+    }
+
+    #[test]
+    fn decimal_positions() {
+        let input = |positions: &str, tolerance: &str| {
+            format!(
+                "Space Group:\n-x,-y,-z;\nPositions:\n{}\nBounds:\n2,2,2;\n{}",
+                positions, tolerance
+            )
+        };
+        // snapping a general position doesn't raise its site symmetry, so it is kept
+        let config: Config = input("0.4999,0.25,1/3;", "").parse().unwrap();
+        assert_eq!(
+            config.positions(),
+            [Pos3::new(
+                Frac::new(4999, 10_000),
+                Frac::new(1, 4),
+                Frac::new(1, 3)
+            )]
+        );
+        assert!(config.warnings().is_empty());
+        // snapping to the inversion centre at 1/2,1/2,0 raises the site symmetry
+        let config: Config = input("0.4999,0.5001,-0.0002;", "").parse().unwrap();
+        assert_eq!(
+            config.positions(),
+            [Pos3::new(Frac::new(1, 2), Frac::new(1, 2), 0.into())]
+        );
+        assert_eq!(config.warnings().len(), 1);
+        // with a smaller tolerance the general position is kept
+        let config: Config = input("0.4999,0.5001,-0.0002;", "Tolerance:\n0.00001;")
+            .parse()
+            .unwrap();
+        assert_eq!(config.positions()[0].x(), Frac::new(4999, 10_000));
+        assert!(config.warnings().is_empty());
+        // only the coordinates on the two-fold axis along b are snapped
+        let string = "Space Group:\n-x,y,-z;\nPositions:\n0.0001,0.2505,0.4999;";
+        let config: Config = string.parse().unwrap();
+        assert_eq!(
+            config.positions(),
+            [Pos3::new(
+                0.into(),
+                Frac::new(2505, 10_000),
+                Frac::new(1, 2)
+            )]
+        );
+        assert_eq!(config.warnings().len(), 1);
+        // large decimals are reduced into the unit cell
+        let config: Config = input("300000.5,-1.25,0;", "").parse().unwrap();
+        assert_eq!(
+            config.positions(),
+            [Pos3::new(Frac::new(1, 2), Frac::new(3, 4), 0.into())]
+        );
+        assert!(config.warnings().is_empty());
+    }
+}
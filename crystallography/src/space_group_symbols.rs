@@ -0,0 +1,626 @@
+//! # Space Group Symbols
+//! This module constructs the 230 space groups from their Hermann-Mauguin symbol or number.
+//!
+//! The groups are stored as Hall symbols of the standard settings of the International Tables
+//! for Crystallography Vol. A, monoclinic groups with unique axis b and rhombohedral groups on
+//! hexagonal axes. The 24 groups with two origin choices use origin choice 1 unless the choice
+//! is appended like Fd-3m:2 or 227:2. Screw axes may be written with or without the underscore
+//! and mirror planes with | instead of /, so P2_1/c, P21/c and P2_1|c are the same symbol.
+
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::{
+    centering::Centering,
+    symmetry::{Isometry, IsometryGroup},
+    Affine3, Frac, Mat3, Vec3,
+};
+
+#[derive(Error, Debug)]
+enum SymbolError {
+    #[error("unknown space group {0}{}", suggestion_string(.1))]
+    Unknown(String, Vec<&'static str>),
+    #[error("the space group {0} has no origin choice {1}")]
+    OriginChoice(String, String),
+    #[error("invalid Hall symbol {0}")]
+    Hall(&'static str),
+}
+
+fn suggestion_string(suggestions: &[&str]) -> String {
+    match suggestions {
+        [] => String::new(),
+        _ => format!(", did you mean {}?", suggestions.join(", ")),
+    }
+}
+
+/// the largest edit distance of suggested symbols
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// the number, Hermann-Mauguin symbol, origin choice and Hall symbol of the standard settings
+#[rustfmt::skip]
+const SPACE_GROUPS: [(u8, &str, Option<char>, &str); 260] = [
+    (1, "P1", None, "P 1"),
+    (2, "P-1", None, "-P 1"),
+    (3, "P2", None, "P 2y"),
+    (4, "P2_1", None, "P 2yb"),
+    (5, "C2", None, "C 2y"),
+    (6, "Pm", None, "P -2y"),
+    (7, "Pc", None, "P -2yc"),
+    (8, "Cm", None, "C -2y"),
+    (9, "Cc", None, "C -2yc"),
+    (10, "P2/m", None, "-P 2y"),
+    (11, "P2_1/m", None, "-P 2yb"),
+    (12, "C2/m", None, "-C 2y"),
+    (13, "P2/c", None, "-P 2yc"),
+    (14, "P2_1/c", None, "-P 2ybc"),
+    (15, "C2/c", None, "-C 2yc"),
+    (16, "P222", None, "P 2 2"),
+    (17, "P222_1", None, "P 2c 2"),
+    (18, "P2_12_12", None, "P 2 2ab"),
+    (19, "P2_12_12_1", None, "P 2ac 2ab"),
+    (20, "C222_1", None, "C 2c 2"),
+    (21, "C222", None, "C 2 2"),
+    (22, "F222", None, "F 2 2"),
+    (23, "I222", None, "I 2 2"),
+    (24, "I2_12_12_1", None, "I 2b 2c"),
+    (25, "Pmm2", None, "P 2 -2"),
+    (26, "Pmc2_1", None, "P 2c -2"),
+    (27, "Pcc2", None, "P 2 -2c"),
+    (28, "Pma2", None, "P 2 -2a"),
+    (29, "Pca2_1", None, "P 2c -2ac"),
+    (30, "Pnc2", None, "P 2 -2bc"),
+    (31, "Pmn2_1", None, "P 2ac -2"),
+    (32, "Pba2", None, "P 2 -2ab"),
+    (33, "Pna2_1", None, "P 2c -2n"),
+    (34, "Pnn2", None, "P 2 -2n"),
+    (35, "Cmm2", None, "C 2 -2"),
+    (36, "Cmc2_1", None, "C 2c -2"),
+    (37, "Ccc2", None, "C 2 -2c"),
+    (38, "Amm2", None, "A 2 -2"),
+    (39, "Aem2", None, "A 2 -2c"),
+    (39, "Abm2", None, "A 2 -2c"),
+    (40, "Ama2", None, "A 2 -2a"),
+    (41, "Aea2", None, "A 2 -2ac"),
+    (41, "Aba2", None, "A 2 -2ac"),
+    (42, "Fmm2", None, "F 2 -2"),
+    (43, "Fdd2", None, "F 2 -2d"),
+    (44, "Imm2", None, "I 2 -2"),
+    (45, "Iba2", None, "I 2 -2c"),
+    (46, "Ima2", None, "I 2 -2a"),
+    (47, "Pmmm", None, "-P 2 2"),
+    (48, "Pnnn", Some('1'), "P 2 2 -1n"),
+    (48, "Pnnn", Some('2'), "-P 2ab 2bc"),
+    (49, "Pccm", None, "-P 2 2c"),
+    (50, "Pban", Some('1'), "P 2 2 -1ab"),
+    (50, "Pban", Some('2'), "-P 2ab 2b"),
+    (51, "Pmma", None, "-P 2a 2a"),
+    (52, "Pnna", None, "-P 2a 2bc"),
+    (53, "Pmna", None, "-P 2ac 2"),
+    (54, "Pcca", None, "-P 2a 2ac"),
+    (55, "Pbam", None, "-P 2 2ab"),
+    (56, "Pccn", None, "-P 2ab 2ac"),
+    (57, "Pbcm", None, "-P 2c 2b"),
+    (58, "Pnnm", None, "-P 2 2n"),
+    (59, "Pmmn", Some('1'), "P 2 2ab -1ab"),
+    (59, "Pmmn", Some('2'), "-P 2ab 2a"),
+    (60, "Pbcn", None, "-P 2n 2ab"),
+    (61, "Pbca", None, "-P 2ac 2ab"),
+    (62, "Pnma", None, "-P 2ac 2n"),
+    (63, "Cmcm", None, "-C 2c 2"),
+    (64, "Cmce", None, "-C 2bc 2"),
+    (64, "Cmca", None, "-C 2bc 2"),
+    (65, "Cmmm", None, "-C 2 2"),
+    (66, "Cccm", None, "-C 2 2c"),
+    (67, "Cmme", None, "-C 2a 2"),
+    (67, "Cmma", None, "-C 2a 2"),
+    (68, "Ccce", Some('1'), "C 2 2 -1ac"),
+    (68, "Ccce", Some('2'), "-C 2a 2ac"),
+    (68, "Ccca", Some('1'), "C 2 2 -1ac"),
+    (68, "Ccca", Some('2'), "-C 2a 2ac"),
+    (69, "Fmmm", None, "-F 2 2"),
+    (70, "Fddd", Some('1'), "F 2 2 -1d"),
+    (70, "Fddd", Some('2'), "-F 2uv 2vw"),
+    (71, "Immm", None, "-I 2 2"),
+    (72, "Ibam", None, "-I 2 2c"),
+    (73, "Ibca", None, "-I 2b 2c"),
+    (74, "Imma", None, "-I 2b 2"),
+    (75, "P4", None, "P 4"),
+    (76, "P4_1", None, "P 4w"),
+    (77, "P4_2", None, "P 4c"),
+    (78, "P4_3", None, "P 4cw"),
+    (79, "I4", None, "I 4"),
+    (80, "I4_1", None, "I 4bw"),
+    (81, "P-4", None, "P -4"),
+    (82, "I-4", None, "I -4"),
+    (83, "P4/m", None, "-P 4"),
+    (84, "P4_2/m", None, "-P 4c"),
+    (85, "P4/n", Some('1'), "P 4ab -1ab"),
+    (85, "P4/n", Some('2'), "-P 4a"),
+    (86, "P4_2/n", Some('1'), "P 4n -1n"),
+    (86, "P4_2/n", Some('2'), "-P 4bc"),
+    (87, "I4/m", None, "-I 4"),
+    (88, "I4_1/a", Some('1'), "I 4bw -1bw"),
+    (88, "I4_1/a", Some('2'), "-I 4ad"),
+    (89, "P422", None, "P 4 2"),
+    (90, "P42_12", None, "P 4ab 2ab"),
+    (91, "P4_122", None, "P 4w 2c"),
+    (92, "P4_12_12", None, "P 4abw 2nw"),
+    (93, "P4_222", None, "P 4c 2"),
+    (94, "P4_22_12", None, "P 4n 2n"),
+    (95, "P4_322", None, "P 4cw 2c"),
+    (96, "P4_32_12", None, "P 4nw 2abw"),
+    (97, "I422", None, "I 4 2"),
+    (98, "I4_122", None, "I 4bw 2bw"),
+    (99, "P4mm", None, "P 4 -2"),
+    (100, "P4bm", None, "P 4 -2ab"),
+    (101, "P4_2cm", None, "P 4c -2c"),
+    (102, "P4_2nm", None, "P 4n -2n"),
+    (103, "P4cc", None, "P 4 -2c"),
+    (104, "P4nc", None, "P 4 -2n"),
+    (105, "P4_2mc", None, "P 4c -2"),
+    (106, "P4_2bc", None, "P 4c -2ab"),
+    (107, "I4mm", None, "I 4 -2"),
+    (108, "I4cm", None, "I 4 -2c"),
+    (109, "I4_1md", None, "I 4bw -2"),
+    (110, "I4_1cd", None, "I 4bw -2c"),
+    (111, "P-42m", None, "P -4 2"),
+    (112, "P-42c", None, "P -4 2c"),
+    (113, "P-42_1m", None, "P -4 2ab"),
+    (114, "P-42_1c", None, "P -4 2n"),
+    (115, "P-4m2", None, "P -4 -2"),
+    (116, "P-4c2", None, "P -4 -2c"),
+    (117, "P-4b2", None, "P -4 -2ab"),
+    (118, "P-4n2", None, "P -4 -2n"),
+    (119, "I-4m2", None, "I -4 -2"),
+    (120, "I-4c2", None, "I -4 -2c"),
+    (121, "I-42m", None, "I -4 2"),
+    (122, "I-42d", None, "I -4 2bw"),
+    (123, "P4/mmm", None, "-P 4 2"),
+    (124, "P4/mcc", None, "-P 4 2c"),
+    (125, "P4/nbm", Some('1'), "P 4 2 -1ab"),
+    (125, "P4/nbm", Some('2'), "-P 4a 2b"),
+    (126, "P4/nnc", Some('1'), "P 4 2 -1n"),
+    (126, "P4/nnc", Some('2'), "-P 4a 2bc"),
+    (127, "P4/mbm", None, "-P 4 2ab"),
+    (128, "P4/mnc", None, "-P 4 2n"),
+    (129, "P4/nmm", Some('1'), "P 4ab 2ab -1ab"),
+    (129, "P4/nmm", Some('2'), "-P 4a 2a"),
+    (130, "P4/ncc", Some('1'), "P 4ab 2n -1ab"),
+    (130, "P4/ncc", Some('2'), "-P 4a 2ac"),
+    (131, "P4_2/mmc", None, "-P 4c 2"),
+    (132, "P4_2/mcm", None, "-P 4c 2c"),
+    (133, "P4_2/nbc", Some('1'), "P 4n 2c -1n"),
+    (133, "P4_2/nbc", Some('2'), "-P 4ac 2b"),
+    (134, "P4_2/nnm", Some('1'), "P 4n 2 -1n"),
+    (134, "P4_2/nnm", Some('2'), "-P 4ac 2bc"),
+    (135, "P4_2/mbc", None, "-P 4c 2ab"),
+    (136, "P4_2/mnm", None, "-P 4n 2n"),
+    (137, "P4_2/nmc", Some('1'), "P 4n 2n -1n"),
+    (137, "P4_2/nmc", Some('2'), "-P 4ac 2a"),
+    (138, "P4_2/ncm", Some('1'), "P 4n 2ab -1n"),
+    (138, "P4_2/ncm", Some('2'), "-P 4ac 2ac"),
+    (139, "I4/mmm", None, "-I 4 2"),
+    (140, "I4/mcm", None, "-I 4 2c"),
+    (141, "I4_1/amd", Some('1'), "I 4bw 2bw -1bw"),
+    (141, "I4_1/amd", Some('2'), "-I 4bd 2"),
+    (142, "I4_1/acd", Some('1'), "I 4bw 2aw -1bw"),
+    (142, "I4_1/acd", Some('2'), "-I 4bd 2c"),
+    (143, "P3", None, "P 3"),
+    (144, "P3_1", None, "P 31"),
+    (145, "P3_2", None, "P 32"),
+    (146, "R3", None, "R 3"),
+    (147, "P-3", None, "-P 3"),
+    (148, "R-3", None, "-R 3"),
+    (149, "P312", None, "P 3 2"),
+    (150, "P321", None, "P 3 2\""),
+    (151, "P3_112", None, "P 31 2c (0 0 1)"),
+    (152, "P3_121", None, "P 31 2\""),
+    (153, "P3_212", None, "P 32 2c (0 0 -1)"),
+    (154, "P3_221", None, "P 32 2\""),
+    (155, "R32", None, "R 3 2\""),
+    (156, "P3m1", None, "P 3 -2\""),
+    (157, "P31m", None, "P 3 -2"),
+    (158, "P3c1", None, "P 3 -2\"c"),
+    (159, "P31c", None, "P 3 -2c"),
+    (160, "R3m", None, "R 3 -2\""),
+    (161, "R3c", None, "R 3 -2\"c"),
+    (162, "P-31m", None, "-P 3 2"),
+    (163, "P-31c", None, "-P 3 2c"),
+    (164, "P-3m1", None, "-P 3 2\""),
+    (165, "P-3c1", None, "-P 3 2\"c"),
+    (166, "R-3m", None, "-R 3 2\""),
+    (167, "R-3c", None, "-R 3 2\"c"),
+    (168, "P6", None, "P 6"),
+    (169, "P6_1", None, "P 61"),
+    (170, "P6_5", None, "P 65"),
+    (171, "P6_2", None, "P 62"),
+    (172, "P6_4", None, "P 64"),
+    (173, "P6_3", None, "P 6c"),
+    (174, "P-6", None, "P -6"),
+    (175, "P6/m", None, "-P 6"),
+    (176, "P6_3/m", None, "-P 6c"),
+    (177, "P622", None, "P 6 2"),
+    (178, "P6_122", None, "P 61 2 (0 0 -1)"),
+    (179, "P6_522", None, "P 65 2 (0 0 1)"),
+    (180, "P6_222", None, "P 62 2c (0 0 1)"),
+    (181, "P6_422", None, "P 64 2c (0 0 -1)"),
+    (182, "P6_322", None, "P 6c 2c"),
+    (183, "P6mm", None, "P 6 -2"),
+    (184, "P6cc", None, "P 6 -2c"),
+    (185, "P6_3cm", None, "P 6c -2"),
+    (186, "P6_3mc", None, "P 6c -2c"),
+    (187, "P-6m2", None, "P -6 2"),
+    (188, "P-6c2", None, "P -6c 2"),
+    (189, "P-62m", None, "P -6 -2"),
+    (190, "P-62c", None, "P -6c -2c"),
+    (191, "P6/mmm", None, "-P 6 2"),
+    (192, "P6/mcc", None, "-P 6 2c"),
+    (193, "P6_3/mcm", None, "-P 6c 2"),
+    (194, "P6_3/mmc", None, "-P 6c 2c"),
+    (195, "P23", None, "P 2 2 3"),
+    (196, "F23", None, "F 2 2 3"),
+    (197, "I23", None, "I 2 2 3"),
+    (198, "P2_13", None, "P 2ac 2ab 3"),
+    (199, "I2_13", None, "I 2b 2c 3"),
+    (200, "Pm-3", None, "-P 2 2 3"),
+    (201, "Pn-3", Some('1'), "P 2 2 3 -1n"),
+    (201, "Pn-3", Some('2'), "-P 2ab 2bc 3"),
+    (202, "Fm-3", None, "-F 2 2 3"),
+    (203, "Fd-3", Some('1'), "F 2 2 3 -1d"),
+    (203, "Fd-3", Some('2'), "-F 2uv 2vw 3"),
+    (204, "Im-3", None, "-I 2 2 3"),
+    (205, "Pa-3", None, "-P 2ac 2ab 3"),
+    (206, "Ia-3", None, "-I 2b 2c 3"),
+    (207, "P432", None, "P 4 2 3"),
+    (208, "P4_232", None, "P 4n 2 3"),
+    (209, "F432", None, "F 4 2 3"),
+    (210, "F4_132", None, "F 4d 2 3"),
+    (211, "I432", None, "I 4 2 3"),
+    (212, "P4_332", None, "P 4acd 2ab 3"),
+    (213, "P4_132", None, "P 4bd 2ab 3"),
+    (214, "I4_132", None, "I 4bd 2c 3"),
+    (215, "P-43m", None, "P -4 2 3"),
+    (216, "F-43m", None, "F -4 2 3"),
+    (217, "I-43m", None, "I -4 2 3"),
+    (218, "P-43n", None, "P -4n 2 3"),
+    (219, "F-43c", None, "F -4c 2 3"),
+    (220, "I-43d", None, "I -4bd 2c 3"),
+    (221, "Pm-3m", None, "-P 4 2 3"),
+    (222, "Pn-3n", Some('1'), "P 4 2 3 -1n"),
+    (222, "Pn-3n", Some('2'), "-P 4a 2bc 3"),
+    (223, "Pm-3n", None, "-P 4n 2 3"),
+    (224, "Pn-3m", Some('1'), "P 4n 2 3 -1n"),
+    (224, "Pn-3m", Some('2'), "-P 4bc 2bc 3"),
+    (225, "Fm-3m", None, "-F 4 2 3"),
+    (226, "Fm-3c", None, "-F 4c 2 3"),
+    (227, "Fd-3m", Some('1'), "F 4d 2 3 -1d"),
+    (227, "Fd-3m", Some('2'), "-F 4vw 2vw 3"),
+    (228, "Fd-3c", Some('1'), "F 4d 2 3 -1cd"),
+    (228, "Fd-3c", Some('2'), "-F 4cvw 2vw 3"),
+    (229, "Im-3m", None, "-I 4 2 3"),
+    (230, "Ia-3d", None, "-I 4bd 2c 3"),
+];
+
+/// removes the underscores of screw axes and replaces | by /
+fn normalize(symbol: &str) -> String {
+    symbol.replace('_', "").replace('|', "/")
+}
+
+/// returns the number of single character edits needed to turn a into b
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// returns the symbols of the table closest to the unknown symbol, ignoring the case
+pub fn suggestions(symbol: &str) -> Vec<&'static str> {
+    let symbol = normalize(symbol).to_lowercase();
+    let mut found: Vec<(usize, &str)> = Vec::new();
+    for &(_, known, _, _) in &SPACE_GROUPS {
+        let distance = edit_distance(&symbol, &normalize(known).to_lowercase());
+        if distance <= MAX_SUGGESTION_DISTANCE && !found.iter().any(|&(_, s)| s == known) {
+            found.push((distance, known));
+        }
+    }
+    // the sort is stable, so symbols with the same distance stay ordered by number
+    found.sort_by_key(|&(distance, _)| distance);
+    found.into_iter().take(5).map(|(_, known)| known).collect()
+}
+
+/// returns the Hall symbol of the space group given by its symbol or number and an optional
+/// origin choice such as Fd-3m:2.
+/// returns Err if the symbol is unknown, with the closest symbols as suggestions
+pub fn hall_symbol(symbol: &str) -> Result<&'static str> {
+    let (name, choice) = match symbol.split_once(':') {
+        Some((name, choice)) => (name.trim(), Some(choice.trim())),
+        None => (symbol.trim(), None),
+    };
+    let number: Option<u8> = name.parse().ok();
+    let entries: Vec<_> = SPACE_GROUPS
+        .iter()
+        .filter(|&&(n, known, _, _)| match number {
+            Some(number) => n == number,
+            None => normalize(known) == normalize(name),
+        })
+        .collect();
+    if entries.is_empty() {
+        return Err(SymbolError::Unknown(name.to_string(), suggestions(name)).into());
+    }
+    let entry = match choice {
+        None => entries[0],
+        Some(choice) => entries
+            .iter()
+            .find(|(_, _, origin, _)| origin.is_some_and(|origin| choice == origin.to_string()))
+            .ok_or_else(|| SymbolError::OriginChoice(name.to_string(), choice.to_string()))?,
+    };
+    Ok(entry.3)
+}
+
+/// returns the matrix of a rotation of order n (1, 2, 3, 4 or 6) around the axis. the axes
+/// are x, y, z, the primed and double primed directions ' and " perpendicular to them
+/// and the body diagonal *
+fn rotation(order: u8, axis: char, prime: Option<char>) -> Option<Mat3> {
+    let mat = match (order, axis, prime) {
+        (1, _, None) => [1, 0, 0, 0, 1, 0, 0, 0, 1],
+        (2, 'x', None) => [1, 0, 0, 0, -1, 0, 0, 0, -1],
+        (2, 'y', None) => [-1, 0, 0, 0, 1, 0, 0, 0, -1],
+        (2, 'z', None) => [-1, 0, 0, 0, -1, 0, 0, 0, 1],
+        (2, 'x', Some('\'')) => [-1, 0, 0, 0, 0, -1, 0, -1, 0],
+        (2, 'y', Some('\'')) => [0, 0, -1, 0, -1, 0, -1, 0, 0],
+        (2, 'z', Some('\'')) => [0, -1, 0, -1, 0, 0, 0, 0, -1],
+        (2, 'x', Some('"')) => [-1, 0, 0, 0, 0, 1, 0, 1, 0],
+        (2, 'y', Some('"')) => [0, 0, 1, 0, -1, 0, 1, 0, 0],
+        (2, 'z', Some('"')) => [0, 1, 0, 1, 0, 0, 0, 0, -1],
+        (3, 'x', None) => [1, 0, 0, 0, 0, -1, 0, 1, -1],
+        (3, 'y', None) => [-1, 0, 1, 0, 1, 0, -1, 0, 0],
+        (3, 'z', None) => [0, -1, 0, 1, -1, 0, 0, 0, 1],
+        (3, '*', None) => [0, 0, 1, 1, 0, 0, 0, 1, 0],
+        (4, 'x', None) => [1, 0, 0, 0, 0, -1, 0, 1, 0],
+        (4, 'y', None) => [0, 0, 1, 0, 1, 0, -1, 0, 0],
+        (4, 'z', None) => [0, -1, 0, 1, 0, 0, 0, 0, 1],
+        (6, 'x', None) => [1, 0, 0, 0, 1, -1, 0, 1, 0],
+        (6, 'y', None) => [0, 0, 1, 0, 1, 0, -1, 0, 1],
+        (6, 'z', None) => [1, -1, 0, 1, 0, 0, 0, 0, 1],
+        _ => return None,
+    };
+    Some(Mat3::from(mat))
+}
+
+/// returns the translation of a translation symbol of a Hall symbol
+fn hall_translation(symbol: char) -> Option<Vec3> {
+    let half = Frac::new(1, 2);
+    let quarter = Frac::new(1, 4);
+    let zero = Frac::from(0);
+    let vec = match symbol {
+        'a' => [half, zero, zero],
+        'b' => [zero, half, zero],
+        'c' => [zero, zero, half],
+        'n' => [half, half, half],
+        'u' => [quarter, zero, zero],
+        'v' => [zero, quarter, zero],
+        'w' => [zero, zero, quarter],
+        'd' => [quarter, quarter, quarter],
+        _ => return None,
+    };
+    Some(Vec3::from(vec))
+}
+
+/// reads one matrix symbol of a Hall symbol such as -2yc or 61. the default axis depends on
+/// the position of the symbol and the order of the preceding one
+fn hall_generator(
+    symbol: &'static str,
+    index: usize,
+    previous: Option<(u8, char)>,
+) -> Result<(Isometry, u8, char)> {
+    let invalid = || SymbolError::Hall(symbol);
+    let mut chars = symbol.chars().peekable();
+    let improper = chars.next_if_eq(&'-').is_some();
+    let order = chars
+        .next()
+        .and_then(|c| c.to_digit(10))
+        .ok_or_else(invalid)? as u8;
+    let screw = chars
+        .next_if(|c| c.is_ascii_digit())
+        .map(|c| c.to_digit(10).expect("is a digit") as i32);
+    let axis = chars.next_if(|c| matches!(c, 'x' | 'y' | 'z' | '*'));
+    let mut prime = chars.next_if(|&c| c == '\'' || c == '"');
+    let translations: Vec<char> = chars.collect();
+    let axis = match (axis, index, previous) {
+        (Some(axis), _, _) => axis,
+        // primed axes are perpendicular to the preceding one
+        (None, _, Some((_, previous_axis))) if prime.is_some() => previous_axis,
+        (None, 1, Some((2 | 4, _))) if order == 2 => 'x',
+        (None, 1, Some((3 | 6, previous_axis))) if order == 2 => {
+            prime = Some('\'');
+            previous_axis
+        }
+        (None, 2, _) if order == 3 => '*',
+        (None, _, _) => 'z',
+    };
+    let mut mat = rotation(order, axis, prime).ok_or_else(invalid)?;
+    if improper {
+        mat = mat * Frac::from(-1);
+    }
+    let mut translation = Vec3::zero();
+    if let Some(screw) = screw {
+        let shift = Frac::new(screw, order.into());
+        translation += match axis {
+            'x' => Vec3::new(shift, 0.into(), 0.into()),
+            'y' => Vec3::new(0.into(), shift, 0.into()),
+            'z' => Vec3::new(0.into(), 0.into(), shift),
+            _ => return Err(invalid().into()),
+        };
+    }
+    for c in translations {
+        translation += hall_translation(c).ok_or_else(invalid)?;
+    }
+    let op = Isometry::new(Affine3::new(mat, translation))?;
+    Ok((op, order, axis))
+}
+
+/// constructs the group of a Hall symbol. only the origin shifts such as (0 0 1) in twelfths
+/// are read as change of basis, which is all the standard settings need
+fn hall_group(hall: &'static str) -> Result<IsometryGroup> {
+    let invalid = || SymbolError::Hall(hall);
+    let (symbols, shift) = match hall.split_once('(') {
+        Some((symbols, shift)) => {
+            let shift: Vec<Frac> = shift
+                .trim_end_matches(')')
+                .split_whitespace()
+                .map(|v| v.parse::<i32>().map(|v| Frac::new(v, 12)))
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid())?;
+            let shift: [Frac; 3] = shift.try_into().map_err(|_| invalid())?;
+            (symbols, Vec3::from(shift))
+        }
+        None => (hall, Vec3::zero()),
+    };
+    let mut tokens = symbols.split_whitespace();
+    let lattice = tokens.next().ok_or_else(invalid)?;
+    let (centrosymmetric, lattice) = match lattice.strip_prefix('-') {
+        Some(lattice) => (true, lattice),
+        None => (false, lattice),
+    };
+    let centering = match lattice {
+        "P" => Centering::P,
+        "A" => Centering::A,
+        "B" => Centering::B,
+        "C" => Centering::C,
+        "I" => Centering::I,
+        "F" => Centering::F,
+        "R" => Centering::R,
+        _ => return Err(invalid().into()),
+    };
+    let mut generators = Vec::new();
+    if centrosymmetric {
+        generators.push(Isometry::from_mat(Mat3::identity() * Frac::from(-1))?);
+    }
+    let mut previous = None;
+    for (index, token) in tokens.enumerate() {
+        let (op, order, axis) = hall_generator(token, index, previous)?;
+        generators.push(op);
+        previous = Some((order, axis));
+    }
+    // the origin shift moves the operations (W, w) to (W, w + v - W v)
+    let mut generators: Vec<Isometry> = generators
+        .into_iter()
+        .map(|op| {
+            let translation = op.translation() + shift - op.mat() * shift;
+            Isometry::new(Affine3::new(op.mat(), translation))
+        })
+        .collect::<Result<_>>()?;
+    generators.extend(
+        centering
+            .translations()
+            .into_iter()
+            .map(Isometry::from_translation),
+    );
+    Ok(IsometryGroup::from_generators(generators))
+}
+
+impl IsometryGroup {
+    /// constructs the space group from its Hermann-Mauguin symbol or number such as Fm-3m or
+    /// 225, optionally followed by the origin choice like Fd-3m:2.
+    /// returns Err with suggestions if the symbol is unknown
+    pub fn from_symbol(symbol: &str) -> Result<Self> {
+        hall_group(hall_symbol(symbol)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// the point group symbol of the space group symbol as used by the crystal classes
+    fn point_group_symbol(symbol: &str) -> String {
+        let mut point_group = String::new();
+        let mut chars = symbol.chars().skip(1).peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '_' => {
+                    chars.next();
+                }
+                'a' | 'b' | 'c' | 'n' | 'd' | 'e' => point_group.push('m'),
+                c => point_group.push(c),
+            }
+        }
+        match point_group.as_str() {
+            "312" | "321" => "32".into(),
+            "31m" | "3m1" => "3m".into(),
+            "-31m" | "-3m1" => "-3m".into(),
+            "-4m2" => "-42m".into(),
+            "-62m" => "-6m2".into(),
+            _ => point_group,
+        }
+    }
+
+    #[test]
+    fn table() {
+        for (number, symbol, choice, hall) in SPACE_GROUPS {
+            let group = hall_group(hall).unwrap();
+            let class = group.crystal_class().unwrap();
+            assert_eq!(class.symbol(), point_group_symbol(symbol), "{}", symbol);
+            let lattice_points = group.centering().unwrap().lattice_points();
+            assert_eq!(group.len(), class.order() * lattice_points, "{}", symbol);
+            // origin choice 2 is at an inversion centre
+            if choice == Some('2') {
+                assert!(
+                    group.contains(Isometry::from_mat(Mat3::identity() * Frac::from(-1)).unwrap())
+                );
+            }
+            let name = match choice {
+                Some(choice) => format!("{}:{}", number, choice),
+                None => number.to_string(),
+            };
+            assert_eq!(IsometryGroup::from_symbol(&name).unwrap(), group);
+        }
+    }
+
+    #[test]
+    fn same_as_files() {
+        for name in [
+            "C2|m",
+            "Cmcm",
+            "Fm-3m",
+            "P-1",
+            "P2_1",
+            "P2_12_12",
+            "P2_12_12_1",
+            "P6_3|mmc",
+            "Pmna",
+            "R-3m",
+        ] {
+            let sg = IsometryGroup::from_file(format!("../files/space_groups/{}", name)).unwrap();
+            assert_eq!(IsometryGroup::from_symbol(name).unwrap(), sg, "{}", name);
+        }
+        assert_eq!(
+            IsometryGroup::from_symbol("P21/c").unwrap(),
+            IsometryGroup::from_symbol("14").unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_symbols() {
+        let error = IsometryGroup::from_symbol("P2_1/n")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("P2_1/c"), "{}", error);
+        assert!(suggestions("fm-3m").contains(&"Fm-3m"));
+        assert!(IsometryGroup::from_symbol("231").is_err());
+        assert!(IsometryGroup::from_symbol("Fm-3m:2").is_err());
+        assert_ne!(
+            IsometryGroup::from_symbol("Fd-3m:1").unwrap(),
+            IsometryGroup::from_symbol("Fd-3m:2").unwrap()
+        );
+    }
+}
@@ -0,0 +1,853 @@
+//! # Subgroups
+//! This module enumerates maximal subgroups of space groups and describes how sites and pair
+//! expansions split when the symmetry is lowered.
+//!
+//! A translationengleiche (t-) subgroup keeps all translations and has a smaller point group,
+//! a klassengleiche (k-) subgroup keeps the point group and loses translations, either
+//! centering translations or by enlarging the cell.
+//! Subgroups are given in their own setting together with the transformation from the setting
+//! of the group they were derived from. k-subgroups keep the cell of the group if they contain
+//! all its unit translations, otherwise the new cell is a primitive cell of their lattice.
+//! Only sublattices of index at most max_index are considered.
+
+use crate::{
+    affine_space::Matrix,
+    frac::lcm,
+    group_theory::CayleyTable,
+    objects::{PairExpansion, ReducedSite, Site},
+    symmetry::{Isometry, IsometryGroup, PointGroupElement},
+    transformation::Transformation,
+    Affine3, Bounds3, Frac, Mat3, Pos3, Vec3,
+};
+
+/// the relation of a subgroup to the group it was derived from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubgroupKind {
+    /// all translations are kept, the point group is reduced
+    Translationengleiche,
+    /// the point group is kept, translations are lost
+    Klassengleiche,
+    /// both the point group and the translations are reduced
+    General,
+}
+
+/// a subgroup of a space group in its own setting
+#[derive(Debug, Clone)]
+pub struct Subgroup {
+    group: IsometryGroup,
+    transformation: Transformation,
+    index: usize,
+    kind: SubgroupKind,
+}
+
+impl Subgroup {
+    /// returns the subgroup in its own setting
+    pub fn group(&self) -> &IsometryGroup {
+        &self.group
+    }
+
+    /// returns the transformation from the setting of the group to the setting of the subgroup
+    pub fn transformation(&self) -> Transformation {
+        self.transformation
+    }
+
+    /// returns the index of the subgroup in the group
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// returns whether the subgroup is a t-, k- or general subgroup
+    pub fn kind(&self) -> SubgroupKind {
+        self.kind
+    }
+
+    /// composes the subgroup with a subgroup of it, e.g. one of its maximal subgroups.
+    /// the result is the subgroup of the original group with the product of the indices
+    pub fn then(&self, subgroup: &Subgroup) -> Subgroup {
+        let kind = if self.index == 1 {
+            subgroup.kind
+        } else if subgroup.index == 1 || self.kind == subgroup.kind {
+            self.kind
+        } else {
+            SubgroupKind::General
+        };
+        Subgroup {
+            group: subgroup.group.clone(),
+            transformation: self.transformation.then(&subgroup.transformation),
+            index: self.index * subgroup.index,
+            kind,
+        }
+    }
+
+    /// returns true if the operation, given in the setting of the group, is in the subgroup
+    pub fn contains(&self, op: Isometry) -> bool {
+        self.group
+            .contains(self.transformation.transform_isometry(op))
+    }
+
+    /// returns the basis vectors of the cell of the subgroup as translations in the setting of
+    /// the group
+    fn cell_translations(&self) -> [Isometry; 3] {
+        let mat = self.transformation.mat();
+        [[1, 0, 0], [0, 1, 0], [0, 0, 1]]
+            .map(|basis| Isometry::from_translation(mat * Vec3::from(basis)))
+    }
+
+    /// returns true if the subgroup contains all translations of this subgroup
+    fn contains_translations_of(&self, other: &Subgroup) -> bool {
+        let back = other.transformation.inverse();
+        other
+            .group
+            .translations()
+            .into_iter()
+            .map(|vec| back.transform_isometry(Isometry::from_translation(vec)))
+            .chain(other.cell_translations())
+            .all(|op| self.contains(op))
+    }
+
+    /// returns true if this subgroup is contained in the other subgroup of the same group
+    pub fn is_subgroup_of(&self, other: &Subgroup) -> bool {
+        let back = self.transformation.inverse();
+        self.group
+            .get_operations()
+            .iter()
+            .map(|&op| back.transform_isometry(op))
+            .chain(self.cell_translations())
+            .all(|op| other.contains(op))
+    }
+
+    /// splits the site of the group at the position into the sites of the subgroup.
+    /// the positions are given in the setting of the subgroup
+    pub fn split_site(&self, group: &IsometryGroup, position: Pos3) -> Vec<ReducedSite> {
+        let group = self
+            .transformation
+            .transform_group(group)
+            .expect("the cell of a subgroup consists of translations of the group");
+        let position = self.transformation.transform_position(position);
+        let mut remaining: Vec<Pos3> = Site::new(&group, position)
+            .orbit_in_bounds(Bounds3::splat(1))
+            .collect();
+        let mut sites = Vec::new();
+        while let Some(&position) = remaining.first() {
+            let site = Site::new(&self.group, position);
+            remaining.retain(|&pos| !site.contains_pos(pos));
+            sites.push(site.to_reduced_site());
+        }
+        sites
+    }
+
+    /// splits the pair expansion of the group into the pair types of the subgroup.
+    /// returns the origin position and pair vector in the setting of the subgroup and the
+    /// number of ordered pairs per unit cell of the subgroup for each new pair type
+    pub fn split_pair_expansion(
+        &self,
+        group: &IsometryGroup,
+        expansion: &PairExpansion,
+    ) -> Vec<(Pos3, Vec3, usize)> {
+        let group = self
+            .transformation
+            .transform_group(group)
+            .expect("the cell of a subgroup consists of translations of the group");
+        let origin = self
+            .transformation
+            .transform_position(expansion.origin_position());
+        let vec = self.transformation.transform_vector(expansion.vector());
+        let mut remaining = pair_orbit(&group, origin, vec);
+        let mut pairs = Vec::new();
+        while let Some(&(origin, vec)) = remaining.first() {
+            let orbit = pair_orbit(&self.group, origin, vec);
+            remaining.retain(|pair| !orbit.contains(pair));
+            pairs.push((origin, vec, orbit.len()));
+        }
+        pairs
+    }
+}
+
+/// returns the ordered pairs equivalent to the pair or its reverse, reduced to the unit cell
+fn pair_orbit(group: &IsometryGroup, origin: Pos3, vec: Vec3) -> Vec<(Pos3, Vec3)> {
+    let mut orbit = Vec::new();
+    for (origin, vec) in [(origin, vec), (origin + vec, -vec)] {
+        for &op in group.get_operations() {
+            let pair = ((op * origin) % Bounds3::splat(1), op * vec);
+            if !orbit.contains(&pair) {
+                orbit.push(pair);
+            }
+        }
+    }
+    orbit
+}
+
+/// the decomposition of a group into left cosets gH of a subgroup H.
+/// the cosets are given in the setting of the subgroup, modulo its lattice
+pub struct CosetDecomposition {
+    transformation: Transformation,
+    representatives: Vec<Isometry>,
+    cosets: Vec<Vec<Isometry>>,
+}
+
+impl CosetDecomposition {
+    /// returns one representative of each coset in the setting of the group, the first one is
+    /// the identity
+    pub fn representatives(&self) -> &[Isometry] {
+        &self.representatives
+    }
+
+    /// returns the elements of each coset in the setting of the subgroup, the first coset is the
+    /// subgroup itself
+    pub fn cosets(&self) -> &[Vec<Isometry>] {
+        &self.cosets
+    }
+
+    /// returns the index of the coset containing the operation, given in the setting of the
+    /// group. returns None if the operation is not in the group
+    pub fn coset_of(&self, op: Isometry) -> Option<usize> {
+        let op = self.transformation.transform_isometry(op) % Bounds3::splat(1);
+        self.cosets.iter().position(|coset| coset.contains(&op))
+    }
+}
+
+/// a pair expansion of a subgroup which is part of a pair expansion of the group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PairChild {
+    origin: Pos3,
+    vector: Vec3,
+    multiplicity: usize,
+    expansion: Option<usize>,
+}
+
+impl PairChild {
+    /// returns the origin position in the setting of the subgroup
+    pub fn origin_position(&self) -> Pos3 {
+        self.origin
+    }
+
+    /// returns the pair vector in the setting of the subgroup
+    pub fn vector(&self) -> Vec3 {
+        self.vector
+    }
+
+    /// returns the number of ordered pairs of this type per unit cell of the subgroup
+    pub fn multiplicity(&self) -> usize {
+        self.multiplicity
+    }
+
+    /// returns the index of the pair expansion of the subgroup, None if it was not calculated
+    pub fn expansion(&self) -> Option<usize> {
+        self.expansion
+    }
+}
+
+/// maps each pair expansion of the group to the pair expansions of the subgroup it splits into.
+/// the expansions of the subgroup must be calculated in the setting of the subgroup with the
+/// given bounds, which should describe the same supercell as the bounds of the group.
+/// the multiplicities count the pairs in the infinite crystal, which differs from
+/// PairExpansion::multiplicity for vectors that are their own negative modulo the bounds.
+pub fn map_pair_expansions(
+    group: &IsometryGroup,
+    subgroup: &Subgroup,
+    expansions: &[PairExpansion],
+    sub_expansions: &[PairExpansion],
+    sub_bounds: Bounds3,
+) -> Vec<Vec<PairChild>> {
+    let reduce = |(pos, vec): (Pos3, Vec3)| (pos, vec % sub_bounds);
+    expansions
+        .iter()
+        .map(|expansion| {
+            subgroup
+                .split_pair_expansion(group, expansion)
+                .into_iter()
+                .map(|(origin, vector, multiplicity)| {
+                    let orbit: Vec<(Pos3, Vec3)> = pair_orbit(subgroup.group(), origin, vector)
+                        .into_iter()
+                        .map(reduce)
+                        .collect();
+                    let expansion = sub_expansions.iter().position(|exp| {
+                        orbit.contains(&reduce((exp.origin_position(), exp.vector())))
+                    });
+                    PairChild {
+                        origin,
+                        vector,
+                        multiplicity,
+                        expansion,
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// assigns the correlation of each pair expansion of the group to its children in the subgroup.
+/// returns one value per pair expansion of the subgroup, None for expansions which are not a
+/// child of any of the mapped expansions
+pub fn lift_correlations(
+    mapping: &[Vec<PairChild>],
+    correlations: &[f64],
+    sub_expansion_count: usize,
+) -> Vec<Option<f64>> {
+    let mut lifted = vec![None; sub_expansion_count];
+    for (children, &correlation) in mapping.iter().zip(correlations) {
+        for child in children {
+            if let Some(i) = child.expansion {
+                lifted[i] = Some(correlation);
+            }
+        }
+    }
+    lifted
+}
+
+/// the subgroups of a group between the group and a target subgroup, connected by maximal
+/// subgroup relations
+pub struct SubgroupLattice {
+    nodes: Vec<Subgroup>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl SubgroupLattice {
+    /// returns the subgroups, the first one is the group itself and all are given relative to it
+    pub fn nodes(&self) -> &[Subgroup] {
+        &self.nodes
+    }
+
+    /// returns the pairs (group, maximal subgroup) as indices into the nodes
+    pub fn edges(&self) -> &[(usize, usize)] {
+        &self.edges
+    }
+
+    /// returns all chains of maximal subgroups from the group to the target as indices into the
+    /// nodes
+    pub fn chains(&self) -> Vec<Vec<usize>> {
+        let target = self
+            .nodes
+            .iter()
+            .map(Subgroup::index)
+            .max()
+            .expect("the lattice contains the group");
+        let mut chains = Vec::new();
+        let mut stack = vec![vec![0]];
+        while let Some(chain) = stack.pop() {
+            let last = *chain.last().expect("chains are never empty");
+            if self.nodes[last].index == target {
+                chains.push(chain);
+                continue;
+            }
+            for &(from, to) in &self.edges {
+                if from == last {
+                    let mut longer = chain.clone();
+                    longer.push(to);
+                    stack.push(longer);
+                }
+            }
+        }
+        chains.sort();
+        chains
+    }
+}
+
+/// subgroups of point groups are represented as bitmasks over the elements of their table
+impl CayleyTable<PointGroupElement> {
+    fn full(&self) -> u64 {
+        if self.elements.len() == 64 {
+            u64::MAX
+        } else {
+            (1 << self.elements.len()) - 1
+        }
+    }
+
+    /// returns the subgroup generated by the elements of the mask
+    fn closure(&self, mut mask: u64) -> u64 {
+        loop {
+            let mut new = mask;
+            for i in (0..self.elements.len()).filter(|i| mask & (1 << i) != 0) {
+                for j in (0..self.elements.len()).filter(|j| mask & (1 << j) != 0) {
+                    new |= 1 << self.table[i][j];
+                }
+            }
+            if new == mask {
+                return mask;
+            }
+            mask = new;
+        }
+    }
+
+    /// returns all subgroups
+    fn subgroups(&self) -> Vec<u64> {
+        let mut subgroups: Vec<u64> = Vec::new();
+        for i in 0..self.elements.len() {
+            let cyclic = self.closure(1 << i);
+            if !subgroups.contains(&cyclic) {
+                subgroups.push(cyclic);
+            }
+        }
+        let mut k = 0;
+        while k < subgroups.len() {
+            for i in 0..self.elements.len() {
+                let joined = self.closure(subgroups[k] | (1 << i));
+                if !subgroups.contains(&joined) {
+                    subgroups.push(joined);
+                }
+            }
+            k += 1;
+        }
+        subgroups
+    }
+
+    /// returns the maximal proper subgroups
+    fn maximal_subgroups(&self) -> Vec<u64> {
+        let full = self.full();
+        let proper: Vec<u64> = self
+            .subgroups()
+            .into_iter()
+            .filter(|&mask| mask != full)
+            .collect();
+        proper
+            .iter()
+            .copied()
+            .filter(|&mask| {
+                !proper
+                    .iter()
+                    .any(|&other| other != mask && other & mask == mask)
+            })
+            .collect()
+    }
+}
+
+/// returns a basis of the lattice generated by the vectors as the columns of a matrix.
+/// the vectors must span the whole space
+fn lattice_basis(vectors: &[Vec3]) -> Mat3 {
+    let denominator = vectors.iter().fold(1, |acc, vec| {
+        [vec.x(), vec.y(), vec.z()]
+            .iter()
+            .fold(acc, |acc, val| lcm(acc, val.get_denominator()))
+    });
+    let mut rows: Vec<[i32; 3]> = vectors
+        .iter()
+        .map(|vec| {
+            [vec.x(), vec.y(), vec.z()].map(|val| (val * Frac::from(denominator)).get_numerator())
+        })
+        .collect();
+    // integer row reduction to echelon form
+    for col in 0..3 {
+        loop {
+            let pivot = (col..rows.len())
+                .filter(|&i| rows[i][col] != 0)
+                .min_by_key(|&i| rows[i][col].abs())
+                .expect("the vectors span the whole space");
+            rows.swap(col, pivot);
+            let mut done = true;
+            for i in col + 1..rows.len() {
+                let factor = rows[i][col] / rows[col][col];
+                let pivot_row = rows[col];
+                for (val, pivot_val) in rows[i].iter_mut().zip(pivot_row) {
+                    *val -= factor * pivot_val;
+                }
+                done &= rows[i][col] == 0;
+            }
+            if done {
+                break;
+            }
+        }
+    }
+    let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(rows[i]) / Frac::from(denominator));
+    Mat3::from_columns(a, b, c)
+}
+
+/// returns true if all entries of the matrix are integers
+pub(crate) fn is_integral<const N: usize>(mat: Matrix<N>) -> bool {
+    (0..N).all(|i| (0..N).all(|j| mat.entry(i, j).get_denominator() == 1))
+}
+
+/// returns the bases of all sublattices of index n of the integer lattice as columns of a
+/// matrix in hermite normal form
+fn sublattices(n: i32) -> Vec<Mat3> {
+    let mut out = Vec::new();
+    for a in (1..=n).filter(|a| n % a == 0) {
+        for d in (1..=n / a).filter(|d| (n / a) % d == 0) {
+            let f = n / a / d;
+            for b in 0..a {
+                for c in 0..a {
+                    for e in 0..d {
+                        out.push([a, b, c, 0, d, e, 0, 0, f].into());
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+impl IsometryGroup {
+    /// decomposes the group into left cosets of the subgroup
+    pub fn coset_decomposition(&self, subgroup: &Subgroup) -> CosetDecomposition {
+        let transformation = subgroup.transformation();
+        let back = transformation.inverse();
+        let mut remaining = transformation
+            .transform_group(self)
+            .expect("the cell of a subgroup consists of translations of the group")
+            .get_operations()
+            .to_vec();
+        // start with the identity so that the first coset is the subgroup
+        remaining.sort();
+        let identity = Isometry::new(Affine3::identity()).expect("identity is an Isometry");
+        if let Some(i) = remaining.iter().position(|&op| op == identity) {
+            remaining.swap(0, i);
+        }
+        let mut representatives = Vec::new();
+        let mut cosets = Vec::new();
+        while let Some(&representative) = remaining.first() {
+            let coset: Vec<Isometry> = subgroup
+                .group()
+                .get_operations()
+                .iter()
+                .map(|&op| (representative * op) % Bounds3::splat(1))
+                .collect();
+            remaining.retain(|op| !coset.contains(op));
+            representatives.push(back.transform_isometry(representative));
+            cosets.push(coset);
+        }
+        CosetDecomposition {
+            transformation,
+            representatives,
+            cosets,
+        }
+    }
+
+    /// returns the pure translations of the group, including the zero translation
+    fn translations(&self) -> Vec<Vec3> {
+        self.get_operations()
+            .iter()
+            .filter(|op| op.mat() == Mat3::identity())
+            .map(|op| op.translation())
+            .collect()
+    }
+
+    /// returns the maximal translationengleiche subgroups
+    pub fn maximal_t_subgroups(&self) -> Vec<Subgroup> {
+        let table = self.reduce_to_point_group().cayley_table();
+        let order = table.elements.len();
+        let mut subgroups = Vec::new();
+        for mask in table.maximal_subgroups() {
+            let mats: Vec<Mat3> = (0..order)
+                .filter(|i| mask & (1 << i) != 0)
+                .map(|i| table.elements[i].mat())
+                .collect();
+            let ops = self
+                .get_operations()
+                .iter()
+                .filter(|op| mats.contains(&op.mat()))
+                .copied()
+                .collect();
+            subgroups.push(Subgroup {
+                group: IsometryGroup::from_closed_symmetries(ops).expect("a t-subgroup is closed"),
+                transformation: Transformation::identity(),
+                index: order / mats.len(),
+                kind: SubgroupKind::Translationengleiche,
+            });
+        }
+        subgroups
+    }
+
+    /// returns the maximal klassengleiche subgroups whose lattice has index at most max_index in
+    /// the lattice of the group
+    pub fn maximal_k_subgroups(&self, max_index: usize) -> Vec<Subgroup> {
+        let mut candidates: Vec<Subgroup> = Vec::new();
+        for n in 2..=max_index as i32 {
+            for subgroup in self.k_subgroups_of_index(n) {
+                let is_new = !candidates
+                    .iter()
+                    .any(|other| other.is_subgroup_of(&subgroup) && subgroup.is_subgroup_of(other));
+                if is_new {
+                    candidates.push(subgroup);
+                }
+            }
+        }
+        candidates
+            .iter()
+            .filter(|subgroup| {
+                !candidates.iter().any(|other| {
+                    other.index < subgroup.index
+                        && subgroup.index.is_multiple_of(other.index)
+                        && subgroup.is_subgroup_of(other)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// returns all klassengleiche subgroups whose lattice has index n in the lattice of the group
+    fn k_subgroups_of_index(&self, n: i32) -> Vec<Subgroup> {
+        let point_group = self.reduce_to_point_group();
+        let table = point_group.cayley_table();
+        let translations = self.translations();
+        let mut generators = translations.clone();
+        generators.extend([[1, 0, 0], [0, 1, 0], [0, 0, 1]].map(Vec3::from));
+        let basis = lattice_basis(&generators);
+        let mut subgroups = Vec::new();
+        for sublattice in sublattices(n) {
+            let lattice = basis * sublattice;
+            let inverse = lattice.inverse().expect("lattice bases are invertible");
+            let is_invariant = point_group
+                .iter()
+                .all(|op| is_integral(inverse * op.mat() * lattice));
+            if !is_invariant {
+                continue;
+            }
+            let mat = if is_integral(inverse) {
+                Mat3::identity()
+            } else {
+                lattice
+            };
+            let inverse_mat = mat.inverse().expect("lattice bases are invertible");
+            let transformation =
+                Transformation::from_basis_change(mat).expect("lattice bases are invertible");
+            let transformed = transformation
+                .transform_group(self)
+                .expect("the new basis vectors are translations of the group");
+            let kept_translations: Vec<Isometry> = [[1, 0, 0], [0, 1, 0], [0, 0, 1]]
+                .map(|b| {
+                    let vec = transformation.transform_vector(lattice * Vec3::from(b));
+                    Isometry::from_translation(vec)
+                })
+                .into();
+            let centering = IsometryGroup::from_generators(kept_translations.clone()).len();
+            // every generator of the point group may be combined with any lost translation
+            let choices: Vec<Vec<Isometry>> = table
+                .minimal_generators()
+                .iter()
+                .map(|&i| {
+                    let mat = inverse_mat * table.elements[i].mat() * mat;
+                    transformed
+                        .get_operations()
+                        .iter()
+                        .filter(|op| op.mat() == mat)
+                        .copied()
+                        .collect()
+                })
+                .collect();
+            let mut found: Vec<IsometryGroup> = Vec::new();
+            let mut indices = vec![0; choices.len()];
+            loop {
+                let mut generators = kept_translations.clone();
+                generators.extend(indices.iter().zip(&choices).map(|(&i, ops)| ops[i]));
+                let group = IsometryGroup::from_generators(generators);
+                let is_new = !found.iter().any(|other| {
+                    other.len() == group.len()
+                        && group.get_operations().iter().all(|&op| other.contains(op))
+                });
+                if group.len() == point_group.len() * centering && is_new {
+                    found.push(group.clone());
+                    subgroups.push(Subgroup {
+                        group,
+                        transformation,
+                        index: n as usize,
+                        kind: SubgroupKind::Klassengleiche,
+                    });
+                }
+                // advance to the next combination
+                let Some(position) =
+                    (0..indices.len()).find(|&k| indices[k] + 1 < choices[k].len())
+                else {
+                    break;
+                };
+                indices[position] += 1;
+                indices[..position].iter_mut().for_each(|i| *i = 0);
+            }
+        }
+        subgroups
+    }
+
+    /// returns the maximal t- and k-subgroups, k-subgroups up to the index max_index
+    pub fn maximal_subgroups(&self, max_index: usize) -> Vec<Subgroup> {
+        let mut subgroups = self.maximal_t_subgroups();
+        subgroups.extend(self.maximal_k_subgroups(max_index));
+        subgroups
+    }
+
+    /// builds the lattice of all subgroups between the group and the target subgroup which
+    /// can be reached by chains of maximal subgroups. k-subgroups are searched up to the index
+    /// of the target. the index of the target is relative to this group, subgroups of subgroups
+    /// are brought into this form by Subgroup::then.
+    pub fn subgroup_lattice(&self, target: &Subgroup) -> SubgroupLattice {
+        let mut nodes = vec![Subgroup {
+            group: self.clone(),
+            transformation: Transformation::identity(),
+            index: 1,
+            kind: SubgroupKind::Translationengleiche,
+        }];
+        let mut edges = Vec::new();
+        let mut k = 0;
+        while k < nodes.len() {
+            let node = nodes[k].clone();
+            k += 1;
+            if node.index >= target.index {
+                continue;
+            }
+            // k-subgroups can't contain a target which has all translations of the node
+            let max_index = if target.contains_translations_of(&node) {
+                1
+            } else {
+                target.index / node.index
+            };
+            for maximal in node.group.maximal_subgroups(max_index) {
+                let candidate = node.then(&maximal);
+                let index = candidate.index;
+                if !target.index.is_multiple_of(index) || !target.is_subgroup_of(&candidate) {
+                    continue;
+                }
+                let existing = nodes.iter().position(|other| {
+                    other.index == index
+                        && other.is_subgroup_of(&candidate)
+                        && candidate.is_subgroup_of(other)
+                });
+                let position = existing.unwrap_or_else(|| {
+                    nodes.push(candidate);
+                    nodes.len() - 1
+                });
+                if !edges.contains(&(k - 1, position)) {
+                    edges.push((k - 1, position));
+                }
+            }
+        }
+        SubgroupLattice { nodes, edges }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::objects::calculate_pairs;
+
+    #[test]
+    fn t_subgroups() {
+        let sg = IsometryGroup::from_file("../files/space_groups/Pmna").unwrap();
+        let subgroups = sg.maximal_t_subgroups();
+        // mmm has the seven maximal subgroups 222, mm2 (three times), 2/m (three times)
+        assert_eq!(subgroups.len(), 7);
+        assert!(subgroups.iter().all(|sub| sub.index() == 2));
+        let sg = IsometryGroup::from_file("../files/space_groups/Fm-3m").unwrap();
+        let mut indices: Vec<usize> = sg.maximal_t_subgroups().iter().map(|s| s.index()).collect();
+        indices.sort();
+        // m-3, 432, -43m and the four conjugates of -3m and the three conjugates of 4/mmm
+        assert_eq!(indices, [2, 2, 2, 3, 3, 3, 4, 4, 4, 4]);
+    }
+
+    #[test]
+    fn k_subgroups() {
+        let sg = IsometryGroup::from_file("../files/space_groups/C2|m").unwrap();
+        let subgroups = sg.maximal_k_subgroups(2);
+        // losing the centering gives P2/m, P2_1/m, P2/c and P2_1/c in the same cell
+        let same_cell: Vec<&Subgroup> = subgroups
+            .iter()
+            .filter(|sub| sub.transformation().mat() == Mat3::identity())
+            .collect();
+        assert_eq!(same_cell.len(), 4);
+        for sub in &subgroups {
+            assert_eq!(sub.kind(), SubgroupKind::Klassengleiche);
+            assert_eq!(sub.index(), 2);
+            assert_eq!(sub.group().reduce_to_point_group().len(), 4);
+        }
+        let sg = IsometryGroup::from_file("../files/space_groups/P-1").unwrap();
+        // doubling any of the seven index two sublattices gives two subgroups each
+        assert_eq!(sg.maximal_k_subgroups(2).len(), 14);
+    }
+
+    #[test]
+    fn splitting() {
+        let sg = IsometryGroup::from_file("../files/space_groups/P-1").unwrap();
+        let doubled = sg
+            .maximal_k_subgroups(2)
+            .into_iter()
+            .find(|sub| {
+                sub.transformation().mat() == [2, 0, 0, 0, 1, 0, 0, 0, 1].into()
+                    && sub
+                        .contains(Isometry::from_mat((-Frac::from(1)) * Mat3::identity()).unwrap())
+            })
+            .unwrap();
+        // the inversion centers at 0 and 1/2 become independent
+        let sites = doubled.split_site(&sg, Pos3::origin());
+        assert_eq!(sites.len(), 2);
+        assert!(sites.iter().all(|site| site.multiplicity() == 1));
+        let expansions = calculate_pairs(sg.clone(), vec![Pos3::origin()], [3, 1, 1].into(), false);
+        for expansion in &expansions {
+            let split = doubled.split_pair_expansion(&sg, expansion);
+            let total: usize = split.iter().map(|(_, _, count)| count).sum();
+            assert_eq!(total, 2 * expansion.multiplicity());
+        }
+    }
+
+    #[test]
+    fn cosets() {
+        let sg = IsometryGroup::from_file("../files/space_groups/Fm-3m").unwrap();
+        for subgroup in sg.maximal_t_subgroups() {
+            let decomposition = sg.coset_decomposition(&subgroup);
+            assert_eq!(decomposition.cosets().len(), subgroup.index());
+            assert!(decomposition.cosets()[0]
+                .iter()
+                .all(|&op| subgroup.group().contains(op)));
+            for (i, &rep) in decomposition.representatives().iter().enumerate() {
+                assert_eq!(decomposition.coset_of(rep), Some(i));
+            }
+        }
+        let sg = IsometryGroup::from_file("../files/space_groups/P2_1").unwrap();
+        let doubled = sg
+            .maximal_k_subgroups(2)
+            .into_iter()
+            .find(|sub| sub.transformation().mat() == [2, 0, 0, 0, 1, 0, 0, 0, 1].into())
+            .unwrap();
+        let decomposition = sg.coset_decomposition(&doubled);
+        assert_eq!(decomposition.cosets().len(), 2);
+        // the lost translation is not in the subgroup
+        let translation = Isometry::from_translation([1, 0, 0].into());
+        assert_eq!(decomposition.coset_of(translation), Some(1));
+    }
+
+    #[test]
+    fn pair_mapping() {
+        let sg = IsometryGroup::from_file("../files/space_groups/P-1").unwrap();
+        let doubled = sg
+            .maximal_k_subgroups(2)
+            .into_iter()
+            .find(|sub| {
+                sub.transformation().mat() == [2, 0, 0, 0, 1, 0, 0, 0, 1].into()
+                    && sub
+                        .contains(Isometry::from_mat((-Frac::from(1)) * Mat3::identity()).unwrap())
+            })
+            .unwrap();
+        let expansions = calculate_pairs(sg.clone(), vec![Pos3::origin()], [4, 1, 1].into(), false);
+        let positions: Vec<Pos3> = doubled
+            .split_site(&sg, Pos3::origin())
+            .iter()
+            .map(|site| site.position())
+            .collect();
+        let sub_bounds = [2, 1, 1].into();
+        let sub_expansions = calculate_pairs(doubled.group().clone(), positions, sub_bounds, true);
+        let mapping = map_pair_expansions(&sg, &doubled, &expansions, &sub_expansions, sub_bounds);
+        for (expansion, children) in expansions.iter().zip(&mapping) {
+            assert!(children.iter().all(|child| child.expansion().is_some()));
+            if (expansion.vector() + expansion.vector()) % Bounds3::from([4, 1, 1]) == Vec3::zero()
+            {
+                // the supercell identifies the vector with its negative
+                continue;
+            }
+            let total: usize = children.iter().map(PairChild::multiplicity).sum();
+            assert_eq!(total, 2 * expansion.multiplicity());
+        }
+        let correlations: Vec<f64> = (0..expansions.len()).map(|i| i as f64).collect();
+        let lifted = lift_correlations(&mapping, &correlations, sub_expansions.len());
+        assert!(lifted.iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn lattice() {
+        let sg = IsometryGroup::from_file("../files/space_groups/Pmna").unwrap();
+        let maximal = sg.maximal_t_subgroups();
+        let target = maximal[0].then(&maximal[0].group().maximal_t_subgroups()[0]);
+        assert_eq!(target.index(), 4);
+        assert_eq!(target.kind(), SubgroupKind::Translationengleiche);
+        let lattice = sg.subgroup_lattice(&target);
+        assert_eq!(lattice.nodes()[0].index(), 1);
+        let chains = lattice.chains();
+        // every subgroup of index 4 of mmm lies in three subgroups of index 2
+        assert_eq!(chains.len(), 3);
+        assert!(chains.iter().all(|chain| chain.len() == 3));
+    }
+}
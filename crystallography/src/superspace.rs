@@ -0,0 +1,735 @@
+//! # Superspace Groups
+//! This module describes the symmetry of incommensurately modulated structures by (3+d)
+//! dimensional superspace groups and calculates site and pair orbits in superspace.
+//!
+//! A superspace operation acts on the coordinates x1, x2 and x3 of the external space and on
+//! the internal coordinates x4 to x(3+d), the phases of the modulations. It never mixes the
+//! internal coordinates into the external ones, so the upper left block of its matrix is an
+//! operation of the basic space group of the average structure.
+//! Like IsometryGroup the groups are represented modulo unit translations in all 3+d
+//! directions. Orbits and pairs are calculated in a supercell of the external space, the
+//! internal coordinates are always taken modulo 1.
+
+use std::{fmt::Display, fs::read_to_string, ops::Mul, path::Path};
+
+use anyhow::Result;
+use pest::{
+    iterators::{Pair as ParserPair, Pairs},
+    Parser,
+};
+use thiserror::Error;
+
+use crate::{
+    group_theory::try_close,
+    orbits::{self, Action},
+    symmetry::{Isometry, IsometryGroup},
+    Affine3, Bounds3, Frac, Mat3, MyParser, Pos3, Rule, Vec3,
+};
+
+#[derive(Error, Debug)]
+enum SuperspaceError {
+    #[error("a superspace has at least 3 dimensions, got {0} coordinates")]
+    TooSmall(usize),
+    #[error("expected {expected} coordinates but got {found}")]
+    Dimension { expected: usize, found: usize },
+    #[error("the coordinate x{0} doesn't exist in {1} dimensions")]
+    UnknownCoordinate(usize, usize),
+    #[error("{0} mixes internal coordinates into the external ones")]
+    MixesInternal(SuperspaceOperation),
+    #[error("{0} doesnt have determinant +/- 1 in external space or superspace")]
+    Determinant(SuperspaceOperation),
+    #[error("the coordinate x{0} appears more than once in {1}")]
+    RepeatedCoordinate(usize, String),
+    #[error("{0} has infinite order")]
+    InfiniteOrder(SuperspaceOperation),
+}
+
+/// the largest order of an integral matrix of finite order in up to nine dimensions
+const MAX_ORDER: usize = 60;
+
+/// a point or a vector of superspace, the first three coordinates are the external ones
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SuperspaceVector(Vec<Frac>);
+
+impl SuperspaceVector {
+    /// constructor returns Err if there are less than three coordinates
+    pub fn new(coordinates: Vec<Frac>) -> Result<Self> {
+        if coordinates.len() < 3 {
+            return Err(SuperspaceError::TooSmall(coordinates.len()).into());
+        }
+        Ok(Self(coordinates))
+    }
+
+    /// returns the number of coordinates
+    pub fn dimension(&self) -> usize {
+        self.0.len()
+    }
+
+    /// returns all coordinates
+    pub fn coordinates(&self) -> &[Frac] {
+        &self.0
+    }
+
+    /// returns the position in external space
+    pub fn external(&self) -> Pos3 {
+        Pos3::new(self.0[0], self.0[1], self.0[2])
+    }
+
+    /// returns the internal coordinates
+    pub fn internal(&self) -> &[Frac] {
+        &self.0[3..]
+    }
+
+    /// reduces the external coordinates into the bounds and the internal coordinates modulo 1
+    pub fn reduce(&self, bounds: Bounds3) -> Self {
+        let bounds = [bounds.x(), bounds.y(), bounds.z()];
+        Self(
+            self.0
+                .iter()
+                .enumerate()
+                .map(|(i, &val)| val % Frac::from(bounds.get(i).copied().unwrap_or(1)))
+                .collect(),
+        )
+    }
+}
+
+impl Display for SuperspaceVector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let coordinates: Vec<String> = self.0.iter().map(Frac::to_string).collect();
+        write!(f, "[{}]", coordinates.join(", "))
+    }
+}
+
+/// a symmetry operation of a superspace group
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SuperspaceOperation {
+    dimension: usize,
+    mat: Vec<Frac>,
+    translation: Vec<Frac>,
+}
+
+impl SuperspaceOperation {
+    /// constructor from the rows of the matrix and the translation.
+    /// returns Err if the sizes don't match, if internal coordinates are mixed into the
+    /// external ones, if the operation doesn't have determinant +/-1 in external space and in
+    /// superspace or if it has infinite order
+    pub fn new(rows: Vec<Vec<Frac>>, translation: Vec<Frac>) -> Result<Self> {
+        let dimension = translation.len();
+        if dimension < 3 {
+            return Err(SuperspaceError::TooSmall(dimension).into());
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != dimension) {
+            return Err(SuperspaceError::Dimension {
+                expected: dimension,
+                found: row.len(),
+            }
+            .into());
+        }
+        if rows.len() != dimension {
+            return Err(SuperspaceError::Dimension {
+                expected: dimension,
+                found: rows.len(),
+            }
+            .into());
+        }
+        let op = Self {
+            dimension,
+            mat: rows.into_iter().flatten().collect(),
+            translation,
+        };
+        if (0..3).any(|i| (3..dimension).any(|j| op.entry(i, j) != 0.into())) {
+            return Err(SuperspaceError::MixesInternal(op).into());
+        }
+        let external = Mat3::from(std::array::from_fn::<Frac, 9, _>(|k| {
+            op.entry(k / 3, k % 3)
+        }));
+        if external.determinant().abs() != 1.into()
+            || determinant(&op.mat, dimension).abs() != 1.into()
+        {
+            return Err(SuperspaceError::Determinant(op).into());
+        }
+        if !op.has_finite_order() {
+            return Err(SuperspaceError::InfiniteOrder(op).into());
+        }
+        Ok(op)
+    }
+
+    /// returns true if a power of the matrix is the identity, such as for x1,x2,x3,-x4 but not
+    /// for the shear x1,x2,x3,x3+x4
+    fn has_finite_order(&self) -> bool {
+        let linear = Self {
+            translation: vec![Frac::from(0); self.dimension],
+            ..self.clone()
+        };
+        let identity = Self::identity(self.dimension);
+        let mut power = linear.clone();
+        for _ in 0..MAX_ORDER {
+            if power == identity {
+                return true;
+            }
+            power = &power * &linear;
+        }
+        false
+    }
+
+    /// returns the identity in the dimension
+    pub fn identity(dimension: usize) -> Self {
+        Self {
+            dimension,
+            mat: (0..dimension * dimension)
+                .map(|k| Frac::from((k / dimension == k % dimension) as i32))
+                .collect(),
+            translation: vec![Frac::from(0); dimension],
+        }
+    }
+
+    /// returns the translation by the vector
+    pub fn from_translation(translation: Vec<Frac>) -> Self {
+        let dimension = translation.len();
+        Self {
+            translation,
+            ..Self::identity(dimension)
+        }
+    }
+
+    /// creates the operation from a parsed superspace operation such as -x1,-x2,x3,-x4+1/2
+    pub(crate) fn from_parser(pair: ParserPair<Rule>) -> Result<Self> {
+        let coefficients: Vec<_> = pair.into_inner().collect();
+        let dimension = coefficients.len();
+        let mut rows = vec![vec![Frac::from(0); dimension]; dimension];
+        let mut translation = vec![Frac::from(0); dimension];
+        for (i, coefficient) in coefficients.into_iter().enumerate() {
+            let mut active_minus = false;
+            let string = coefficient.as_str().to_string();
+            for term in coefficient.into_inner() {
+                let signed = |val: Frac| if active_minus { -val } else { val };
+                match term.as_rule() {
+                    Rule::sign => active_minus = term.as_str() == "-",
+                    Rule::superspace_variable => {
+                        let j: usize = term.as_str()[1..].parse().expect("enforced by grammar");
+                        if j > dimension {
+                            return Err(SuperspaceError::UnknownCoordinate(j, dimension).into());
+                        }
+                        if rows[i][j - 1] != 0.into() {
+                            return Err(SuperspaceError::RepeatedCoordinate(j, string).into());
+                        }
+                        rows[i][j - 1] = signed(1.into());
+                        active_minus = false;
+                    }
+                    Rule::p_rational_num => {
+                        translation[i] =
+                            signed(term.as_str().parse().expect("enforced by grammar"));
+                        active_minus = false;
+                    }
+                    _ => unreachable!("enforced by grammar"),
+                }
+            }
+        }
+        Self::new(rows, translation)
+    }
+
+    /// returns the number of coordinates the operation acts on
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// returns the entry of the matrix in row i and column j
+    pub fn entry(&self, i: usize, j: usize) -> Frac {
+        self.mat[i * self.dimension + j]
+    }
+
+    /// returns the translation part of the operation
+    pub fn translation(&self) -> &[Frac] {
+        &self.translation
+    }
+
+    /// returns the operation of the basic space group acting on external space
+    pub fn external(&self) -> Isometry {
+        let mat = Mat3::from(std::array::from_fn::<Frac, 9, _>(|k| {
+            self.entry(k / 3, k % 3)
+        }));
+        let translation = Vec3::new(
+            self.translation[0],
+            self.translation[1],
+            self.translation[2],
+        );
+        Isometry::new(Affine3::new(mat, translation)).expect("checked by the constructor")
+    }
+
+    /// returns the inverse of the operation
+    pub fn invert(&self) -> Self {
+        let n = self.dimension;
+        // gauss jordan elimination on the matrix augmented by the identity
+        let mut rows: Vec<Vec<Frac>> = (0..n)
+            .map(|i| {
+                (0..2 * n)
+                    .map(|j| match j < n {
+                        true => self.entry(i, j),
+                        false => Frac::from((j - n == i) as i32),
+                    })
+                    .collect()
+            })
+            .collect();
+        for col in 0..n {
+            let pivot_row = (col..n)
+                .find(|&i| rows[i][col] != 0.into())
+                .expect("the determinant is +/-1");
+            rows.swap(col, pivot_row);
+            let pivot = rows[col][col];
+            rows[col].iter_mut().for_each(|val| *val /= pivot);
+            for i in (0..n).filter(|&i| i != col) {
+                let factor = rows[i][col];
+                let reduced = rows[col].clone();
+                rows[i]
+                    .iter_mut()
+                    .zip(reduced.iter())
+                    .for_each(|(val, red)| *val -= factor * *red);
+            }
+        }
+        let mat: Vec<Frac> = rows.iter().flat_map(|row| row[n..].to_vec()).collect();
+        let linear = Self {
+            dimension: n,
+            mat,
+            translation: vec![Frac::from(0); n],
+        };
+        let translation = linear
+            .apply(&self.translation)
+            .into_iter()
+            .map(|val| -val)
+            .collect();
+        Self {
+            translation,
+            ..linear
+        }
+    }
+
+    /// reduces the external translation into the bounds and the internal translation modulo 1
+    pub fn reduce(&self, bounds: Bounds3) -> Self {
+        Self {
+            translation: SuperspaceVector(self.translation.clone()).reduce(bounds).0,
+            ..self.clone()
+        }
+    }
+
+    /// applies the matrix and the translation to the coordinates
+    fn apply(&self, coordinates: &[Frac]) -> Vec<Frac> {
+        (0..self.dimension)
+            .map(|i| {
+                (0..self.dimension).fold(self.translation[i], |acc, j| {
+                    acc + self.entry(i, j) * coordinates[j]
+                })
+            })
+            .collect()
+    }
+}
+
+impl Display for SuperspaceOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<String> = (0..self.dimension)
+            .map(|i| {
+                let mut row = String::new();
+                for j in 0..self.dimension {
+                    let val = self.entry(i, j);
+                    if val != 0.into() {
+                        let prefactor = match row.is_empty() {
+                            true => val.as_prefactor(),
+                            false => val.as_signed_prefactor(),
+                        };
+                        row += &format!("{}x{}", prefactor, j + 1);
+                    }
+                }
+                if self.translation[i] != 0.into() {
+                    row += &match row.is_empty() {
+                        true => self.translation[i].to_string(),
+                        false => self.translation[i].as_string_signed(),
+                    };
+                }
+                row
+            })
+            .collect();
+        write!(f, "{}", rows.join(","))
+    }
+}
+
+impl Mul for &SuperspaceOperation {
+    type Output = SuperspaceOperation;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let n = self.dimension;
+        let mut mat = vec![Frac::from(0); n * n];
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    mat[i * n + j] += self.entry(i, k) * rhs.entry(k, j);
+                }
+            }
+        }
+        SuperspaceOperation {
+            dimension: n,
+            mat,
+            translation: self.apply(&rhs.translation),
+        }
+    }
+}
+
+impl Mul<&SuperspaceVector> for &SuperspaceOperation {
+    type Output = SuperspaceVector;
+
+    fn mul(self, rhs: &SuperspaceVector) -> Self::Output {
+        SuperspaceVector(self.apply(&rhs.0))
+    }
+}
+
+/// returns the determinant of the square matrix given by its rows
+fn determinant(mat: &[Frac], n: usize) -> Frac {
+    let mut rows: Vec<Vec<Frac>> = mat.chunks(n).map(<[Frac]>::to_vec).collect();
+    let mut det = Frac::from(1);
+    for col in 0..n {
+        let Some(pivot_row) = (col..n).find(|&i| rows[i][col] != 0.into()) else {
+            return 0.into();
+        };
+        if pivot_row != col {
+            rows.swap(col, pivot_row);
+            det = -det;
+        }
+        let pivot = rows[col][col];
+        det *= pivot;
+        for i in col + 1..n {
+            let factor = rows[i][col] / pivot;
+            let reduced = rows[col].clone();
+            rows[i]
+                .iter_mut()
+                .zip(reduced.iter())
+                .for_each(|(val, red)| *val -= factor * *red);
+        }
+    }
+    det
+}
+
+/// a superspace group represented modulo unit translations
+#[derive(Debug, Clone)]
+pub struct SuperspaceGroup {
+    dimension: usize,
+    symmetries: Vec<SuperspaceOperation>,
+}
+
+impl SuperspaceGroup {
+    /// closes the operations under multiplication modulo unit translations like
+    /// IsometryGroup::from_generators.
+    /// returns Err if an operation doesn't act on the given number of coordinates or if a
+    /// product of the operations has infinite order, then the group is infinite.
+    /// panics if this cannot be done within 10_000 iterations to prevent an infinite loop.
+    pub fn from_generators(dimension: usize, generators: Vec<SuperspaceOperation>) -> Result<Self> {
+        let mut elements = vec![SuperspaceOperation::identity(dimension)];
+        for op in generators {
+            if op.dimension != dimension {
+                return Err(SuperspaceError::Dimension {
+                    expected: dimension,
+                    found: op.dimension,
+                }
+                .into());
+            }
+            elements.push(op.reduce(Bounds3::splat(1)));
+        }
+        let symmetries = try_close(
+            elements,
+            |a, b| (a * b).reduce(Bounds3::splat(1)),
+            |op| match op.has_finite_order() {
+                true => Ok(()),
+                false => Err(SuperspaceError::InfiniteOrder(op.clone())),
+            },
+        )?;
+        Ok(Self {
+            dimension,
+            symmetries,
+        })
+    }
+
+    /// creates the group generated by the parsed superspace operations, the dimension is taken
+    /// from the first one
+    pub fn from_superspace_list(pairs: Pairs<Rule>) -> Result<Self> {
+        let generators = pairs
+            .map(SuperspaceOperation::from_parser)
+            .collect::<Result<Vec<_>>>()?;
+        let dimension = generators[0].dimension;
+        Self::from_generators(dimension, generators)
+    }
+
+    /// reads a list of superspace operations such as -x1,-x2,x3,-x4; from a file and closes it
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let string = read_to_string(path)?;
+        let parsed = MyParser::parse(Rule::superspace_list, &string)?
+            .next()
+            .expect("never fails");
+        Self::from_superspace_list(parsed.into_inner())
+    }
+
+    /// returns the number of coordinates
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// returns the cardinality of the quotient group with unit translations
+    pub fn len(&self) -> usize {
+        self.symmetries.len()
+    }
+
+    /// returns true if the group has no elements, which never happens for a constructed group
+    pub fn is_empty(&self) -> bool {
+        self.symmetries.is_empty()
+    }
+
+    /// returns true if the operation is an element of the group
+    pub fn contains(&self, op: &SuperspaceOperation) -> bool {
+        self.symmetries.contains(&op.reduce(Bounds3::splat(1)))
+    }
+
+    /// returns a list of all operations in the group
+    pub fn get_operations(&self) -> &[SuperspaceOperation] {
+        &self.symmetries
+    }
+
+    /// returns the basic space group, the symmetry of the average structure in external space
+    pub fn basic_space_group(&self) -> IsometryGroup {
+        IsometryGroup::from_generators(self.symmetries.iter().map(|op| op.external()).collect())
+    }
+
+    /// returns the operations combined with the external translations within the bounds
+    fn iter_with_bounds(&self, bounds: Bounds3) -> impl Iterator<Item = SuperspaceOperation> + '_ {
+        let dimension = self.dimension;
+        let translations: Vec<SuperspaceOperation> = (0..bounds.volume())
+            .map(move |k| {
+                let mut translation = vec![Frac::from(0); dimension];
+                translation[0] = (k / (bounds.y() * bounds.z())).into();
+                translation[1] = (k / bounds.z() % bounds.y()).into();
+                translation[2] = (k % bounds.z()).into();
+                SuperspaceOperation::from_translation(translation)
+            })
+            .collect();
+        self.symmetries.iter().flat_map(move |op| {
+            translations
+                .iter()
+                .map(move |translation| (translation * op).reduce(bounds))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    /// returns the orbit of the position within the bounds.
+    /// returns Err if the position doesn't have one coordinate per dimension
+    pub fn orbit(
+        &self,
+        position: &SuperspaceVector,
+        bounds: Bounds3,
+    ) -> Result<Vec<SuperspaceVector>> {
+        self.check_dimension(position)?;
+        Ok(orbits::orbit(self, position, bounds))
+    }
+
+    /// returns the operations mapping the position onto itself modulo unit translations.
+    /// returns Err if the position doesn't have one coordinate per dimension
+    pub fn site_symmetry(&self, position: &SuperspaceVector) -> Result<Vec<SuperspaceOperation>> {
+        self.check_dimension(position)?;
+        let position = position.reduce(Bounds3::splat(1));
+        Ok(self
+            .symmetries
+            .iter()
+            .filter(|&op| (op * &position).reduce(Bounds3::splat(1)) == position)
+            .cloned()
+            .collect())
+    }
+
+    fn check_dimension(&self, position: &SuperspaceVector) -> Result<()> {
+        if position.dimension() != self.dimension {
+            return Err(SuperspaceError::Dimension {
+                expected: self.dimension,
+                found: position.dimension(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl Display for SuperspaceGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for op in &self.symmetries {
+            writeln!(f, "{};", op)?;
+        }
+        Ok(())
+    }
+}
+
+impl Action for SuperspaceGroup {
+    type Position = SuperspaceVector;
+    type Operation = SuperspaceOperation;
+
+    fn operations(&self, bounds: Bounds3) -> Box<dyn Iterator<Item = SuperspaceOperation> + '_> {
+        Box::new(self.iter_with_bounds(bounds))
+    }
+
+    fn reduce(&self, position: &SuperspaceVector, bounds: Bounds3) -> SuperspaceVector {
+        position.reduce(bounds)
+    }
+
+    fn apply(
+        &self,
+        op: &SuperspaceOperation,
+        position: &SuperspaceVector,
+        bounds: Bounds3,
+    ) -> SuperspaceVector {
+        (op * position).reduce(bounds)
+    }
+
+    fn same(&self, a: &SuperspaceVector, b: &SuperspaceVector, _bounds: Bounds3) -> bool {
+        a == b
+    }
+}
+
+/// a pair of symmetry related positions of superspace, the pair vector contains the difference
+/// of the phases of the modulations at the two positions
+pub struct SuperspacePairExpansion {
+    origin_position: SuperspaceVector,
+    origin_multiplicity: usize,
+    vec: SuperspaceVector,
+    expansion: Vec<SuperspaceVector>,
+    is_ab_pair: bool,
+}
+
+impl SuperspacePairExpansion {
+    /// calculates the pair expansion like PairExpansion::from_positions
+    /// the origin site is given by its orbit in the unit cell starting with the origin position
+    fn from_positions(
+        origin_site: &[SuperspaceVector],
+        end_position: &SuperspaceVector,
+        is_ab_pair: bool,
+        group: &SuperspaceGroup,
+        bounds: Bounds3,
+    ) -> Self {
+        let origin_position = &origin_site[0];
+        let expansion = orbits::pair_expansion(group, origin_position, end_position, bounds);
+        let vec = origin_position
+            .0
+            .iter()
+            .zip(end_position.0.iter())
+            .map(|(&origin, &end)| end - origin)
+            .collect();
+        Self {
+            origin_position: origin_position.clone(),
+            origin_multiplicity: origin_site.len(),
+            vec: SuperspaceVector(vec).reduce(bounds),
+            expansion,
+            is_ab_pair,
+        }
+    }
+
+    /// returns the representative origin position of the pair
+    pub fn origin_position(&self) -> &SuperspaceVector {
+        &self.origin_position
+    }
+
+    /// returns the representative pair vector, the external part reduced into the bounds and
+    /// the internal part modulo 1
+    pub fn vector(&self) -> &SuperspaceVector {
+        &self.vec
+    }
+
+    /// return how many ordered pairs of this type can be formed from positions within a unitcell
+    pub fn multiplicity(&self) -> usize {
+        self.origin_multiplicity * self.expansion.len() * if self.is_ab_pair { 2 } else { 1 }
+    }
+}
+
+/// constructs all pairs from the superspace positions like calculate_pairs, positions in the
+/// orbit of an earlier position are skipped.
+/// returns Err if a position doesn't have one coordinate per dimension of the group
+pub fn calculate_superspace_pairs(
+    group: &SuperspaceGroup,
+    positions: &[SuperspaceVector],
+    bounds: Bounds3,
+    construct_ab_pairs: bool,
+) -> Result<Vec<SuperspacePairExpansion>> {
+    for position in positions {
+        group.check_dimension(position)?;
+    }
+    let sites = orbits::sites(group, positions);
+    let end_orbits: Vec<Vec<SuperspaceVector>> = sites
+        .iter()
+        .map(|site| orbits::orbit(group, &site[0], bounds))
+        .collect();
+    Ok(orbits::representative_pairs(
+        &end_orbits,
+        construct_ab_pairs,
+        |i, j, end_position| {
+            SuperspacePairExpansion::from_positions(&sites[i], end_position, i != j, group, bounds)
+        },
+        |expansion, end_position| expansion.expansion.contains(end_position),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn group(string: &str) -> Result<SuperspaceGroup> {
+        let parsed = MyParser::parse(Rule::superspace_list, string)?
+            .next()
+            .unwrap();
+        SuperspaceGroup::from_superspace_list(parsed.into_inner())
+    }
+
+    fn position(coordinates: [Frac; 4]) -> SuperspaceVector {
+        SuperspaceVector::new(coordinates.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn groups() {
+        let ssg = group("-x1,-x2,x3+1/2,-x4;\n-x1,-x2,-x3,-x4;").unwrap();
+        assert_eq!(ssg.dimension(), 4);
+        assert_eq!(ssg.len(), 4);
+        assert_eq!(ssg.basic_space_group().len(), 4);
+        let reparsed = group(&ssg.to_string()).unwrap();
+        assert!(ssg.get_operations().iter().all(|op| reparsed.contains(op)));
+        for op in ssg.get_operations() {
+            assert!(ssg.contains(&(op * &op.invert())));
+            assert_eq!(
+                (op * &op.invert()).reduce(Bounds3::splat(1)),
+                SuperspaceOperation::identity(4)
+            );
+        }
+        // the internal coordinate must not enter the external ones
+        assert!(group("x1,x2,x3+x4,x4;").is_err());
+        assert!(group("x1,x2,x5,x4;").is_err());
+        // a coordinate may appear only once in a coefficient
+        assert!(group("x1-x1,x2,x3,x4;").is_err());
+        assert!(group("x1+x1,x2,x3,x4;").is_err());
+        // a shear of the phase has infinite order
+        assert!(group("x1,x2,x3,x3+x4;").is_err());
+        assert!(group("x1,x2,x3,-x3-x4;").is_ok());
+        // two operations of order 2 whose product is a shear generate an infinite group
+        assert!(group("x1,x2,x3,-x4;\nx1,x2,x3,x3-x4;").is_err());
+    }
+
+    #[test]
+    fn orbits_and_pairs() {
+        let ssg = group("-x1,-x2,x3+1/2,-x4;\n-x1,-x2,-x3,-x4;").unwrap();
+        let bounds: Bounds3 = [1, 1, 2].into();
+        let zero = Frac::from(0);
+        // the phase separates the positions which coincide in the average structure
+        let general = position([zero, zero, zero, Frac::new(1, 4)]);
+        assert_eq!(ssg.orbit(&general, bounds).unwrap().len(), 4 * 2);
+        assert_eq!(ssg.site_symmetry(&general).unwrap().len(), 1);
+        let special = position([zero, zero, zero, Frac::new(1, 2)]);
+        assert_eq!(ssg.orbit(&special, bounds).unwrap().len(), 2 * 2);
+        assert_eq!(ssg.site_symmetry(&special).unwrap().len(), 2);
+        // every origin forms a pair with every position of the orbit in the supercell
+        let pairs = calculate_superspace_pairs(&ssg, &[general], bounds, false).unwrap();
+        let total: usize = pairs
+            .iter()
+            .map(SuperspacePairExpansion::multiplicity)
+            .sum();
+        assert_eq!(total, 4 * 4 * 2);
+        assert!(pairs
+            .iter()
+            .any(|pair| pair.vector().internal() == [Frac::new(1, 2)]));
+    }
+}
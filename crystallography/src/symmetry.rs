@@ -14,12 +14,19 @@ use pest::iterators::{Pair as ParserPair, Pairs};
 use pest::Parser;
 use thiserror::Error;
 
-use crate::{affine_space::Bounds3, copy_mul_impl, Affine3, Mat3, MyParser, Pos3, Rule, Vec3};
+use crate::{
+    affine_space::{Affine, Bounds, Bounds3, Matrix, Position, Vector},
+    copy_mul_impl,
+    dimension::check_components,
+    group_theory::close,
+    symmetry_elements::{seitz_matrix, seitz_setting, SeitzSetting},
+    Affine3, Frac, Mat3, MyParser, Pos3, RecVec3, Rule, Vec3,
+};
 
 #[derive(Error, Debug)]
 enum InvalidOpError {
     #[error("{0} doesnt have determinant +/- 1")]
-    IsometryGroup(Affine3),
+    IsometryGroup(String),
     #[error("{0} doesnt have determinant +/- 1")]
     PointGroup(Mat3),
 }
@@ -31,7 +38,7 @@ pub struct PointGroupElement(Mat3);
 impl PointGroupElement {
     /// constructor returns Err if the determinant is not +/- 1
     pub fn new(mat: Mat3) -> Result<Self> {
-        if !(mat.determinant().abs() == 1.into()) {
+        if mat.determinant().abs() != 1.into() {
             return Err(InvalidOpError::PointGroup(mat).into());
         }
         Ok(Self(mat))
@@ -45,11 +52,32 @@ impl PointGroupElement {
                 .expect("point group elements are always inverible"),
         )
     }
+
+    /// returns the identity
+    pub fn identity() -> Self {
+        Self(Mat3::identity())
+    }
+
+    /// returns the inversion -x,-y,-z
+    pub fn inversion() -> Self {
+        Self(Frac::new(-1, 1) * Mat3::identity())
+    }
+
+    /// returns the matrix of the element
+    pub fn mat(&self) -> Mat3 {
+        self.0
+    }
 }
 
-impl Into<Matrix3<f32>> for PointGroupElement {
-    fn into(self) -> Matrix3<f32> {
-        self.0.into()
+impl Display for PointGroupElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<PointGroupElement> for Matrix3<f32> {
+    fn from(val: PointGroupElement) -> Self {
+        val.0.into()
     }
 }
 
@@ -83,6 +111,18 @@ impl Mul<Pos3> for PointGroupElement {
 
 copy_mul_impl!(PointGroupElement, Pos3);
 
+/// the action on reciprocal vectors is given by the transpose of the inverse matrix.
+/// this keeps the scalar product invariant: (g h) . (g r) = h . r
+impl Mul<RecVec3> for PointGroupElement {
+    type Output = RecVec3;
+
+    fn mul(self, rhs: RecVec3) -> Self::Output {
+        self.invert().0.row_mul(rhs)
+    }
+}
+
+copy_mul_impl!(PointGroupElement, RecVec3);
+
 /// a struct representing a pointgroup
 pub struct PointGroup {
     symmetries: Vec<PointGroupElement>,
@@ -93,30 +133,7 @@ impl PointGroup {
     /// this function tries to produce closure under multiplication
     /// panics if closure cannot be reached within 10_000 moves
     pub fn from_generators(generators: Vec<PointGroupElement>) -> Self {
-        let mut symmetries: Vec<PointGroupElement> = Vec::new();
-        for op in generators {
-            if !symmetries.contains(&op) {
-                symmetries.push(op)
-            }
-        }
-        let mut counter = 0;
-        let mut added_new = true;
-        while added_new && counter < 10_000 {
-            counter += 1;
-            added_new = false;
-            for i in 0..symmetries.len() {
-                for j in 0..symmetries.len() {
-                    let op = symmetries[i] * symmetries[j];
-                    if !symmetries.contains(&op) {
-                        symmetries.push(op);
-                        added_new = true;
-                    }
-                }
-            }
-        }
-        if !(counter < 10_000) {
-            panic!("didn't manage to close group within 10'000 iterations");
-        }
+        let mut symmetries = close(generators, |a, b| a * b);
         if symmetries.is_empty() {
             symmetries = vec![PointGroupElement::new(Mat3::identity()).unwrap()]
         }
@@ -151,15 +168,60 @@ impl PointGroup {
                 }
             }
         }
-        return true;
+        true
     }
 }
 
 impl PointGroup {
     /// retruns an iterator over the operations
-    pub fn iter(&self) -> slice::Iter<PointGroupElement> {
+    pub fn iter(&self) -> slice::Iter<'_, PointGroupElement> {
         self.symmetries.iter()
     }
+
+    /// returns the order of the point group
+    pub fn len(&self) -> usize {
+        self.symmetries.len()
+    }
+
+    /// returns true if the group has no elements, which never happens for a constructed group
+    pub fn is_empty(&self) -> bool {
+        self.symmetries.is_empty()
+    }
+
+    /// returns true if the element is in the group
+    pub fn contains(&self, op: PointGroupElement) -> bool {
+        self.symmetries.contains(&op)
+    }
+
+    /// returns true if the group contains the inversion
+    pub fn is_centrosymmetric(&self) -> bool {
+        self.contains(PointGroupElement::inversion())
+    }
+
+    /// returns the Laue group, the group generated by the point group and the inversion
+    pub fn laue_group(&self) -> PointGroup {
+        let mut generators = self.symmetries.clone();
+        generators.push(PointGroupElement::inversion());
+        PointGroup::from_generators(generators)
+    }
+
+    /// returns all reflections symmetry equivalent to hkl under the Laue group
+    pub fn reflection_orbit(&self, hkl: RecVec3) -> Vec<RecVec3> {
+        let mut orbit = Vec::new();
+        for op in self.laue_group().iter() {
+            let new_hkl = op * hkl;
+            if !orbit.contains(&new_hkl) {
+                orbit.push(new_hkl)
+            }
+        }
+        orbit
+    }
+
+    /// returns the multiplicity of the reflection, the number of reflections equivalent to hkl
+    /// under the Laue group
+    pub fn reflection_multiplicity(&self, hkl: RecVec3) -> usize {
+        self.reflection_orbit(hkl).len()
+    }
 }
 
 impl PartialEq for PointGroup {
@@ -168,7 +230,7 @@ impl PartialEq for PointGroup {
             return false;
         }
         for op in &self.symmetries {
-            if !other.symmetries.contains(&op) {
+            if !other.symmetries.contains(op) {
                 return false;
             }
         }
@@ -188,48 +250,66 @@ impl IntoIterator for PointGroup {
 
 impl Eq for PointGroup {}
 
-/// a type representing a crystallographic symmetry operation
+/// a type representing a crystallographic symmetry operation in N dimensions
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Isometry(Affine3);
+pub struct Isometry<const N: usize = 3>(Affine<N>);
 
-impl Isometry {
+impl<const N: usize> Isometry<N> {
     /// constructor returns ok if the operation has determinant +/-1
-    pub fn new(operation: Affine3) -> Result<Self> {
-        if !(operation.mat_determinant().abs() == 1.into()) {
-            return Err(InvalidOpError::IsometryGroup(operation).into());
+    pub fn new(operation: Affine<N>) -> Result<Self> {
+        if operation.mat_determinant().abs() != 1.into() {
+            return Err(InvalidOpError::IsometryGroup(operation.to_string()).into());
         }
         Ok(Self(operation))
     }
 
     /// constructor from matrix returns Ok if the matrix has determinant +/-1
-    pub fn from_mat(mat: Mat3) -> Result<Self> {
-        Self::new(Affine3::from_mat(mat))
+    pub fn from_mat(mat: Matrix<N>) -> Result<Self> {
+        Self::new(Affine::from_mat(mat))
     }
 
     /// constructor from translation
-    pub fn from_translation(translation: Vec3) -> Self {
-        Self(Affine3::from_translation(translation))
+    pub fn from_translation(translation: Vector<N>) -> Self {
+        Self(Affine::from_translation(translation))
     }
 
-    /// creates the symmetry element from a parsed pair
-    pub(crate) fn from_parser(pair: ParserPair<Rule>) -> Result<Self> {
-        debug_assert!(pair.as_rule() == Rule::affine || pair.as_rule() == Rule::vector);
-        Ok(Self::new(Affine3::from_parser(pair))?)
+    /// reads every operation of the list without closing them into a group
+    /// Seitz symbols are read in the hexagonal setting if any of them requires it
+    pub(crate) fn from_affine_list(pairs: Pairs<Rule>) -> Vec<Result<Self>> {
+        let setting = seitz_setting(pairs.clone().filter_map(|pair| seitz_symbol_str(&pair)));
+        pairs
+            .map(|pair| Isometry::from_parser(pair, setting))
+            .collect()
     }
 
-    /// removes the translation from the element and returns the associated point group element
-    pub fn reduce_to_point_group_element(&self) -> PointGroupElement {
-        PointGroupElement(self.0.mat())
+    /// creates the symmetry element from a parsed pair, Seitz symbols are read in the setting.
+    /// returns Err if the operation doesn't act on exactly N coordinates
+    pub(crate) fn from_parser(pair: ParserPair<Rule>, setting: SeitzSetting) -> Result<Self> {
+        if pair.as_rule() != Rule::seitz {
+            check_components::<N>(&pair)?;
+            return Self::new(Affine::from_parser(pair));
+        }
+        let mut inner = pair.into_inner();
+        let symbol = inner.next().expect("enforced by grammar");
+        let translation = inner.next().expect("enforced by grammar");
+        let mat = seitz_matrix(symbol.as_str(), setting)?;
+        let translation = match translation.as_rule() {
+            Rule::vector => Vec3::from_parser_vector(translation),
+            _ => Vec3::zero(),
+        };
+        Isometry::new(Affine3::new(mat, translation))?.restrict()
     }
-}
 
-impl Into<nalgebra::Affine3<f32>> for Isometry {
-    fn into(self) -> nalgebra::Affine3<f32> {
-        self.0.into()
+    /// returns the matrix part of the operation
+    pub fn mat(&self) -> Matrix<N> {
+        self.0.mat()
+    }
+
+    /// returns the translation part of the operation
+    pub fn translation(&self) -> Vector<N> {
+        self.0.translation()
     }
-}
 
-impl Isometry {
     /// returns the inverse of the operation
     pub fn invert(&self) -> Self {
         Self(
@@ -240,13 +320,81 @@ impl Isometry {
     }
 }
 
-impl Display for Isometry {
+impl Isometry {
+    /// returns the operation written in the notation
+    pub fn display_as(&self, notation: Notation) -> IsometryDisplay<'_> {
+        IsometryDisplay {
+            isometry: self,
+            notation,
+        }
+    }
+
+    /// removes the translation from the element and returns the associated point group element
+    pub fn reduce_to_point_group_element(&self) -> PointGroupElement {
+        PointGroupElement(self.0.mat())
+    }
+}
+
+impl From<Isometry> for nalgebra::Affine3<f32> {
+    fn from(val: Isometry) -> Self {
+        val.0.into()
+    }
+}
+
+impl<const N: usize> Display for Isometry<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        if f.alternate() {
+            write!(f, "{:#}", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+/// the notations in which isometries can be written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notation {
+    /// coordinate triplets such as -x,-y,z+1/2
+    #[default]
+    Triplet,
+    /// Seitz symbols such as {2_001|0,0,1/2}
+    Seitz,
+    /// 4x4 augmented matrices
+    Matrix,
+}
+
+/// helper to write an isometry in a chosen notation, created by Isometry::display_as
+pub struct IsometryDisplay<'a> {
+    isometry: &'a Isometry,
+    notation: Notation,
+}
+
+impl Display for IsometryDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.notation {
+            Notation::Triplet => write!(f, "{}", self.isometry.0),
+            Notation::Matrix => write!(f, "{:#}", self.isometry.0),
+            // all isometries in groups are crystallographic, others fall back to triplets
+            Notation::Seitz => match self.isometry.seitz_symbol() {
+                Ok(symbol) => write!(f, "{}", symbol),
+                Err(_) => write!(f, "{}", self.isometry.0),
+            },
+        }
+    }
+}
+
+/// returns the Seitz symbol of the pair if it is an operation in Seitz notation
+fn seitz_symbol_str<'a>(pair: &ParserPair<'a, Rule>) -> Option<&'a str> {
+    if pair.as_rule() != Rule::seitz {
+        return None;
     }
+    pair.clone()
+        .into_inner()
+        .next()
+        .map(|symbol| symbol.as_str())
 }
 
-impl Mul for Isometry {
+impl<const N: usize> Mul for Isometry<N> {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
@@ -254,69 +402,135 @@ impl Mul for Isometry {
     }
 }
 
-copy_mul_impl!(Isometry, Isometry);
+impl<const N: usize> Mul<&Isometry<N>> for Isometry<N> {
+    type Output = Self;
 
-impl Mul<Vec3> for Isometry {
-    type Output = Vec3;
+    fn mul(self, rhs: &Isometry<N>) -> Self::Output {
+        self * *rhs
+    }
+}
 
-    fn mul(self, rhs: Vec3) -> Self::Output {
+impl<const N: usize> Mul<Isometry<N>> for &Isometry<N> {
+    type Output = Isometry<N>;
+
+    fn mul(self, rhs: Isometry<N>) -> Self::Output {
+        *self * rhs
+    }
+}
+
+impl<const N: usize> Mul<&Isometry<N>> for &Isometry<N> {
+    type Output = Isometry<N>;
+
+    fn mul(self, rhs: &Isometry<N>) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Vector<N>> for Isometry<N> {
+    type Output = Vector<N>;
+
+    fn mul(self, rhs: Vector<N>) -> Self::Output {
         self.0 * rhs
     }
 }
 
-copy_mul_impl!(Isometry, Vec3);
+impl<const N: usize> Mul<&Vector<N>> for Isometry<N> {
+    type Output = Vector<N>;
 
-impl Mul<Pos3> for Isometry {
-    type Output = Pos3;
+    fn mul(self, rhs: &Vector<N>) -> Self::Output {
+        self * *rhs
+    }
+}
 
-    fn mul(self, rhs: Pos3) -> Self::Output {
+impl<const N: usize> Mul<Vector<N>> for &Isometry<N> {
+    type Output = Vector<N>;
+
+    fn mul(self, rhs: Vector<N>) -> Self::Output {
+        *self * rhs
+    }
+}
+
+impl<const N: usize> Mul<&Vector<N>> for &Isometry<N> {
+    type Output = Vector<N>;
+
+    fn mul(self, rhs: &Vector<N>) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Position<N>> for Isometry<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: Position<N>) -> Self::Output {
         self.0 * rhs
     }
 }
 
-copy_mul_impl!(Isometry, Pos3);
+impl<const N: usize> Mul<&Position<N>> for Isometry<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: &Position<N>) -> Self::Output {
+        self * *rhs
+    }
+}
+
+impl<const N: usize> Mul<Position<N>> for &Isometry<N> {
+    type Output = Position<N>;
 
-impl Rem<Bounds3> for Isometry {
-    type Output = Isometry;
+    fn mul(self, rhs: Position<N>) -> Self::Output {
+        *self * rhs
+    }
+}
+
+impl<const N: usize> Mul<&Position<N>> for &Isometry<N> {
+    type Output = Position<N>;
+
+    fn mul(self, rhs: &Position<N>) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+impl<const N: usize> Rem<Bounds<N>> for Isometry<N> {
+    type Output = Self;
 
-    fn rem(mut self, rhs: Bounds3) -> Self::Output {
+    fn rem(mut self, rhs: Bounds<N>) -> Self::Output {
         self.0 %= rhs;
         self
     }
 }
 
-impl Rem<&Bounds3> for Isometry {
-    type Output = Isometry;
+impl<const N: usize> Rem<&Bounds<N>> for Isometry<N> {
+    type Output = Self;
 
-    fn rem(self, rhs: &Bounds3) -> Self::Output {
+    fn rem(self, rhs: &Bounds<N>) -> Self::Output {
         self % *rhs
     }
 }
 
-impl Rem<Bounds3> for &Isometry {
-    type Output = Isometry;
+impl<const N: usize> Rem<Bounds<N>> for &Isometry<N> {
+    type Output = Isometry<N>;
 
-    fn rem(self, rhs: Bounds3) -> Self::Output {
+    fn rem(self, rhs: Bounds<N>) -> Self::Output {
         *self % rhs
     }
 }
 
-impl Rem<&Bounds3> for &Isometry {
-    type Output = Isometry;
+impl<const N: usize> Rem<&Bounds<N>> for &Isometry<N> {
+    type Output = Isometry<N>;
 
-    fn rem(self, rhs: &Bounds3) -> Self::Output {
+    fn rem(self, rhs: &Bounds<N>) -> Self::Output {
         *self % *rhs
     }
 }
 
-impl RemAssign<Bounds3> for Isometry {
-    fn rem_assign(&mut self, rhs: Bounds3) {
+impl<const N: usize> RemAssign<Bounds<N>> for Isometry<N> {
+    fn rem_assign(&mut self, rhs: Bounds<N>) {
         *self = *self % rhs
     }
 }
 
-impl RemAssign<&Bounds3> for Isometry {
-    fn rem_assign(&mut self, rhs: &Bounds3) {
+impl<const N: usize> RemAssign<&Bounds<N>> for Isometry<N> {
+    fn rem_assign(&mut self, rhs: &Bounds<N>) {
         *self = *self % *rhs
     }
 }
@@ -325,17 +539,17 @@ impl RemAssign<&Bounds3> for Isometry {
 /// internaly the space group is represented as the qutient group of the space group modulo the
 /// group genreated by translations along axes by the integers of the given Bounds3.
 #[derive(Debug, Clone)]
-pub struct IsometryGroup {
-    symmetries: Vec<Isometry>,
+pub struct IsometryGroup<const N: usize = 3> {
+    symmetries: Vec<Isometry<N>>,
 }
 
-impl PartialEq for IsometryGroup {
+impl<const N: usize> PartialEq for IsometryGroup<N> {
     fn eq(&self, other: &Self) -> bool {
         if !self.symmetries.len() == other.symmetries.len() {
             return false;
         }
         for op in &self.symmetries {
-            if !other.symmetries.contains(&op) {
+            if !other.symmetries.contains(op) {
                 return false;
             }
         }
@@ -343,40 +557,20 @@ impl PartialEq for IsometryGroup {
     }
 }
 
-impl Eq for IsometryGroup {}
+impl<const N: usize> Eq for IsometryGroup<N> {}
 
-impl IsometryGroup {
+impl<const N: usize> IsometryGroup<N> {
     /// this function takes a Vec of symmetries and tries to close them under multiplication.
     /// all operations are performed modulo (1, 1, 1) as defined in the affine space module
     /// panics if this cannot be done within 10_000 iterations to prevent an infinite loop.
-    pub fn from_generators(generators: Vec<Isometry>) -> Self {
-        let mut symmetries: Vec<Isometry> = Vec::new();
-        for op in generators {
-            let op = op % Bounds3::splat(1.into());
-            if !symmetries.contains(&op) {
-                symmetries.push(op)
-            }
-        }
-        let mut counter = 0;
-        let mut added_new = true;
-        while added_new && counter < 10_000 {
-            counter += 1;
-            added_new = false;
-            for i in 0..symmetries.len() {
-                for j in 0..symmetries.len() {
-                    let op = (symmetries[i] * symmetries[j]) % Bounds3::splat(1.into());
-                    if !symmetries.contains(&op) {
-                        symmetries.push(op);
-                        added_new = true;
-                    }
-                }
-            }
-        }
-        if !(counter < 10_000) {
-            panic!("didn't manage to close group within 10'000 iterations");
-        }
+    pub fn from_generators(generators: Vec<Isometry<N>>) -> Self {
+        let generators = generators
+            .into_iter()
+            .map(|op| op % Bounds::splat(1))
+            .collect();
+        let mut symmetries = close(generators, |a, b| (a * b) % Bounds::splat(1));
         if symmetries.is_empty() {
-            symmetries = vec![Isometry::new(Affine3::identity()).expect("identity is an Isometry")]
+            symmetries = vec![Isometry::new(Affine::identity()).expect("identity is an Isometry")]
         }
         Self { symmetries }
     }
@@ -384,11 +578,11 @@ impl IsometryGroup {
     /// this function takes a oplist as a string and parses it
     /// note that the parsed symmetry operations are sent through SpaceGroup::from_symmetries thus
     /// the same conditions for panicing applies
+    /// Seitz symbols are read in the hexagonal setting if any of them requires it
     pub fn from_affine_list(pairs: Pairs<Rule>) -> Result<Self> {
-        let mut symmetries = Vec::new();
-        for pair in pairs {
-            symmetries.push(Isometry::from_parser(pair)?);
-        }
+        let symmetries = Isometry::from_affine_list(pairs)
+            .into_iter()
+            .collect::<Result<_>>()?;
         Ok(Self::from_generators(symmetries))
     }
 
@@ -399,22 +593,22 @@ impl IsometryGroup {
         let parsed = MyParser::parse(Rule::affine_list, &string)?
             .next()
             .expect("never fails");
-        Self::from_affine_list(parsed.into_inner()).into()
+        Self::from_affine_list(parsed.into_inner())
     }
 
     /// returns an iterator over the symmetry operations in the given bounds
-    pub fn iter_with_bounds(&self, bounds: Bounds3) -> IsometryIter<'_, Isometry> {
+    pub fn iter_with_bounds(&self, bounds: Bounds<N>) -> IsometryIter<'_, Isometry<N>, N> {
         IsometryIter::new(&self.symmetries, bounds)
     }
 
     /// creates a group from the elements given
     /// returns None if the elements are not given modulo the bounds or if the set of elements is
     /// not closed under multiplication modulo the bounds, also dedups the elements
-    pub fn from_closed_symmetries(mut symmetries: Vec<Isometry>) -> Option<Self> {
+    pub fn from_closed_symmetries(mut symmetries: Vec<Isometry<N>>) -> Option<Self> {
         symmetries.sort();
         symmetries.dedup();
         for sym in symmetries.iter() {
-            assert_eq!(*sym, sym % Bounds3::splat(1))
+            assert_eq!(*sym, sym % Bounds::splat(1))
         }
         let this = Self { symmetries };
         if !this.check_represetation() {
@@ -423,29 +617,36 @@ impl IsometryGroup {
         Some(this)
     }
 
+    /// returns the same group with its operations in sorted order
+    pub(crate) fn sorted(&self) -> Self {
+        let mut symmetries = self.symmetries.clone();
+        symmetries.sort();
+        Self { symmetries }
+    }
+
     /// checks closure and if the elements are given modulo the bounds
     /// ignores duplicates
     fn check_represetation(&self) -> bool {
         for sym1 in &self.symmetries {
             if !self
                 .symmetries
-                .contains(&(sym1.invert() % Bounds3::splat(1)))
+                .contains(&(sym1.invert() % Bounds::splat(1)))
             {
                 return false;
             }
-            if !(sym1 % Bounds3::splat(1) == *sym1) {
+            if sym1 % Bounds::splat(1) != *sym1 {
                 return false;
             }
             for sym2 in &self.symmetries {
                 if !self
                     .symmetries
-                    .contains(&((sym1 * sym2) % Bounds3::splat(1)))
+                    .contains(&((sym1 * sym2) % Bounds::splat(1)))
                 {
                     return false;
                 }
                 if !self
                     .symmetries
-                    .contains(&((sym2 * sym1) % Bounds3::splat(1)))
+                    .contains(&((sym2 * sym1) % Bounds::splat(1)))
                 {
                     return false;
                 }
@@ -453,7 +654,9 @@ impl IsometryGroup {
         }
         true
     }
+}
 
+impl IsometryGroup {
     /// removes the translation part of the each element of the spacae group and returns a point
     /// group
     pub fn reduce_to_point_group(&self) -> PointGroup {
@@ -465,12 +668,18 @@ impl IsometryGroup {
         PointGroup::from_closed_symmetries(symmetries)
             .expect("a space group can always be reduced to a point group")
     }
+
+    /// returns the Laue group of the point group of the space group, which describes the
+    /// symmetry of the diffraction pattern
+    pub fn laue_group(&self) -> PointGroup {
+        self.reduce_to_point_group().laue_group()
+    }
 }
 
-impl IsometryGroup {
+impl<const N: usize> IsometryGroup<N> {
     /// returns true if the operation is an element of the space group
-    pub fn contains(&self, op: Isometry) -> bool {
-        let op = op % Bounds3::splat(1.into());
+    pub fn contains(&self, op: Isometry<N>) -> bool {
+        let op = op % Bounds::splat(1);
         self.symmetries.contains(&op)
     }
 
@@ -479,13 +688,297 @@ impl IsometryGroup {
         self.symmetries.len()
     }
 
+    /// returns true if the group has no elements, which never happens for a constructed group
+    pub fn is_empty(&self) -> bool {
+        self.symmetries.is_empty()
+    }
+
     /// returns a list of all operations in the group
-    pub fn get_operations(&self) -> &[Isometry] {
+    pub fn get_operations(&self) -> &[Isometry<N>] {
         &self.symmetries
     }
 }
 
+impl IsometryGroup {
+    /// returns the operations written in the notation, in the format read by from_file
+    pub fn display_as(&self, notation: Notation) -> IsometryGroupDisplay<'_> {
+        IsometryGroupDisplay {
+            group: self,
+            notation,
+            generators_only: false,
+        }
+    }
+}
+
 impl Display for IsometryGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_as(Notation::Triplet))
+    }
+}
+
+/// helper to write an isometry group in a chosen notation, created by IsometryGroup::display_as
+pub struct IsometryGroupDisplay<'a> {
+    group: &'a IsometryGroup,
+    notation: Notation,
+    generators_only: bool,
+}
+
+impl IsometryGroupDisplay<'_> {
+    /// only writes a minimal set of generators instead of all operations
+    pub fn generators_only(mut self) -> Self {
+        self.generators_only = true;
+        self
+    }
+}
+
+impl Display for IsometryGroupDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let generators;
+        let operations = if self.generators_only {
+            generators = self.group.generators();
+            &generators
+        } else {
+            &self.group.symmetries
+        };
+        for op in operations {
+            writeln!(f, "{};", op.display_as(self.notation))?;
+        }
+        Ok(())
+    }
+}
+
+/// a symmetry operation of a magnetic space group, an isometry which may be combined with time
+/// reversal. primed operations, the ones with time reversal, reverse magnetic moments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MagneticIsometry {
+    isometry: Isometry,
+    time_reversal: bool,
+}
+
+impl MagneticIsometry {
+    /// constructor
+    pub fn new(isometry: Isometry, time_reversal: bool) -> Self {
+        Self {
+            isometry,
+            time_reversal,
+        }
+    }
+
+    /// returns the time reversal 1', the identity combined with time reversal
+    pub fn time_reversal() -> Self {
+        Self::new(Isometry::from_translation(Vec3::zero()), true)
+    }
+
+    /// creates the operation from a parsed magnetic operation such as -x,-y,z,-1
+    /// returns Err if the spatial part is not an isometry
+    pub(crate) fn from_parser(pair: ParserPair<Rule>) -> Result<Self> {
+        let mut inner = pair.into_inner();
+        let operation = inner.next().expect("enforced by grammar");
+        let time_reversal = inner.next().expect("enforced by grammar");
+        Ok(Self::new(
+            Isometry::new(Affine3::from_parser(operation))?,
+            time_reversal.as_str() == "-1",
+        ))
+    }
+
+    /// returns the spatial part of the operation
+    pub fn isometry(&self) -> Isometry {
+        self.isometry
+    }
+
+    /// returns true if the operation is combined with time reversal
+    pub fn is_primed(&self) -> bool {
+        self.time_reversal
+    }
+
+    /// returns the inverse of the operation
+    pub fn invert(&self) -> Self {
+        Self::new(self.isometry.invert(), self.time_reversal)
+    }
+
+    /// returns the matrix acting on magnetic moments, which are axial vectors and therefore
+    /// invariant under inversion. time reversal changes the sign of the moments
+    pub fn moment_mat(&self) -> Mat3 {
+        let sign = if self.time_reversal { -1 } else { 1 };
+        self.mat() * (self.mat().determinant() * Frac::from(sign))
+    }
+
+    /// returns the image of the magnetic moment given in the basis of the lattice
+    pub fn transform_moment(&self, moment: Vec3) -> Vec3 {
+        self.moment_mat() * moment
+    }
+
+    /// returns the matrix part of the operation
+    pub fn mat(&self) -> Mat3 {
+        self.isometry.mat()
+    }
+
+    /// returns the translation part of the operation
+    pub fn translation(&self) -> Vec3 {
+        self.isometry.translation()
+    }
+}
+
+impl Display for MagneticIsometry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time_reversal = if self.time_reversal { "-1" } else { "+1" };
+        write!(f, "{},{}", self.isometry, time_reversal)
+    }
+}
+
+impl Mul for MagneticIsometry {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.isometry * rhs.isometry,
+            self.time_reversal != rhs.time_reversal,
+        )
+    }
+}
+
+copy_mul_impl!(MagneticIsometry, MagneticIsometry);
+
+impl Mul<MagneticIsometry> for Isometry {
+    type Output = MagneticIsometry;
+
+    fn mul(self, rhs: MagneticIsometry) -> Self::Output {
+        MagneticIsometry::new(self * rhs.isometry, rhs.time_reversal)
+    }
+}
+
+copy_mul_impl!(Isometry, MagneticIsometry);
+
+impl Mul<Pos3> for MagneticIsometry {
+    type Output = Pos3;
+
+    fn mul(self, rhs: Pos3) -> Self::Output {
+        self.isometry * rhs
+    }
+}
+
+copy_mul_impl!(MagneticIsometry, Pos3);
+
+impl Rem<Bounds3> for MagneticIsometry {
+    type Output = MagneticIsometry;
+
+    fn rem(self, rhs: Bounds3) -> Self::Output {
+        Self::new(self.isometry % rhs, self.time_reversal)
+    }
+}
+
+/// a magnetic space group, the parallel of IsometryGroup for operations with time reversal.
+/// like IsometryGroup it is represented modulo unit translations, so a primed translation such
+/// as 1/2,0,0,-1 stays distinct from the translation 1/2,0,0
+#[derive(Debug, Clone)]
+pub struct MagneticGroup {
+    symmetries: Vec<MagneticIsometry>,
+}
+
+impl MagneticGroup {
+    /// closes the operations under multiplication modulo (1, 1, 1) like
+    /// IsometryGroup::from_generators.
+    /// panics if this cannot be done within 10_000 iterations to prevent an infinite loop.
+    pub fn from_generators(generators: Vec<MagneticIsometry>) -> Self {
+        let generators = generators
+            .into_iter()
+            .map(|op| op % Bounds3::splat(1))
+            .collect();
+        let mut symmetries = close(generators, |a, b| (a * b) % Bounds3::splat(1));
+        if symmetries.is_empty() {
+            symmetries = vec![Self::identity()]
+        }
+        Self { symmetries }
+    }
+
+    fn identity() -> MagneticIsometry {
+        MagneticIsometry::new(Isometry::from_translation(Vec3::zero()), false)
+    }
+
+    /// creates the group generated by the parsed magnetic operations
+    pub fn from_magnetic_list(pairs: Pairs<Rule>) -> Result<Self> {
+        let symmetries = pairs
+            .map(MagneticIsometry::from_parser)
+            .collect::<Result<_>>()?;
+        Ok(Self::from_generators(symmetries))
+    }
+
+    /// reads a list of magnetic operations such as x,y,z,-1; from a file and closes it
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let string = read_to_string(path)?;
+        let parsed = MyParser::parse(Rule::magnetic_list, &string)?
+            .next()
+            .expect("never fails");
+        Self::from_magnetic_list(parsed.into_inner())
+    }
+
+    /// returns an iterator over the symmetry operations in the given bounds
+    pub fn iter_with_bounds(&self, bounds: Bounds3) -> IsometryIter<'_, MagneticIsometry> {
+        IsometryIter::new(&self.symmetries, bounds)
+    }
+
+    /// returns true if the operation is an element of the group
+    pub fn contains(&self, op: MagneticIsometry) -> bool {
+        self.symmetries.contains(&(op % Bounds3::splat(1)))
+    }
+
+    /// returns the cardinality of the quotient group with unit translations
+    pub fn len(&self) -> usize {
+        self.symmetries.len()
+    }
+
+    /// returns true if the group has no elements, which never happens for a constructed group
+    pub fn is_empty(&self) -> bool {
+        self.symmetries.is_empty()
+    }
+
+    /// returns a list of all operations in the group
+    pub fn get_operations(&self) -> &[MagneticIsometry] {
+        &self.symmetries
+    }
+
+    /// returns the family group, the space group obtained by ignoring time reversal
+    pub fn family_group(&self) -> IsometryGroup {
+        IsometryGroup::from_closed_symmetries(
+            self.symmetries.iter().map(|op| op.isometry).collect(),
+        )
+        .expect("the spatial parts of a group form a group")
+    }
+
+    /// returns true if the group contains the time reversal 1' itself, such groups describe
+    /// paramagnetic structures and forbid any magnetic moment
+    pub fn is_grey(&self) -> bool {
+        self.contains(MagneticIsometry::time_reversal())
+    }
+
+    /// returns the magnetic site symmetry group, the operations of the group mapping the
+    /// position onto itself modulo unit translations, given modulo unit translations
+    pub fn site_symmetry(&self, position: Pos3) -> MagneticGroup {
+        let position = position % Bounds3::splat(1);
+        let symmetries = self
+            .iter_with_bounds(Bounds3::splat(1))
+            .filter(|&op| (op * position) % Bounds3::splat(1) == position)
+            .collect();
+        Self { symmetries }
+    }
+
+    /// returns a basis of the magnetic moments at the position which are invariant under the
+    /// magnetic site symmetry
+    pub fn allowed_moments(&self, position: Pos3) -> Vec<Vec3> {
+        let site_symmetry = self.site_symmetry(position);
+        let projector = site_symmetry
+            .symmetries
+            .iter()
+            .fold(Mat3::from([0; 9]), |acc, op| acc + op.moment_mat())
+            * Frac::new(1, site_symmetry.len() as i32);
+        let (_, directions) = (projector - Mat3::identity())
+            .solve(Vec3::zero())
+            .expect("the zero vector is always a solution");
+        directions.into_iter().map(|(_, vec)| vec).collect()
+    }
+}
+
+impl Display for MagneticGroup {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for op in &self.symmetries {
             writeln!(f, "{};", op)?;
@@ -495,49 +988,50 @@ impl Display for IsometryGroup {
 }
 
 /// An Iterator over the elements of a bounded space group
-pub struct IsometryIter<'a, T>
+pub struct IsometryIter<'a, T, const N: usize = 3>
 where
-    Isometry: Mul<&'a T, Output = T>,
-    T: Rem<Bounds3, Output = T>,
+    Isometry<N>: Mul<&'a T, Output = T>,
+    T: Rem<Bounds<N>, Output = T>,
 {
     symmetries: std::slice::Iter<'a, T>,
     current_item: Option<&'a T>,
-    bounds: Bounds3,
-    state: [i32; 3],
+    bounds: Bounds<N>,
+    state: [i32; N],
 }
 
-impl<'a, T> IsometryIter<'a, T>
+impl<'a, T, const N: usize> IsometryIter<'a, T, N>
 where
-    Isometry: Mul<&'a T, Output = T>,
-    T: Rem<Bounds3, Output = T>,
+    Isometry<N>: Mul<&'a T, Output = T>,
+    T: Rem<Bounds<N>, Output = T>,
 {
     /// constructor
-    pub fn new(symmetries: &'a [T], bounds: Bounds3) -> Self {
+    pub fn new(symmetries: &'a [T], bounds: Bounds<N>) -> Self {
         Self {
             symmetries: symmetries.iter(),
             current_item: None,
             bounds,
-            state: [0; 3],
+            state: [0; N],
         }
     }
 
+    /// counts the translation up like an odometer, the first coordinate turning slowest
     fn increase_state(&mut self) {
-        self.state[2] += 1;
-        if self.state[2] >= self.bounds.z() {
-            self.state[1] += 1;
-            self.state[2] = 0;
-        }
-        if self.state[1] >= self.bounds.y() {
-            self.state[0] += 1;
-            self.state[1] = 0;
+        let lengths = self.bounds.lengths();
+        for i in (1..N).rev() {
+            self.state[i] += 1;
+            if self.state[i] < lengths[i] {
+                return;
+            }
+            self.state[i] = 0;
         }
+        self.state[0] += 1;
     }
 }
 
-impl<'a, T> Iterator for IsometryIter<'a, T>
+impl<'a, T, const N: usize> Iterator for IsometryIter<'a, T, N>
 where
-    Isometry: Mul<&'a T, Output = T>,
-    T: Rem<Bounds3, Output = T>,
+    Isometry<N>: Mul<&'a T, Output = T>,
+    T: Rem<Bounds<N>, Output = T>,
 {
     type Item = T;
 
@@ -548,18 +1042,18 @@ where
             self.current_item = self.symmetries.next();
         }
         if let Some(item) = self.current_item {
-            if self.state[0] < self.bounds.x() {
+            if self.state[0] < self.bounds.lengths()[0] {
                 let next =
                     Some((Isometry::from_translation(self.state.into()) * item) % self.bounds);
                 self.increase_state();
-                return next;
+                next
             } else {
-                self.state = [0; 3];
+                self.state = [0; N];
                 self.current_item = None;
-                return self.next();
+                self.next()
             }
         } else {
-            return None;
+            None
         }
     }
 }
@@ -570,11 +1064,35 @@ mod test {
 
     macro_rules! test_sg {
         ($path:literal, $expected_number:literal) => {
-            let sg = IsometryGroup::from_file($path).unwrap();
+            let sg: IsometryGroup = IsometryGroup::from_file($path).unwrap();
             assert_eq!($expected_number, sg.len())
         };
     }
 
+    #[test]
+    pub fn laue_test() {
+        let sg = IsometryGroup::from_file("../files/space_groups/P2_1").unwrap();
+        assert_eq!(sg.laue_group().len(), 4);
+        let sg = IsometryGroup::from_file("../files/space_groups/Fm-3m").unwrap();
+        let laue = sg.laue_group();
+        assert_eq!(laue.len(), 48);
+        assert_eq!(laue.reflection_multiplicity([1, 0, 0].into()), 6);
+        assert_eq!(laue.reflection_multiplicity([1, 1, 1].into()), 8);
+        assert_eq!(laue.reflection_multiplicity([1, 2, 3].into()), 48);
+    }
+
+    #[test]
+    pub fn reciprocal_action_test() {
+        let sg = IsometryGroup::from_file("../files/space_groups/P6_3|mmc").unwrap();
+        let hkl: RecVec3 = [1, 2, 3].into();
+        let vec: Vec3 = [Frac::new(1, 3), 2.into(), Frac::new(-1, 2)].into();
+        for op in sg.laue_group().iter() {
+            assert_eq!(hkl.dot(&vec), (op * hkl).dot(&(op * vec)));
+        }
+        assert_eq!(sg.laue_group().reflection_multiplicity([1, 0, 0].into()), 6);
+        assert_eq!(sg.laue_group().reflection_multiplicity([0, 0, 1].into()), 2);
+    }
+
     #[test]
     pub fn cardinality_test() {
         test_sg!("../files/space_groups/P-1", 2);
@@ -601,4 +1119,69 @@ mod test {
         let ops: Vec<_> = sg.iter_with_bounds([3, 2, 1].into()).collect();
         assert_eq!(ops.len(), 3 * 2 * 2);
     }
+
+    #[test]
+    pub fn alternate_display_test() {
+        let sg = IsometryGroup::from_file("../files/space_groups/P2_1").unwrap();
+        for op in sg.get_operations() {
+            assert_eq!(
+                format!("{:#}", op),
+                op.display_as(Notation::Matrix).to_string()
+            );
+            assert_eq!(
+                format!("{}", op),
+                op.display_as(Notation::Triplet).to_string()
+            );
+        }
+    }
+
+    fn magnetic_group(string: &str) -> MagneticGroup {
+        let parsed = MyParser::parse(Rule::magnetic_list, string)
+            .unwrap()
+            .next()
+            .unwrap();
+        MagneticGroup::from_magnetic_list(parsed.into_inner()).unwrap()
+    }
+
+    #[test]
+    pub fn magnetic_group_test() {
+        // an antiferromagnetic chain with the primed translation a/2
+        let group = magnetic_group("x+1/2,y,z,-1;\n-x,-y,-z,+1;");
+        assert_eq!(group.len(), 4);
+        assert!(!group.is_grey());
+        let primed = MagneticIsometry::new(
+            Isometry::from_translation(Vec3::from([1, 0, 0]) / Frac::from(2)),
+            true,
+        );
+        assert!(group.contains(primed));
+        assert!(!group.contains(MagneticIsometry::new(primed.isometry(), false)));
+        assert_eq!(group.family_group().len(), 4);
+        let reparsed = magnetic_group(&group.to_string());
+        assert!(group
+            .get_operations()
+            .iter()
+            .all(|&op| reparsed.contains(op)));
+        // the grey group forbids any moment
+        let grey = magnetic_group("x,y,z,-1;");
+        assert!(grey.is_grey());
+        assert!(grey.allowed_moments(Pos3::origin()).is_empty());
+    }
+
+    #[test]
+    pub fn allowed_moments_test() {
+        // in P2'/m' the moments at the origin lie perpendicular to the primed 2-fold axis
+        let group = magnetic_group("-x,y,-z,-1;\n-x,-y,-z,1;");
+        assert_eq!(group.site_symmetry(Pos3::origin()).len(), 4);
+        let moments = group.allowed_moments(Pos3::origin());
+        assert_eq!(moments.len(), 2);
+        assert!(moments
+            .iter()
+            .all(|moment| moment.dot(&Vec3::from([0, 1, 0])) == 0.into()));
+        // in P2/m the moments at the origin are parallel to the 2-fold axis
+        let group = magnetic_group("-x,y,-z,+1;\n-x,-y,-z,+1;");
+        assert_eq!(
+            group.allowed_moments(Pos3::origin()),
+            vec![Vec3::from([0, 1, 0])]
+        );
+    }
 }
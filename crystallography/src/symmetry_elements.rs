@@ -0,0 +1,671 @@
+//! # Symmetry Elements
+//! This module classifies isometries geometrically as described in the International Tables for
+//! Crystallography Vol. A.
+//!
+//! The type of the linear part W is determined from its determinant and trace.
+//! The translation part w is split into the intrinsic translation w_g, the screw or glide part,
+//! and the location part w_l = w - w_g. For an operation whose linear part has order k
+//!
+//! w_g = 1/k (W^0 + W^1 + ... + W^(k-1)) w
+//!
+//! The location of the symmetry element is the set of points fixed by (W, w_l).
+//!
+//! The Seitz symbol {R|w} of an operation names the linear part R by its type, sense and the
+//! direction of its axis or plane normal, such as 2_001, 3+_111 or m_1-10.
+//! The matrix of a Seitz symbol depends on the metric of the lattice. Symbols are read in an
+//! orthogonal setting unless they only exist in the hexagonal setting, in which case the
+//! hexagonal setting is used for the whole list of operations.
+
+use std::{fmt::Display, sync::OnceLock};
+
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::{affine_space::primitive_integer, symmetry::Isometry, Frac, Mat3, Pos3, Vec3};
+
+#[derive(Error, Debug)]
+enum ClassificationError {
+    #[error("{0} is not a crystallographic operation")]
+    NotCrystallographic(Isometry),
+    #[error("{0} is not a valid Seitz symbol")]
+    UnknownSeitzSymbol(String),
+}
+
+/// the lattice metric in which the linear part of a Seitz symbol is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SeitzSetting {
+    /// orthogonal axes as in cubic, tetragonal, orthorhombic and monoclinic groups
+    Orthogonal,
+    /// a = b with an angle of 120 degrees between them as in hexagonal and trigonal groups
+    Hexagonal,
+}
+
+impl SeitzSetting {
+    /// returns the metric tensor of the setting, up to the lengths of a and c
+    fn metric(self) -> Mat3 {
+        match self {
+            Self::Orthogonal => Mat3::identity(),
+            Self::Hexagonal => [2, -1, 0, -1, 2, 0, 0, 0, 1].into(),
+        }
+    }
+}
+
+/// a linear part which can be written as a Seitz symbol
+struct SeitzEntry {
+    setting: SeitzSetting,
+    rotation_type: i32,
+    positive_sense: Option<bool>,
+    axis: Option<[i32; 3]>,
+    mat: Mat3,
+}
+
+/// returns the linear parts of all point group operations of the orthogonal and the hexagonal
+/// setting
+fn seitz_table() -> &'static [SeitzEntry] {
+    static TABLE: OnceLock<Vec<SeitzEntry>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = Vec::new();
+        for code in 0..3_i32.pow(9) {
+            let entries: [i32; 9] = std::array::from_fn(|i| code / 3_i32.pow(i as u32) % 3 - 1);
+            let mat: Mat3 = entries.into();
+            for setting in [SeitzSetting::Orthogonal, SeitzSetting::Hexagonal] {
+                let metric = setting.metric();
+                if mat.transpose() * metric * mat != metric {
+                    continue;
+                }
+                let element = Isometry::from_mat(mat)
+                    .and_then(|op| op.symmetry_element())
+                    .expect("matrices preserving a metric are crystallographic");
+                table.push(SeitzEntry {
+                    setting,
+                    rotation_type: element.rotation_type,
+                    positive_sense: element.positive_sense,
+                    axis: element.axis,
+                    mat,
+                });
+            }
+        }
+        table
+    })
+}
+
+/// the linear part of a parsed Seitz symbol
+struct SeitzLinearPart {
+    rotation_type: i32,
+    positive_sense: Option<bool>,
+    axis: Option<[i32; 3]>,
+}
+
+impl SeitzLinearPart {
+    /// reads a symbol such as -4^+_001, returns None if it is malformed
+    fn from_str(symbol: &str) -> Option<Self> {
+        let (rotation, direction) = match symbol.split_once('_') {
+            Some((rotation, direction)) => (rotation, Some(direction)),
+            None => (symbol, None),
+        };
+        let rotation = rotation.replace('^', "");
+        let (rotation, positive_sense) = if let Some(r) = rotation.strip_suffix('+') {
+            (r, Some(true))
+        } else if rotation.len() > 1 && rotation.ends_with('-') {
+            (&rotation[..rotation.len() - 1], Some(false))
+        } else {
+            (rotation.as_str(), None)
+        };
+        let rotation_type = match rotation {
+            "m" => -2,
+            r => r.parse().ok()?,
+        };
+        let axis = match direction {
+            Some(direction) => {
+                let mut components = Vec::new();
+                let mut negative = false;
+                for c in direction.chars() {
+                    if c == '-' {
+                        negative = true;
+                    } else {
+                        let digit = c.to_digit(10)? as i32;
+                        components.push(if negative { -digit } else { digit });
+                        negative = false;
+                    }
+                }
+                let components: [i32; 3] = components.try_into().ok()?;
+                Some(primitive_integer(components.map(Frac::from)))
+            }
+            None => None,
+        };
+        Some(Self {
+            rotation_type,
+            positive_sense,
+            axis,
+        })
+    }
+
+    /// returns true if the entry of the table has this linear part. the axis may be given in
+    /// the opposite direction, which reverses the sense of rotation
+    fn matches(&self, entry: &SeitzEntry) -> bool {
+        if self.rotation_type != entry.rotation_type {
+            return false;
+        }
+        match (self.axis, entry.axis) {
+            (None, None) => true,
+            (Some(axis), Some(other)) => {
+                (axis == other && self.positive_sense == entry.positive_sense)
+                    || (axis == other.map(|val| -val)
+                        && self.positive_sense == entry.positive_sense.map(|sense| !sense))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// returns the setting a list of Seitz symbols has to be read in
+pub(crate) fn seitz_setting<'a>(symbols: impl IntoIterator<Item = &'a str>) -> SeitzSetting {
+    let needs_hexagonal = symbols.into_iter().any(|symbol| {
+        seitz_matrix(symbol, SeitzSetting::Orthogonal).is_ok_and(|mat| !mat.is_orthogonal())
+    });
+    if needs_hexagonal {
+        SeitzSetting::Hexagonal
+    } else {
+        SeitzSetting::Orthogonal
+    }
+}
+
+/// returns the matrix of the linear part of the Seitz symbol such as 3+_111.
+/// the symbol is looked up in the given setting first and in the other setting otherwise.
+/// returns Err if the symbol doesn't describe a crystallographic operation
+pub(crate) fn seitz_matrix(symbol: &str, setting: SeitzSetting) -> Result<Mat3> {
+    let unknown = || ClassificationError::UnknownSeitzSymbol(symbol.to_string());
+    let linear_part = SeitzLinearPart::from_str(symbol).ok_or_else(unknown)?;
+    let table = seitz_table();
+    table
+        .iter()
+        .filter(|entry| entry.setting == setting)
+        .chain(table.iter().filter(|entry| entry.setting != setting))
+        .find(|entry| linear_part.matches(entry))
+        .map(|entry| entry.mat)
+        .ok_or_else(|| unknown().into())
+}
+
+/// the geometric kind of a symmetry operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    /// the identity 1
+    Identity,
+    /// a pure lattice or centering translation
+    Translation,
+    /// the inversion -1 through a point
+    Inversion,
+    /// an n-fold rotation around an axis
+    Rotation,
+    /// an n-fold rotation followed by a translation along the axis
+    Screw,
+    /// a rotoinversion -3, -4 or -6
+    Rotoinversion,
+    /// a reflection through a mirror plane
+    Reflection,
+    /// a reflection followed by a translation parallel to the plane
+    Glide,
+}
+
+/// an affine subspace of positions, given by a point and directions.
+/// each direction is parametrized by one of the coordinates x, y or z
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AffineSubspace {
+    point: Pos3,
+    directions: Vec<(usize, Vec3)>,
+}
+
+impl AffineSubspace {
+    /// returns a point in the subspace
+    pub fn point(&self) -> Pos3 {
+        self.point
+    }
+
+    /// returns the directions spanning the subspace
+    pub fn directions(&self) -> Vec<Vec3> {
+        self.directions.iter().map(|(_, dir)| *dir).collect()
+    }
+
+    /// returns the dimension of the subspace, 0 for a point, 1 for a line and 2 for a plane
+    pub fn dimension(&self) -> usize {
+        self.directions.len()
+    }
+}
+
+impl Display for AffineSubspace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let point = [self.point.x(), self.point.y(), self.point.z()];
+        let mut coordinates = Vec::new();
+        for (i, constant) in point.into_iter().enumerate() {
+            let mut coordinate = String::new();
+            for (param, dir) in &self.directions {
+                let coeff = [dir.x(), dir.y(), dir.z()][i];
+                if coeff != 0.into() {
+                    coordinate.push_str(&format!(
+                        "{}{}",
+                        coeff.as_signed_prefactor(),
+                        ['x', 'y', 'z'][*param]
+                    ));
+                }
+            }
+            if constant != 0.into() || coordinate.is_empty() {
+                coordinate.push_str(&constant.as_string_signed());
+            }
+            coordinates.push(
+                coordinate
+                    .strip_prefix('+')
+                    .unwrap_or(&coordinate)
+                    .to_string(),
+            );
+        }
+        write!(f, "{}", coordinates.join(","))
+    }
+}
+
+/// the geometric description of a symmetry operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymmetryElement {
+    kind: OperationKind,
+    rotation_type: i32,
+    positive_sense: Option<bool>,
+    axis: Option<[i32; 3]>,
+    intrinsic_translation: Vec3,
+    location: Option<AffineSubspace>,
+    inversion_point: Option<Pos3>,
+}
+
+impl SymmetryElement {
+    /// returns the kind of the operation
+    pub fn kind(&self) -> OperationKind {
+        self.kind
+    }
+
+    /// returns the type of the linear part: 1, 2, 3, 4, 6 for rotations and -1, -2, -3, -4, -6
+    /// for rotoinversions, where -2 is a reflection
+    pub fn rotation_type(&self) -> i32 {
+        self.rotation_type
+    }
+
+    /// returns the sense of rotation for axes of order 3 and higher.
+    /// true if the rotation is counter clockwise looking down the axis
+    pub fn positive_sense(&self) -> Option<bool> {
+        self.positive_sense
+    }
+
+    /// returns the direction of the rotation axis or the normal of the mirror plane as the
+    /// shortest integer vector
+    pub fn axis(&self) -> Option<[i32; 3]> {
+        self.axis
+    }
+
+    /// returns the screw or glide part of the translation
+    pub fn intrinsic_translation(&self) -> Vec3 {
+        self.intrinsic_translation
+    }
+
+    /// returns the location of the axis or plane
+    pub fn location(&self) -> Option<&AffineSubspace> {
+        self.location.as_ref()
+    }
+
+    /// returns the inversion point of inversions and rotoinversions
+    pub fn inversion_point(&self) -> Option<Pos3> {
+        self.inversion_point
+    }
+
+    /// returns the symbol of a glide plane
+    fn glide_symbol(&self) -> String {
+        let g = self.intrinsic_translation;
+        let components = [g.x(), g.y(), g.z()];
+        let nonzero: Vec<usize> = (0..3).filter(|&i| components[i] != 0.into()).collect();
+        let all_equal_to = |val: Frac| nonzero.iter().all(|&i| components[i].abs() == val);
+        if nonzero.len() == 1 && all_equal_to(Frac::new(1, 2)) {
+            ['a', 'b', 'c'][nonzero[0]].to_string()
+        } else if all_equal_to(Frac::new(1, 2)) {
+            format!("n{}", format_vector(g))
+        } else if all_equal_to(Frac::new(1, 4)) {
+            format!("d{}", format_vector(g))
+        } else {
+            format!("g{}", format_vector(g))
+        }
+    }
+
+    /// returns the linear part of the Seitz symbol such as 2_001, -4+_001 or m_1-10
+    pub fn seitz_linear_symbol(&self) -> String {
+        let direction = self
+            .axis
+            .map(|axis| axis.map(|val| val.to_string()).concat())
+            .unwrap_or_default();
+        match self.rotation_type {
+            1 | -1 => self.rotation_type.to_string(),
+            -2 => format!("m_{}", direction),
+            n => format!("{}{}_{}", n, self.sense_symbol(), direction),
+        }
+    }
+
+    fn sense_symbol(&self) -> &str {
+        match self.positive_sense {
+            Some(true) => "+",
+            Some(false) => "-",
+            None => "",
+        }
+    }
+}
+
+/// formats a vector in the parenthesized form used in the symbols
+fn format_vector(vec: Vec3) -> String {
+    format!("({},{},{})", vec.x(), vec.y(), vec.z())
+}
+
+/// formats a position as a coordinate triplet
+fn format_point(pos: Pos3) -> String {
+    format!("{},{},{}", pos.x(), pos.y(), pos.z())
+}
+
+impl Display for SymmetryElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let location = self
+            .location
+            .as_ref()
+            .map(|l| l.to_string())
+            .unwrap_or_default();
+        let inversion_point = self.inversion_point.map(format_point).unwrap_or_default();
+        match self.kind {
+            OperationKind::Identity => write!(f, "1"),
+            OperationKind::Translation => {
+                write!(f, "t{}", format_vector(self.intrinsic_translation))
+            }
+            OperationKind::Inversion => write!(f, "-1 {}", inversion_point),
+            OperationKind::Rotation => write!(
+                f,
+                "{}{} {}",
+                self.rotation_type,
+                self.sense_symbol(),
+                location
+            ),
+            OperationKind::Screw => write!(
+                f,
+                "{}{}{} {}",
+                self.rotation_type,
+                self.sense_symbol(),
+                format_vector(self.intrinsic_translation),
+                location
+            ),
+            OperationKind::Rotoinversion => write!(
+                f,
+                "{}{} {}; {}",
+                self.rotation_type,
+                self.sense_symbol(),
+                location,
+                inversion_point
+            ),
+            OperationKind::Reflection => write!(f, "m {}", location),
+            OperationKind::Glide => write!(f, "{} {}", self.glide_symbol(), location),
+        }
+    }
+}
+
+/// returns the type of the linear part from its determinant and trace: 1, 2, 3, 4, 6 for
+/// rotations and -1, -2, -3, -4, -6 for rotoinversions.
+/// returns None if no crystallographic operation has this determinant and trace, which doesn't
+/// guarantee that the matrix is crystallographic
+pub(crate) fn rotation_type(mat: Mat3) -> Option<i32> {
+    let (det, trace) = (mat.determinant(), mat.trace());
+    if det.get_denominator() != 1 || trace.get_denominator() != 1 {
+        return None;
+    }
+    match (det.get_numerator(), trace.get_numerator()) {
+        (1, 3) => Some(1),
+        (1, -1) => Some(2),
+        (1, 0) => Some(3),
+        (1, 1) => Some(4),
+        (1, 2) => Some(6),
+        (-1, -3) => Some(-1),
+        (-1, 1) => Some(-2),
+        (-1, 0) => Some(-3),
+        (-1, -1) => Some(-4),
+        (-1, -2) => Some(-6),
+        _ => None,
+    }
+}
+
+/// returns the set of points fixed by the affine transformation (mat, translation)
+fn fixed_points(mat: Mat3, translation: Vec3) -> Option<AffineSubspace> {
+    let (point, directions) = (mat - Mat3::identity()).solve(-translation)?;
+    Some(AffineSubspace {
+        point: point.into(),
+        directions,
+    })
+}
+
+/// returns the direction of the one dimensional eigenspace of the eigenvalue
+fn eigen_direction(mat: Mat3, eigenvalue: Frac) -> Option<[i32; 3]> {
+    let (_, directions) = (mat - eigenvalue * Mat3::identity()).solve(Vec3::zero())?;
+    if directions.len() != 1 {
+        return None;
+    }
+    let dir = directions[0].1;
+    Some(primitive_integer([dir.x(), dir.y(), dir.z()]))
+}
+
+/// returns the sense of rotation of the proper rotation around the axis
+fn rotation_sense(proper: Mat3, axis: [i32; 3]) -> bool {
+    let axis: Vec3 = axis.into();
+    for basis in [[1, 0, 0], [0, 1, 0], [0, 0, 1]] {
+        let vec: Vec3 = basis.into();
+        let det = Mat3::from_columns(axis, vec, proper * vec).determinant();
+        if det != 0.into() {
+            return det > 0.into();
+        }
+    }
+    unreachable!("the axis can not be parallel to all basis vectors")
+}
+
+impl Isometry {
+    /// returns the Seitz symbol of the operation such as {2_001|0,0,1/2}.
+    /// returns Err if the matrix is not the matrix of a crystallographic operation
+    pub fn seitz_symbol(&self) -> Result<String> {
+        Ok(format!(
+            "{{{}|{}}}",
+            self.symmetry_element()?.seitz_linear_symbol(),
+            format_point(Pos3::origin() + self.translation())
+        ))
+    }
+
+    /// classifies the operation geometrically and calculates the location of its symmetry
+    /// element.
+    /// returns Err if the matrix is not the matrix of a crystallographic operation
+    pub fn symmetry_element(&self) -> Result<SymmetryElement> {
+        let mat = self.mat();
+        let translation = self.translation();
+        let det = mat.determinant();
+        let Some(rotation_type) = rotation_type(mat) else {
+            return Err(ClassificationError::NotCrystallographic(*self).into());
+        };
+        let order = match rotation_type {
+            -1 | -2 => 2,
+            -3 => 6,
+            n => n.abs(),
+        };
+        let mut power = Mat3::identity();
+        let mut sum = Vec3::zero();
+        for _ in 0..order {
+            sum += power * translation;
+            power = mat * power;
+        }
+        if power != Mat3::identity() || mat.trace().get_denominator() != 1 {
+            return Err(ClassificationError::NotCrystallographic(*self).into());
+        }
+        let intrinsic_translation = if det == 1.into() || rotation_type == -2 {
+            sum / Frac::from(order)
+        } else {
+            Vec3::zero()
+        };
+        let location_translation = translation - intrinsic_translation;
+        let has_intrinsic = intrinsic_translation != Vec3::zero();
+
+        let kind = match rotation_type {
+            1 if has_intrinsic => OperationKind::Translation,
+            1 => OperationKind::Identity,
+            -1 => OperationKind::Inversion,
+            -2 if has_intrinsic => OperationKind::Glide,
+            -2 => OperationKind::Reflection,
+            -3 | -4 | -6 => OperationKind::Rotoinversion,
+            _ if has_intrinsic => OperationKind::Screw,
+            _ => OperationKind::Rotation,
+        };
+
+        let axis = match kind {
+            OperationKind::Identity | OperationKind::Translation | OperationKind::Inversion => None,
+            OperationKind::Rotation | OperationKind::Screw => eigen_direction(mat, 1.into()),
+            _ => eigen_direction(mat, (-1).into()),
+        };
+        let positive_sense = match (axis, rotation_type.abs() >= 3) {
+            (Some(axis), true) => Some(rotation_sense(det * mat, axis)),
+            _ => None,
+        };
+        let inversion_point = match kind {
+            OperationKind::Inversion | OperationKind::Rotoinversion => {
+                fixed_points(mat, translation).map(|set| set.point)
+            }
+            _ => None,
+        };
+        let location = match kind {
+            OperationKind::Identity | OperationKind::Translation | OperationKind::Inversion => None,
+            // the axis of a rotoinversion is the axis of its square, which is a rotation
+            OperationKind::Rotoinversion => {
+                fixed_points(mat * mat, mat * translation + translation)
+            }
+            _ => fixed_points(mat, location_translation),
+        };
+        Ok(SymmetryElement {
+            kind,
+            rotation_type,
+            positive_sense,
+            axis,
+            intrinsic_translation,
+            location,
+            inversion_point,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        symmetry::{IsometryGroup, Notation},
+        MyParser, Rule,
+    };
+    use pest::Parser;
+
+    fn symbol(op: &str) -> String {
+        let pair = MyParser::parse(Rule::affine, op).unwrap().next().unwrap();
+        Isometry::from_parser(pair, SeitzSetting::Orthogonal)
+            .unwrap()
+            .symmetry_element()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn symbol_test() {
+        assert_eq!(symbol("x,y,z"), "1");
+        assert_eq!(symbol("x+1/2,y+1/2,z"), "t(1/2,1/2,0)");
+        assert_eq!(symbol("-x,-y,-z"), "-1 0,0,0");
+        assert_eq!(symbol("-x+1/2,-y,-z"), "-1 1/4,0,0");
+        assert_eq!(symbol("-x,-y,z+1/2"), "2(0,0,1/2) 0,0,z");
+        assert_eq!(symbol("-x+1/2,-y,z+1/2"), "2(0,0,1/2) 1/4,0,z");
+        assert_eq!(symbol("-x,y,-z+1/2"), "2 0,y,1/4");
+        assert_eq!(symbol("y,x,-z"), "2 x,x,0");
+        assert_eq!(symbol("x,-y,z+1/2"), "c x,0,z");
+        assert_eq!(symbol("x+1/2,-y+1/2,z"), "a x,1/4,z");
+        assert_eq!(symbol("x+1/2,y+1/2,-z"), "n(1/2,1/2,0) x,y,0");
+        assert_eq!(symbol("-x,y,z"), "m 0,y,z");
+        assert_eq!(symbol("-y,x,z"), "4+ 0,0,z");
+        assert_eq!(symbol("y,-x,z+3/4"), "4-(0,0,3/4) 0,0,z");
+        assert_eq!(symbol("y,-x,-z"), "-4+ 0,0,z; 0,0,0");
+        assert_eq!(symbol("z,x,y"), "3+ x,x,x");
+        assert_eq!(symbol("-z,-x,-y"), "-3+ x,x,x; 0,0,0");
+        assert_eq!(symbol("x-y,x,z"), "6+ 0,0,z");
+        assert_eq!(symbol("-y,x-y,z"), "3+ 0,0,z");
+    }
+
+    #[test]
+    fn classify_groups() {
+        let sg = IsometryGroup::from_file("../files/space_groups/Fm-3m").unwrap();
+        for op in sg.get_operations() {
+            let element = op.symmetry_element().unwrap();
+            match element.kind() {
+                OperationKind::Inversion | OperationKind::Rotoinversion => {
+                    let pos = element.inversion_point().unwrap();
+                    assert_eq!(*op * pos, pos);
+                }
+                OperationKind::Identity | OperationKind::Translation => (),
+                _ => {
+                    // without the intrinsic translation the operation fixes its location
+                    let pos = element.location().unwrap().point();
+                    assert_eq!(*op * pos, pos + element.intrinsic_translation());
+                }
+            }
+        }
+        let sg = IsometryGroup::from_file("../files/space_groups/P6_3|mmc").unwrap();
+        for op in sg.get_operations() {
+            op.symmetry_element().unwrap();
+        }
+    }
+
+    fn seitz(op: &str) -> String {
+        let pair = MyParser::parse(Rule::affine, op).unwrap().next().unwrap();
+        Isometry::from_parser(pair, SeitzSetting::Orthogonal)
+            .unwrap()
+            .seitz_symbol()
+            .unwrap()
+    }
+
+    #[test]
+    fn seitz_test() {
+        assert_eq!(seitz("x,y,z"), "{1|0,0,0}");
+        assert_eq!(seitz("-x,-y,z+1/2"), "{2_001|0,0,1/2}");
+        assert_eq!(seitz("y,x,-z"), "{2_110|0,0,0}");
+        assert_eq!(seitz("x,-y,z+1/2"), "{m_010|0,0,1/2}");
+        assert_eq!(seitz("y,-x,-z"), "{-4+_001|0,0,0}");
+        assert_eq!(seitz("z,x,y"), "{3+_111|0,0,0}");
+        assert_eq!(seitz("x-y,x,z"), "{6+_001|0,0,0}");
+        assert_eq!(
+            seitz_matrix("2_100", SeitzSetting::Hexagonal).unwrap(),
+            [1, -1, 0, 0, -1, 0, 0, 0, -1].into()
+        );
+        assert_eq!(
+            seitz_matrix("2_100", SeitzSetting::Orthogonal).unwrap(),
+            [1, 0, 0, 0, -1, 0, 0, 0, -1].into()
+        );
+        // the reversed axis reverses the sense
+        assert_eq!(
+            seitz_matrix("4^-_00-1", SeitzSetting::Orthogonal).unwrap(),
+            seitz_matrix("4+_001", SeitzSetting::Orthogonal).unwrap()
+        );
+        assert!(seitz_matrix("5_001", SeitzSetting::Orthogonal).is_err());
+        assert!(seitz_matrix("4+_111", SeitzSetting::Orthogonal).is_err());
+    }
+
+    #[test]
+    fn seitz_round_trip() {
+        for name in ["Fm-3m", "P6_3|mmc", "R-3m", "Cmcm"] {
+            let sg = IsometryGroup::from_file(format!("../files/space_groups/{}", name)).unwrap();
+            let string = sg.display_as(Notation::Seitz).to_string();
+            let parsed = MyParser::parse(Rule::affine_list, &string)
+                .unwrap()
+                .next()
+                .unwrap();
+            let read = IsometryGroup::from_affine_list(parsed.into_inner()).unwrap();
+            assert_eq!(read.get_operations(), sg.get_operations(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn non_crystallographic() {
+        let op = Isometry::from_mat([1, 1, 0, 0, 1, 0, 0, 0, 1].into()).unwrap();
+        assert!(op.symmetry_element().is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! # Setting Transformations
+//! This module transforms groups, positions and vectors to a different setting, following the
+//! conventions of the International Tables for Crystallography Vol. A.
+//!
+//! A transformation (P, p) changes the basis to (a', b', c') = (a, b, c) P and shifts the origin
+//! by p, given in the old basis. Coordinates transform as x' = P^-1 (x - p), vectors as
+//! v' = P^-1 v, reciprocal vectors as h' = h P and operations as W' = (P, p)^-1 W (P, p).
+
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::{
+    frac::lcm,
+    symmetry::{Isometry, IsometryGroup},
+    Affine3, Bounds3, Frac, Mat3, Pos3, RecVec3, Vec3,
+};
+
+#[derive(Error, Debug)]
+enum TransformationError {
+    #[error("the matrix {0} is not invertible")]
+    Singular(Mat3),
+    #[error("the new basis vector {0} is not a translation of the group")]
+    NotATranslation(Vec3),
+    #[error(
+        "the supercell {0:?} is not a diagonal supercell in the new setting, the smallest \
+         diagonal supercell containing it is {1:?}"
+    )]
+    NotDiagonal(Bounds3, Bounds3),
+}
+
+/// a change of basis P together with an origin shift p
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transformation {
+    affine: Affine3,
+    inverse: Affine3,
+}
+
+impl Transformation {
+    /// constructor returns Err if the matrix is not invertible
+    pub fn new(mat: Mat3, origin_shift: Vec3) -> Result<Self> {
+        let affine = Affine3::new(mat, origin_shift);
+        let inverse = affine.inverse().ok_or(TransformationError::Singular(mat))?;
+        Ok(Self { affine, inverse })
+    }
+
+    /// returns the transformation which leaves the setting unchanged
+    pub fn identity() -> Self {
+        Self {
+            affine: Affine3::identity(),
+            inverse: Affine3::identity(),
+        }
+    }
+
+    /// creates a transformation which only shifts the origin
+    pub fn from_origin_shift(origin_shift: Vec3) -> Self {
+        Self::new(Mat3::identity(), origin_shift).expect("the identity is invertible")
+    }
+
+    /// creates a transformation which only changes the basis
+    /// returns Err if the matrix is not invertible
+    pub fn from_basis_change(mat: Mat3) -> Result<Self> {
+        Self::new(mat, Vec3::zero())
+    }
+
+    /// returns the matrix P, whose columns are the new basis vectors in the old basis
+    pub fn mat(&self) -> Mat3 {
+        self.affine.mat()
+    }
+
+    /// returns the origin shift p in the old basis
+    pub fn origin_shift(&self) -> Vec3 {
+        self.affine.translation()
+    }
+
+    /// returns the transformation back to the old setting
+    pub fn inverse(&self) -> Self {
+        Self {
+            affine: self.inverse,
+            inverse: self.affine,
+        }
+    }
+
+    /// returns the transformation which first applies this and then the other transformation,
+    /// the other transformation is given relative to the setting after this one
+    pub fn then(&self, other: &Transformation) -> Self {
+        Self {
+            affine: self.affine * other.affine,
+            inverse: other.inverse * self.inverse,
+        }
+    }
+
+    /// returns the coordinates of the position in the new setting
+    pub fn transform_position(&self, position: Pos3) -> Pos3 {
+        self.inverse * position
+    }
+
+    /// returns the components of the vector in the new basis, used for pair vectors
+    pub fn transform_vector(&self, vec: Vec3) -> Vec3 {
+        self.inverse * vec
+    }
+
+    /// returns the indices of the reciprocal vector in the new basis
+    pub fn transform_reciprocal(&self, hkl: RecVec3) -> RecVec3 {
+        self.mat().row_mul(hkl)
+    }
+
+    /// returns the operation in the new setting, its translation is not reduced
+    pub fn transform_isometry(&self, op: Isometry) -> Isometry {
+        let affine = self.inverse * Affine3::new(op.mat(), op.translation()) * self.affine;
+        Isometry::new(affine).expect("conjugation preserves the determinant")
+    }
+
+    /// returns the group in the new setting.
+    /// the translations of the old lattice become centering translations if the new cell is
+    /// larger.
+    /// returns Err if a new basis vector is not a translation of the group
+    pub fn transform_group(&self, group: &IsometryGroup) -> Result<IsometryGroup> {
+        let mat = self.mat();
+        for basis in [[1, 0, 0], [0, 1, 0], [0, 0, 1]] {
+            let vec = mat * Vec3::from(basis);
+            let is_translation = group.get_operations().iter().any(|op| {
+                op.mat() == Mat3::identity()
+                    && (op.translation() - vec) % Bounds3::splat(1) == Vec3::zero()
+            });
+            if !is_translation {
+                return Err(TransformationError::NotATranslation(vec).into());
+            }
+        }
+        let mut generators: Vec<Isometry> = group
+            .get_operations()
+            .iter()
+            .map(|&op| self.transform_isometry(op))
+            .collect();
+        for basis in [[1, 0, 0], [0, 1, 0], [0, 0, 1]] {
+            generators.push(Isometry::from_translation(
+                self.transform_vector(basis.into()),
+            ));
+        }
+        Ok(IsometryGroup::from_generators(generators))
+    }
+
+    /// returns the supercell in the new setting.
+    /// returns None if the supercell is not spanned by multiples of the new basis vectors
+    pub fn transform_bounds(&self, bounds: Bounds3) -> Option<Bounds3> {
+        let new_bounds = self.compatible_bounds(bounds);
+        let is_diagonal = self.supercell_columns(bounds).iter().all(|col| {
+            [col.x(), col.y(), col.z()]
+                .iter()
+                .zip([new_bounds.x(), new_bounds.y(), new_bounds.z()])
+                .all(|(val, bound)| (*val / Frac::from(bound)).get_denominator() == 1)
+        });
+        is_diagonal.then_some(new_bounds)
+    }
+
+    /// returns the smallest diagonal supercell in the new setting whose lattice is contained in
+    /// the lattice of the supercell, which is the transformed supercell if it is diagonal
+    pub fn compatible_bounds(&self, bounds: Bounds3) -> Bounds3 {
+        let columns = self.supercell_columns(bounds);
+        let supercell = Mat3::from_columns(columns[0], columns[1], columns[2]);
+        let inverse = supercell.inverse().expect("transformations are invertible");
+        // the smallest multiple of each new basis vector which lies in the supercell lattice
+        let new_bounds: [i32; 3] = std::array::from_fn(|i| {
+            let mut basis = [0; 3];
+            basis[i] = 1;
+            let coefficients = inverse * Vec3::from(basis);
+            [coefficients.x(), coefficients.y(), coefficients.z()]
+                .iter()
+                .fold(1, |acc, val| lcm(acc, val.get_denominator()))
+        });
+        new_bounds.into()
+    }
+
+    /// returns the vectors spanning the supercell in the new basis
+    fn supercell_columns(&self, bounds: Bounds3) -> [Vec3; 3] {
+        let old_axes = [
+            Vec3::from([bounds.x(), 0, 0]),
+            Vec3::from([0, bounds.y(), 0]),
+            Vec3::from([0, 0, bounds.z()]),
+        ];
+        old_axes.map(|vec| self.transform_vector(vec))
+    }
+
+    /// moves a whole input, the group, the positions and the supercell, to the new setting.
+    /// the positions are reduced to the new unit cell.
+    /// returns Err if the group can't be transformed or the supercell is not diagonal in the
+    /// new setting
+    pub fn transform_input(
+        &self,
+        group: &IsometryGroup,
+        positions: &[Pos3],
+        bounds: Bounds3,
+    ) -> Result<(IsometryGroup, Vec<Pos3>, Bounds3)> {
+        let group = self.transform_group(group)?;
+        let positions = positions
+            .iter()
+            .map(|&pos| self.transform_position(pos) % Bounds3::splat(1))
+            .collect();
+        let bounds = self.transform_bounds(bounds).ok_or_else(|| {
+            TransformationError::NotDiagonal(bounds, self.compatible_bounds(bounds))
+        })?;
+        Ok((group, positions, bounds))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sorted(group: &IsometryGroup) -> Vec<Isometry> {
+        let mut ops = group.get_operations().to_vec();
+        ops.sort();
+        ops
+    }
+
+    #[test]
+    fn origin_shift() {
+        let sg = IsometryGroup::from_file("../files/space_groups/P-1").unwrap();
+        let shift = Transformation::from_origin_shift(Vec3::from([1, 0, 0]) / Frac::from(4));
+        let shifted = shift.transform_group(&sg).unwrap();
+        let inversion = shifted
+            .get_operations()
+            .iter()
+            .find(|op| op.mat() != Mat3::identity())
+            .unwrap();
+        assert_eq!(inversion.to_string(), "-x+1/2,-y,-z");
+        assert_eq!(
+            shift.transform_position(Pos3::origin()),
+            Pos3::origin() + Vec3::from([-1, 0, 0]) / Frac::from(4)
+        );
+        let back = shift.inverse().transform_group(&shifted).unwrap();
+        assert_eq!(sorted(&back), sorted(&sg));
+    }
+
+    #[test]
+    fn centered_to_primitive() {
+        let sg = IsometryGroup::from_file("../files/space_groups/C2|m").unwrap();
+        let half = Frac::new(1, 2);
+        let to_primitive = Transformation::from_basis_change(
+            [
+                half,
+                -half,
+                0.into(),
+                half,
+                half,
+                0.into(),
+                0.into(),
+                0.into(),
+                1.into(),
+            ]
+            .into(),
+        )
+        .unwrap();
+        let primitive = to_primitive.transform_group(&sg).unwrap();
+        assert_eq!(
+            primitive.get_operations().len() * 2,
+            sg.get_operations().len()
+        );
+        let back = to_primitive.inverse().transform_group(&primitive).unwrap();
+        assert_eq!(sorted(&back), sorted(&sg));
+        // vectors and reciprocal vectors keep their scalar product
+        let vec: Vec3 = [1, 1, 0].into();
+        let hkl: RecVec3 = [1, 1, 1].into();
+        assert_eq!(
+            to_primitive
+                .transform_reciprocal(hkl)
+                .dot(&to_primitive.transform_vector(vec)),
+            hkl.dot(&vec)
+        );
+        // the primitive basis vectors are not translations of P2_1
+        let sg = IsometryGroup::from_file("../files/space_groups/P2_1").unwrap();
+        assert!(to_primitive.transform_group(&sg).is_err());
+    }
+
+    #[test]
+    fn bounds() {
+        let double = Transformation::from_basis_change([2, 0, 0, 0, 1, 0, 0, 0, 1].into()).unwrap();
+        let bounds = double.transform_bounds([4, 2, 2].into()).unwrap();
+        assert_eq!([bounds.x(), bounds.y(), bounds.z()], [2, 2, 2]);
+        assert!(double.transform_bounds([3, 2, 2].into()).is_none());
+        let shift = Transformation::from_origin_shift([1, 1, 1].into());
+        let bounds = shift.transform_bounds([3, 2, 1].into()).unwrap();
+        assert_eq!([bounds.x(), bounds.y(), bounds.z()], [3, 2, 1]);
+    }
+}
@@ -0,0 +1,335 @@
+//! # Input Validation
+//! This module checks the meaning of a parsed input file before it is read into a configuration.
+//!
+//! Pest only reports syntax errors. The checks here find operations which are not isometries or
+//! not crystallographic, operations which don't generate a finite point group, positions which
+//! are given twice and bounds whose supercell is not mapped onto itself by the group. All
+//! problems are collected and reported together, each with its line, column and a snippet of
+//! the input marking the location.
+
+use std::fmt::Display;
+
+use pest::{
+    error::{Error as PestError, ErrorVariant},
+    iterators::{Pair as ParserPair, Pairs},
+    Span,
+};
+
+use crate::{
+    affine_space::{Bounds, Matrix, Position, Vector},
+    dimension::check_components,
+    snapping::{SnappedPosition, DEFAULT_TOLERANCE},
+    symmetry::{Isometry, IsometryGroup},
+    Frac, Rule,
+};
+
+/// the order of the largest crystallographic point group
+const MAX_POINT_GROUP_ORDER: usize = 48;
+
+/// the largest order of a crystallographic operation
+const MAX_OPERATION_ORDER: usize = 6;
+
+/// a problem in the input together with its location
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    line: usize,
+    column: usize,
+    message: String,
+    rendered: String,
+}
+
+impl Diagnostic {
+    fn new(span: Span, message: String) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        let variant = ErrorVariant::<Rule>::CustomError {
+            message: message.clone(),
+        };
+        Self {
+            line,
+            column,
+            message,
+            rendered: PestError::new_from_span(variant, span).to_string(),
+        }
+    }
+
+    /// returns the line of the start of the problem, starting at 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// returns the column of the start of the problem, starting at 1
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// returns the description of the problem without the location
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rendered)
+    }
+}
+
+/// all problems found in the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    /// returns the problems in the order of the sections in the input
+    pub fn as_slice(&self) -> &[Diagnostic] {
+        &self.0
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rendered: Vec<String> = self.0.iter().map(Diagnostic::to_string).collect();
+        write!(f, "{}", rendered.join("\n"))
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+/// returns true if a power of the matrix up to the order of six-fold axes is the identity
+fn has_crystallographic_order<const N: usize>(mat: Matrix<N>) -> bool {
+    let mut power = mat;
+    for _ in 0..MAX_OPERATION_ORDER {
+        if power == Matrix::identity() {
+            return true;
+        }
+        power = power * mat;
+    }
+    false
+}
+
+/// closes the linear parts under multiplication.
+/// returns None if the group is larger than any crystallographic point group
+fn close_point_group<const N: usize>(generators: &[Matrix<N>]) -> Option<Vec<Matrix<N>>> {
+    let mut elements = vec![Matrix::identity()];
+    let mut added_new = true;
+    while added_new {
+        added_new = false;
+        for i in 0..elements.len() {
+            for mat in generators {
+                let product = elements[i] * *mat;
+                if !elements.contains(&product) {
+                    if elements.len() == MAX_POINT_GROUP_ORDER
+                        || !has_crystallographic_order(product)
+                    {
+                        return None;
+                    }
+                    elements.push(product);
+                    added_new = true;
+                }
+            }
+        }
+    }
+    Some(elements)
+}
+
+/// returns true if the supercell of the bounds is mapped onto itself by the linear part
+fn preserves_bounds<const N: usize>(mat: Matrix<N>, bounds: Bounds<N>) -> bool {
+    let lengths = bounds.lengths().map(Frac::from);
+    (0..N).all(|j| {
+        let mut edge = [Frac::from(0); N];
+        edge[j] = lengths[j];
+        let image = mat * Vector::from(edge);
+        image
+            .components()
+            .iter()
+            .zip(lengths)
+            .all(|(&component, length)| (component / length).get_denominator() == 1)
+    })
+}
+
+/// collects the diagnostics of all sections
+struct Validator<const N: usize> {
+    diagnostics: Vec<Diagnostic>,
+    operations: Vec<Isometry<N>>,
+}
+
+impl<const N: usize> Validator<N> {
+    fn report(&mut self, span: Span, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic::new(span, message.into()));
+    }
+
+    /// checks the operations of a list or the group of a symbol
+    fn check_group(&mut self, pair: ParserPair<Rule>) {
+        if pair.as_rule() == Rule::group_symbol {
+            match IsometryGroup::from_symbol(pair.as_str()) {
+                Ok(group) => match group.restrict::<N>() {
+                    Ok(group) => self.operations.extend_from_slice(group.get_operations()),
+                    Err(_) => self.report(
+                        pair.as_span(),
+                        format!("the space group acts on more than {} coordinates", N),
+                    ),
+                },
+                Err(err) => self.report(pair.as_span(), err.to_string()),
+            }
+            return;
+        }
+        let ops = Isometry::from_affine_list(pair.clone().into_inner());
+        for (op_pair, op) in pair.into_inner().zip(ops) {
+            let span = op_pair.as_span();
+            match op {
+                Err(err) => self.report(span, err.to_string()),
+                Ok(op) if !has_crystallographic_order(op.mat()) => self.report(
+                    span,
+                    format!("{} is not crystallographic, it has no order up to 6", op),
+                ),
+                Ok(op) => self.operations.push(op),
+            }
+        }
+    }
+
+    /// checks that the bounds are compatible with the rotations of the group
+    fn check_bounds(&mut self, pair: ParserPair<Rule>) {
+        if let Err(err) = check_components::<N>(&pair) {
+            self.report(pair.as_span(), err.to_string());
+            return;
+        }
+        let span = pair.as_span();
+        let bounds = Bounds::from_parser_int_vector(pair);
+        if let Some(op) = self
+            .operations
+            .iter()
+            .find(|op| !preserves_bounds(op.mat(), bounds))
+        {
+            let message = format!(
+                "the supercell {} is not mapped onto itself by {}",
+                span.as_str(),
+                op
+            );
+            self.report(span, message);
+        }
+    }
+}
+
+/// checks the parsed input file of a problem in N dimensions and returns all problems found
+pub(crate) fn validate<const N: usize>(pairs: Pairs<Rule>) -> Result<(), Diagnostics> {
+    let file = pairs.peek().expect("must contain file");
+    let sections: Vec<_> = file.into_inner().collect();
+    let contents = |rule: Rule| {
+        sections
+            .iter()
+            .filter(move |section| section.as_rule() == rule)
+            .flat_map(|section| section.clone().into_inner())
+    };
+    let tolerance = contents(Rule::tolerance_section)
+        .next()
+        .map_or(DEFAULT_TOLERANCE, |pair| {
+            pair.as_str().parse().expect("enforced by grammar")
+        });
+    // floating point positions are used as given
+    let float = contents(Rule::arithmetic_section)
+        .next()
+        .is_some_and(|pair| pair.as_str() == "float");
+    let mut validator = Validator::<N> {
+        diagnostics: Vec::new(),
+        operations: Vec::new(),
+    };
+
+    for pair in contents(Rule::group_section) {
+        validator.check_group(pair);
+    }
+    let group_sections: Vec<_> = contents(Rule::group_section).collect();
+    if let (Some(first), true) = (group_sections.first(), validator.diagnostics.is_empty()) {
+        let mats: Vec<Matrix<N>> = validator.operations.iter().map(Isometry::mat).collect();
+        match close_point_group(&mats) {
+            Some(_) => (),
+            None => validator.report(
+                first.as_span(),
+                "the operations don't generate a crystallographic group",
+            ),
+        }
+    }
+
+    // positions are snapped in the group if it could be read, otherwise without symmetry
+    let group = match validator.diagnostics.is_empty() {
+        true => IsometryGroup::from_generators(validator.operations.clone()),
+        false => IsometryGroup::from_generators(Vec::new()),
+    };
+    let mut positions: Vec<(Position<N>, Span)> = Vec::new();
+    for pair in contents(Rule::positions_section) {
+        let span = pair.as_span();
+        if let Err(err) = check_components::<N>(&pair) {
+            validator.report(span, err.to_string());
+            continue;
+        }
+        let snapped = SnappedPosition::from_parser(pair, tolerance, &group);
+        let position = match float {
+            true => snapped.unsnapped(),
+            false => snapped.position(),
+        } % Bounds::splat(1);
+        match positions.iter().find(|(other, _)| *other == position) {
+            Some((_, first)) => {
+                let (line, column) = first.start_pos().line_col();
+                let message = format!(
+                    "the position {} is already given at {}:{}",
+                    position, line, column
+                );
+                validator.report(span, message);
+            }
+            None => positions.push((position, span)),
+        }
+    }
+
+    for pair in contents(Rule::bounds_section) {
+        validator.check_bounds(pair);
+    }
+
+    if validator.diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(Diagnostics(validator.diagnostics))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{MyParser, Rule};
+    use pest::Parser;
+
+    fn diagnostics(string: &str) -> Vec<Diagnostic> {
+        let pairs = MyParser::parse(Rule::file, string).unwrap();
+        match validate::<3>(pairs) {
+            Ok(()) => Vec::new(),
+            Err(diagnostics) => diagnostics.as_slice().to_vec(),
+        }
+    }
+
+    #[test]
+    fn all_problems_are_reported() {
+        let string = "Space Group:\n-y,x,z;\nx+y,x-y,z;\nPositions:\n0,0,0;\n0.5,0,0;\n1/2,1,0;\nBounds:\n2,3,1;";
+        let found = diagnostics(string);
+        let locations: Vec<_> = found.iter().map(|d| (d.line(), d.column())).collect();
+        assert_eq!(locations, [(3, 1), (7, 1), (9, 1)]);
+        assert!(found[0].message().contains("determinant"));
+        assert!(found[1].message().contains("already given at 6:1"));
+        assert!(found[1].to_string().contains("7 | 1/2,1,0;\n  | ^-----^"));
+        // the four-fold axis maps the edge along a onto one of length 2 along b
+        assert!(found[2].message().contains("not mapped onto itself"));
+        assert!(
+            diagnostics("Space Group:\n-y,x,z;\nPositions:\n0,0,0;\nBounds:\n2,2,1;").is_empty()
+        );
+    }
+
+    #[test]
+    fn infinite_groups() {
+        // a shear has infinite order
+        let found = diagnostics("Space Group:\nx+y,y,z;\nPositions:\n0,0,0;");
+        assert_eq!((found[0].line(), found[0].column()), (2, 1));
+        // a three-fold and a four-fold axis around the same axis generate a twelve-fold one
+        let found = diagnostics("Space Group:\n-y,x-y,z;\n-y,x,z;\nPositions:\n0,0,0;");
+        assert_eq!(found.len(), 1);
+        assert!(found[0].message().contains("crystallographic group"));
+        assert!("Space Group:\n-y,x-y,z;\n-y,x,z;\nPositions:\n0,0,0;"
+            .parse::<crate::config::Config>()
+            .is_err());
+    }
+}
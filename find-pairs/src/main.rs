@@ -15,10 +15,11 @@ fn print_help() {
 }
 
 fn make_output<P: AsRef<Path>>(path: P) -> anyhow::Result<String> {
-    let (group, positions, bounds, construct_ab_pairs) = crystallography::objects::from_file(path)?;
-    let expansions =
-        crystallography::objects::calculate_pairs(group, positions, bounds, construct_ab_pairs);
-    Ok(crystallography::objects::produce_output_string(&expansions))
+    let config = crystallography::objects::from_file(path)?;
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+    Ok(config.produce_output())
 }
 
 fn main() -> anyhow::Result<()> {
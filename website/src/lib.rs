@@ -16,12 +16,14 @@ pub fn get_example() -> String {
 
 #[wasm_bindgen]
 pub fn process_input(string: &str) -> String {
-    let (group, positions, bounds, construct_ab_pairs) =
-        match crystallography::objects::from_str(string) {
-            Ok(args) => args,
-            Err(err) => return format!("There was an error during parsing:\n {}", err),
-        };
-    let expansions =
-        crystallography::objects::calculate_pairs(group, positions, bounds, construct_ab_pairs);
-    convert_string_for_wasm(crystallography::objects::produce_output_string(&expansions))
+    let config = match crystallography::objects::from_str(string) {
+        Ok(config) => config,
+        Err(err) => return format!("There was an error during parsing:\n {}", err),
+    };
+    let mut output = String::new();
+    for warning in config.warnings() {
+        output += &format!("Warning: {}\n", warning);
+    }
+    output += &config.produce_output();
+    convert_string_for_wasm(output)
 }